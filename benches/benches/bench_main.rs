@@ -5,7 +5,9 @@ use criterion::criterion_main;
 
 criterion_main! {
     benchmarks::always_success::process_block,
+    benchmarks::empty_block::process_block,
     benchmarks::secp_2in2out::process_block,
     benchmarks::overall::overall,
     benchmarks::resolve::resolve,
+    benchmarks::locator::locator,
 }
@@ -0,0 +1,66 @@
+use crate::benchmarks::util::{gen_empty_block, new_always_success_chain};
+use ckb_store::{self, ChainStore};
+use ckb_verification_traits::Switch;
+use criterion::{criterion_group, BatchSize, BenchmarkId, Criterion};
+use std::sync::Arc;
+
+#[cfg(not(feature = "ci"))]
+const BLOCKS: &[usize] = &[20usize, 100];
+
+#[cfg(feature = "ci")]
+const BLOCKS: &[usize] = &[5usize];
+
+fn bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("process_block");
+
+    // benchmark processing cellbase-only blocks on main branch
+    for blocks_size in BLOCKS.iter() {
+        group.bench_with_input(
+            BenchmarkId::new("cellbase_only main_branch", blocks_size),
+            blocks_size,
+            |b, i| {
+                b.iter_batched(
+                    || {
+                        let chains = new_always_success_chain(1, 2);
+                        let (ref chain1, ref shared1) = chains.0[0];
+                        let (ref chain2, ref shared2) = chains.0[1];
+                        let mut blocks = vec![shared1
+                            .snapshot()
+                            .get_block(&shared1.genesis_hash())
+                            .unwrap()];
+                        let mut parent = blocks[0].clone();
+                        (0..*i).for_each(|_| {
+                            let block = gen_empty_block(&parent, shared2);
+                            chain2
+                                .blocking_process_block_with_switch(
+                                    Arc::new(block.clone()),
+                                    Switch::DISABLE_ALL,
+                                )
+                                .expect("process block OK");
+                            blocks.push(block.clone());
+                            parent = block;
+                        });
+                        (chain1.clone(), blocks)
+                    },
+                    |(chain, blocks)| {
+                        blocks.into_iter().skip(1).for_each(|block| {
+                            chain
+                                .blocking_process_block_with_switch(
+                                    Arc::new(block),
+                                    Switch::DISABLE_EXTENSION,
+                                )
+                                .expect("process block OK");
+                        });
+                    },
+                    BatchSize::PerIteration,
+                )
+            },
+        );
+    }
+}
+
+criterion_group!(
+    name = process_block;
+    config = Criterion::default().sample_size(10);
+    targets = bench
+);
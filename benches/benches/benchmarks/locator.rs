@@ -0,0 +1,49 @@
+use crate::benchmarks::util::{gen_empty_block, new_always_success_chain};
+use ckb_store::ChainStore;
+use ckb_sync::SyncShared;
+use ckb_verification_traits::Switch;
+use criterion::{criterion_group, BenchmarkId, Criterion};
+use std::sync::Arc;
+
+#[cfg(not(feature = "ci"))]
+const BLOCKS: &[usize] = &[1_000usize, 10_000];
+
+#[cfg(feature = "ci")]
+const BLOCKS: &[usize] = &[100usize];
+
+fn bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("locator");
+
+    // benchmark building the block locator from the main chain tip
+    for blocks_size in BLOCKS.iter() {
+        let chains = new_always_success_chain(1, 1);
+        let (ref chain, ref shared) = chains.0[0];
+        let mut parent = shared.snapshot().get_block(&shared.genesis_hash()).unwrap();
+        (0..*blocks_size).for_each(|_| {
+            let block = gen_empty_block(&parent, shared);
+            chain
+                .blocking_process_block_with_switch(Arc::new(block.clone()), Switch::DISABLE_ALL)
+                .expect("process block OK");
+            parent = block;
+        });
+
+        let (_relay_tx_sender, relay_tx_receiver) = ckb_channel::unbounded();
+        let sync_shared = SyncShared::new(shared.clone(), Default::default(), relay_tx_receiver);
+
+        group.bench_with_input(
+            BenchmarkId::new("get_locator main_chain", blocks_size),
+            blocks_size,
+            |b, _| {
+                let active_chain = sync_shared.active_chain();
+                let tip = active_chain.tip_header();
+                b.iter(|| active_chain.get_locator((tip.number(), tip.hash()).into()))
+            },
+        );
+    }
+}
+
+criterion_group!(
+    name = locator;
+    config = Criterion::default().sample_size(10);
+    targets = bench
+);
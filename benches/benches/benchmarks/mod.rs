@@ -1,4 +1,6 @@
 pub mod always_success;
+pub mod empty_block;
+pub mod locator;
 pub mod overall;
 pub mod resolve;
 pub mod secp_2in2out;
@@ -196,6 +196,32 @@ pub fn gen_always_success_block(
     block
 }
 
+pub fn gen_empty_block(p_block: &BlockView, shared: &Shared) -> BlockView {
+    let (number, timestamp) = (
+        p_block.header().number() + 1,
+        p_block.header().timestamp() + 10000,
+    );
+    let cellbase = create_always_success_cellbase(shared, &p_block.header());
+    let dao = dao_data(shared, &p_block.header(), &[cellbase.clone()]);
+
+    let epoch = shared
+        .consensus()
+        .next_epoch_ext(&p_block.header(), &shared.store().borrow_as_data_loader())
+        .unwrap()
+        .epoch();
+
+    BlockBuilder::default()
+        .transaction(cellbase)
+        .parent_hash(p_block.hash())
+        .number(number.pack())
+        .timestamp(timestamp.pack())
+        .compact_target(epoch.compact_target().pack())
+        .epoch(epoch.number_with_fraction(number).pack())
+        .nonce(random::<u128>().pack())
+        .dao(dao)
+        .build()
+}
+
 const PRIVKEY: H256 = h256!("0xb2b3324cece882bca684eaf202667bb56ed8e8c2fd4b4dc71f615ebd6d9055a5");
 const PUBKEY_HASH: H160 = h160!("0x779e5930892a0a9bf2fedfe048f685466c7d0396");
 
@@ -1,19 +1,40 @@
 //! CKB chain controller.
 #![allow(missing_docs)]
 
+use crate::utils::channel_backlog::{observe_channel, PROCESS_BLOCK_CHANNEL};
 use crate::utils::orphan_block_pool::OrphanBlockPool;
-use crate::{LonelyBlock, ProcessBlockRequest, RemoteBlock, TruncateRequest, VerifyResult};
+use crate::utils::peer_block_stats::{PeerBlockCounts, PeerBlockStats};
+use crate::utils::runtime_switch::RuntimeSwitch;
+use crate::utils::tip_block_cycles::TipBlockCycles;
+use crate::utils::verification_saturation::VerificationSaturation;
+use crate::verify::resolve_block_transactions;
+use crate::{
+    delete_unverified_block, LonelyBlock, ProcessBlockRequest, RemoteBlock, TruncateRequest,
+    VerifyResult,
+};
 use ckb_channel::Sender;
 use ckb_error::{Error, InternalErrorKind};
-use ckb_logger::{self, error};
-use ckb_store::ChainDB;
+use ckb_logger::{self, debug, error};
+use ckb_network::PeerIndex;
+use ckb_shared::{HeaderIndex, Shared, Snapshot};
+use ckb_store::{data_loader_wrapper::AsDataLoader, ChainDB, ChainStore};
 use ckb_types::{
-    core::{service::Request, BlockView},
+    core::{
+        cell::{resolve_transaction, ResolvedTransaction},
+        service::Request,
+        BlockNumber, BlockView, Cycle, TransactionView,
+    },
     packed::Byte32,
 };
+use ckb_verification::{
+    ContextualTransactionVerifier, NonContextualTransactionVerifier, TxVerifyEnv,
+};
 use ckb_verification_traits::Switch;
+use std::collections::HashSet;
 use std::sync::atomic::AtomicBool;
+use std::sync::mpsc::RecvTimeoutError;
 use std::sync::Arc;
+use std::time::Duration;
 
 /// Controller to the chain service.
 ///
@@ -26,8 +47,13 @@ pub struct ChainController {
     process_block_sender: Sender<ProcessBlockRequest>,
     truncate_sender: Sender<TruncateRequest>,
     orphan_block_broker: Arc<OrphanBlockPool>,
+    shared: Shared,
 
     is_verifying_unverified_blocks_on_startup: Arc<AtomicBool>,
+    runtime_switch: Arc<RuntimeSwitch>,
+    tip_block_cycles: Arc<TipBlockCycles>,
+    peer_block_stats: Arc<PeerBlockStats>,
+    verification_saturation: Arc<VerificationSaturation>,
 }
 
 #[cfg_attr(feature = "mock", faux::methods)]
@@ -36,13 +62,23 @@ impl ChainController {
         process_block_sender: Sender<ProcessBlockRequest>,
         truncate_sender: Sender<TruncateRequest>,
         orphan_block_broker: Arc<OrphanBlockPool>,
+        shared: Shared,
         is_verifying_unverified_blocks_on_startup: Arc<AtomicBool>,
+        runtime_switch: Arc<RuntimeSwitch>,
+        tip_block_cycles: Arc<TipBlockCycles>,
+        peer_block_stats: Arc<PeerBlockStats>,
+        verification_saturation: Arc<VerificationSaturation>,
     ) -> Self {
         ChainController {
             process_block_sender,
             truncate_sender,
             orphan_block_broker,
+            shared,
             is_verifying_unverified_blocks_on_startup,
+            runtime_switch,
+            tip_block_cycles,
+            peer_block_stats,
+            verification_saturation,
         }
     }
 
@@ -51,10 +87,25 @@ impl ChainController {
             .load(std::sync::atomic::Ordering::Acquire)
     }
 
+    /// Whether the received blocks pile up faster than they are verified, the block download
+    /// should slow down until it's cleared
+    pub fn is_verification_saturated(&self) -> bool {
+        self.verification_saturation.is_saturated()
+    }
+
     pub fn asynchronous_process_remote_block(&self, remote_block: RemoteBlock) {
+        let RemoteBlock {
+            block,
+            verify_callback,
+            peer_id,
+        } = remote_block;
+        let peer_block_stats = Arc::clone(&self.peer_block_stats);
         let lonely_block = LonelyBlock {
-            block: remote_block.block,
-            verify_callback: Some(remote_block.verify_callback),
+            block,
+            verify_callback: Some(Box::new(move |verify_result: VerifyResult| {
+                peer_block_stats.record(peer_id, &verify_result);
+                verify_callback(verify_result);
+            })),
             switch: None,
         };
         self.asynchronous_process_lonely_block(lonely_block);
@@ -64,11 +115,29 @@ impl ChainController {
         if Request::call(&self.process_block_sender, lonely_block).is_none() {
             error!("Chain service has gone")
         }
+        observe_channel(
+            PROCESS_BLOCK_CHANNEL,
+            self.process_block_sender.len(),
+            self.process_block_sender.capacity(),
+        );
     }
 
     /// MinerRpc::submit_block and `ckb import` need this blocking way to process block
     pub fn blocking_process_block(&self, block: Arc<BlockView>) -> VerifyResult {
-        self.blocking_process_block_internal(block, None)
+        self.blocking_process_block_internal(block, None, None)
+    }
+
+    /// Same as `blocking_process_block`, but gives up waiting for the verify result after
+    /// `timeout`
+    ///
+    /// The block is still processed by the chain service after the timeout, its result is
+    /// discarded
+    pub fn blocking_process_block_with_timeout(
+        &self,
+        block: Arc<BlockView>,
+        timeout: Duration,
+    ) -> VerifyResult {
+        self.blocking_process_block_internal(block, None, Some(timeout))
     }
 
     /// `IntegrationTestRpcImpl::process_block_without_verify` need this
@@ -77,21 +146,23 @@ impl ChainController {
         block: Arc<BlockView>,
         switch: Switch,
     ) -> VerifyResult {
-        self.blocking_process_block_internal(block, Some(switch))
+        self.blocking_process_block_internal(block, Some(switch), None)
     }
 
     fn blocking_process_block_internal(
         &self,
         block: Arc<BlockView>,
         switch: Option<Switch>,
+        timeout: Option<Duration>,
     ) -> VerifyResult {
         let (verify_result_tx, verify_result_rx) = ckb_channel::oneshot::channel::<VerifyResult>();
 
         let verify_callback = {
             move |result: VerifyResult| {
+                // the receiver is dropped once the caller timed out
                 if let Err(err) = verify_result_tx.send(result) {
-                    error!(
-                        "blocking send verify_result failed: {}, this shouldn't happen",
+                    debug!(
+                        "blocking send verify_result failed: {}, the caller has gone",
                         err
                     )
                 }
@@ -105,22 +176,94 @@ impl ChainController {
         };
 
         self.asynchronous_process_lonely_block(lonely_block);
-        verify_result_rx.recv().unwrap_or_else(|err| {
-            Err(InternalErrorKind::System
-                .other(format!("blocking recv verify_result failed: {}", err))
-                .into())
-        })
+        match timeout {
+            None => verify_result_rx.recv().unwrap_or_else(|err| {
+                Err(InternalErrorKind::System
+                    .other(format!("blocking recv verify_result failed: {}", err))
+                    .into())
+            }),
+            Some(timeout) => match verify_result_rx.recv_timeout(timeout) {
+                Ok(verify_result) => verify_result,
+                Err(RecvTimeoutError::Timeout) => Err(InternalErrorKind::System
+                    .other(format!("verify timed out after {:?}", timeout))
+                    .into()),
+                Err(err @ RecvTimeoutError::Disconnected) => Err(InternalErrorKind::System
+                    .other(format!("blocking recv verify_result failed: {}", err))
+                    .into()),
+            },
+        }
     }
 
     /// Truncate chain to specified target
     ///
     /// Should use for testing only
     pub fn truncate(&self, target_tip_hash: Byte32) -> Result<(), Error> {
+        let snapshot = self.shared.snapshot();
+        let target_number = snapshot
+            .get_block_number(&target_tip_hash)
+            .unwrap_or_else(|| snapshot.tip_number());
+        let truncated: HashSet<Byte32> = (target_number + 1..=snapshot.tip_number())
+            .filter_map(|number| snapshot.get_block_hash(number))
+            .collect();
+
         Request::call(&self.truncate_sender, target_tip_hash).unwrap_or_else(|| {
             Err(InternalErrorKind::System
                 .other("Chain service has gone")
                 .into())
-        })
+        })?;
+        self.remove_truncated_orphans(target_number, &truncated);
+        Ok(())
+    }
+
+    // The orphans descending from the truncated blocks would never be connected to the main
+    // chain
+    fn remove_truncated_orphans(&self, target_number: BlockNumber, truncated: &HashSet<Byte32>) {
+        if truncated.is_empty() {
+            return;
+        }
+        for leader in self.orphan_block_broker.clone_leaders() {
+            if !self.descends_from_truncated(&leader, target_number, truncated) {
+                continue;
+            }
+            for orphan in self.orphan_block_broker.remove_blocks_by_parent(&leader) {
+                let (number, hash) = (orphan.number(), orphan.hash());
+                delete_unverified_block(
+                    self.shared.store(),
+                    hash.clone(),
+                    number,
+                    orphan.parent_hash(),
+                );
+                self.shared.remove_header_view(&hash);
+                self.shared.remove_block_status(&hash);
+                orphan.execute_callback(Err(InternalErrorKind::Other
+                    .other(format!(
+                        "orphan {}-{} descends from a truncated block",
+                        number, hash
+                    ))
+                    .into()));
+            }
+        }
+    }
+
+    // Walk up the known headers from `leader` until reaching the truncation target
+    fn descends_from_truncated(
+        &self,
+        leader: &Byte32,
+        target_number: BlockNumber,
+        truncated: &HashSet<Byte32>,
+    ) -> bool {
+        let mut hash = leader.clone();
+        loop {
+            if truncated.contains(&hash) {
+                return true;
+            }
+            match self.shared.header_map().get(&hash) {
+                Some(header) if header.number() > target_number + 1 => {
+                    hash = header.parent_hash();
+                }
+                _ => return false,
+            }
+        }
     }
 
     /// `Relayer::reconstruct_block` need this
@@ -128,8 +271,86 @@ impl ChainController {
         self.orphan_block_broker.get_block(store, hash)
     }
 
+    /// Resolve the transactions of `block` against the live cells of `snapshot`, without
+    /// verifying the block
+    ///
+    /// The block is expected to extend the snapshot's tip, analytics tools need this
+    pub fn resolve_block_transactions(
+        &self,
+        snapshot: &Snapshot,
+        block: &BlockView,
+    ) -> Result<Vec<Arc<ResolvedTransaction>>, Error> {
+        resolve_block_transactions(block, snapshot, snapshot)
+    }
+
+    /// Verify `tx` as if it were submitted on top of the tip of `snapshot`, returns the
+    /// cycles consumed by its scripts
+    ///
+    /// Neither the tx-pool nor the chain is touched, wallets embedding a node need this
+    pub fn verify_transaction(
+        &self,
+        snapshot: &Arc<Snapshot>,
+        tx: TransactionView,
+    ) -> Result<Cycle, Error> {
+        let consensus = snapshot.cloned_consensus();
+        NonContextualTransactionVerifier::new(&tx, &consensus).verify()?;
+
+        let rtx = resolve_transaction(tx, &mut HashSet::new(), &**snapshot, &**snapshot)?;
+        let tx_env = TxVerifyEnv::new_submit(snapshot.tip_header());
+        ContextualTransactionVerifier::new(
+            Arc::new(rtx),
+            Arc::clone(&consensus),
+            snapshot.as_data_loader(),
+            Arc::new(tx_env),
+        )
+        .verify(consensus.max_block_cycles(), false)
+        .map(|completed| completed.cycles)
+    }
+
+    /// Reload the verification phases disabled for subsequently-verified blocks
+    ///
+    /// Mainnet refuses to disable script, non-contextual and reward verification
+    pub fn set_verification_switch(&self, switch: Switch) -> Result<(), Error> {
+        self.runtime_switch.update(switch)
+    }
+
+    /// The verification phases currently disabled at runtime
+    pub fn verification_switch(&self) -> Switch {
+        self.runtime_switch.load()
+    }
+
+    /// The tip block hash and the total cycles consumed by its verification
+    ///
+    /// Returns `None` if the tip's scripts were not verified by this process
+    pub fn tip_block_cycles(&self) -> Option<(Byte32, Cycle)> {
+        self.tip_block_cycles.load()
+    }
+
+    /// The accepted and rejected blocks sent by `peer`
+    pub fn peer_block_counts(&self, peer: PeerIndex) -> PeerBlockCounts {
+        self.peer_block_stats.get(peer)
+    }
+
+    /// The accepted and rejected blocks sent by every peer, sorted by peer index
+    pub fn all_peer_block_counts(&self) -> Vec<(PeerIndex, PeerBlockCounts)> {
+        self.peer_block_stats.all()
+    }
+
+    /// Forget the blocks sent by `peer`, called when it's disconnected, so the counters don't
+    /// pile up over the peers ever connected
+    pub fn remove_peer_block_counts(&self, peer: PeerIndex) {
+        self.peer_block_stats.remove(peer);
+    }
+
     /// `NetRpcImpl::sync_state` rpc need this
     pub fn orphan_blocks_len(&self) -> usize {
         self.orphan_block_broker.len()
     }
+
+    /// The tip of the stored blocks, which may be ahead of the verified tip
+    ///
+    /// Compare it with the snapshot's tip to see how far the verification lags behind
+    pub fn get_unverified_tip(&self) -> HeaderIndex {
+        self.shared.get_unverified_tip()
+    }
 }
@@ -2,22 +2,33 @@
 #![allow(missing_docs)]
 
 use crate::orphan_broker::OrphanBroker;
+use crate::utils::channel_backlog::{observe_channel, PROCESS_BLOCK_CHANNEL};
+use crate::utils::peer_block_stats::PeerBlockStats;
 use crate::{LonelyBlock, ProcessBlockRequest};
+use ckb_app_config::DuplicateBlockPolicy;
 use ckb_channel::{select, Receiver};
 use ckb_error::{Error, InternalErrorKind};
 use ckb_logger::{self, debug, error, info, warn};
 use ckb_shared::block_status::BlockStatus;
 use ckb_shared::shared::Shared;
 use ckb_stop_handler::new_crossbeam_exit_rx;
-use ckb_types::core::{service::Request, BlockView};
-use ckb_verification::{BlockVerifier, NonContextualBlockTxsVerifier};
+use ckb_types::core::{
+    service::{Request, VerificationPhase},
+    BlockView,
+};
+use ckb_types::packed::Byte32;
+use ckb_verification::{block_rejection, BlockVerifier, NonContextualBlockTxsVerifier};
 use ckb_verification_traits::Verifier;
+use std::sync::Arc;
 
 /// Chain background service to receive LonelyBlock and only do `non_contextual_verify`
 pub(crate) struct ChainService {
     shared: Shared,
     process_block_rx: Receiver<ProcessBlockRequest>,
     orphan_broker: OrphanBroker,
+    duplicate_block_policy: DuplicateBlockPolicy,
+    // logged every minute when `log_peer_block_stats` is enabled
+    peer_block_stats: Option<Arc<PeerBlockStats>>,
 }
 impl ChainService {
     /// Create a new ChainService instance with shared.
@@ -25,11 +36,15 @@ impl ChainService {
         shared: Shared,
         process_block_rx: Receiver<ProcessBlockRequest>,
         consume_orphan: OrphanBroker,
+        duplicate_block_policy: DuplicateBlockPolicy,
+        peer_block_stats: Option<Arc<PeerBlockStats>>,
     ) -> ChainService {
         ChainService {
             shared,
             process_block_rx,
             orphan_broker: consume_orphan,
+            duplicate_block_policy,
+            peer_block_stats,
         }
     }
 
@@ -37,13 +52,16 @@ impl ChainService {
     pub(crate) fn start_process_block(self) {
         let signal_receiver = new_crossbeam_exit_rx();
 
-        let clean_expired_orphan_timer =
-            crossbeam::channel::tick(std::time::Duration::from_secs(60));
+        // check at least as often as an orphan expires
+        let clean_expired_orphan_timer = crossbeam::channel::tick(
+            std::time::Duration::from_secs(60).min(self.orphan_broker.max_age()),
+        );
 
         loop {
             select! {
                 recv(self.process_block_rx) -> msg => match msg {
                     Ok(Request { responder, arguments: lonely_block }) => {
+                        observe_channel(PROCESS_BLOCK_CHANNEL, self.process_block_rx.len(), self.process_block_rx.capacity());
                         // asynchronous_process_block doesn't interact with tx-pool,
                         // no need to pause tx-pool's chunk_process here.
                         let _trace_now = minstant::Instant::now();
@@ -60,6 +78,8 @@ impl ChainService {
                 },
                 recv(clean_expired_orphan_timer) -> _ => {
                     self.orphan_broker.clean_expired_orphans();
+                    self.orphan_broker.evict_expired_orphans();
+                    self.log_peer_block_stats();
                 },
                 recv(signal_receiver) -> _ => {
                     info!("ChainService received exit signal, exit now");
@@ -69,6 +89,17 @@ impl ChainService {
         }
     }
 
+    fn log_peer_block_stats(&self) {
+        if let Some(peer_block_stats) = &self.peer_block_stats {
+            for (peer, counts) in peer_block_stats.all() {
+                info!(
+                    "peer {} contributed blocks, accepted: {}, rejected: {}",
+                    peer, counts.accepted, counts.rejected
+                );
+            }
+        }
+    }
+
     fn non_contextual_verify(&self, block: &BlockView) -> Result<(), Error> {
         let consensus = self.shared.consensus();
         BlockVerifier::new(consensus).verify(block).map_err(|e| {
@@ -88,6 +119,15 @@ impl ChainService {
             .map(|_| ())
     }
 
+    // A block is known if it has been verified successfully, or it has been accepted and is
+    // waiting for the contextual verification.
+    fn is_known_block(&self, block_hash: &Byte32) -> bool {
+        self.shared
+            .get_block_status(block_hash)
+            .contains(BlockStatus::BLOCK_VALID)
+            || self.orphan_broker.is_pending_verify(block_hash)
+    }
+
     // `self.non_contextual_verify` is very fast.
     fn asynchronous_process_block(&self, lonely_block: LonelyBlock) {
         let block_number = lonely_block.block().number();
@@ -114,6 +154,17 @@ impl ChainService {
             return;
         }
 
+        if self.duplicate_block_policy == DuplicateBlockPolicy::ShortCircuit
+            && self.is_known_block(&block_hash)
+        {
+            debug!(
+                "block {}-{} is already known, skip processing it again",
+                block_number, block_hash
+            );
+            lonely_block.execute_callback(Ok(false));
+            return;
+        }
+
         if lonely_block.switch().is_none()
             || matches!(lonely_block.switch(), Some(switch) if !switch.disable_non_contextual())
         {
@@ -125,6 +176,13 @@ impl ChainService {
                 );
                 self.shared
                     .insert_block_status(lonely_block.block().hash(), BlockStatus::BLOCK_INVALID);
+                self.shared
+                    .notify_controller()
+                    .notify_block_rejection(block_rejection(
+                        lonely_block.block(),
+                        VerificationPhase::NonContextual,
+                        &err,
+                    ));
                 lonely_block.execute_callback(Err(err));
                 return;
             }
@@ -6,6 +6,10 @@ use crate::init_load_unverified::InitLoadUnverified;
 use crate::orphan_broker::OrphanBroker;
 use crate::preload_unverified_blocks_channel::PreloadUnverifiedBlocksChannel;
 use crate::utils::orphan_block_pool::OrphanBlockPool;
+use crate::utils::peer_block_stats::PeerBlockStats;
+use crate::utils::runtime_switch::RuntimeSwitch;
+use crate::utils::tip_block_cycles::TipBlockCycles;
+use crate::utils::verification_saturation::VerificationSaturation;
 use crate::verify::ConsumeUnverifiedBlocks;
 use crate::{chain_controller::ChainController, LonelyBlockHash, UnverifiedBlock};
 use ckb_channel::{self as channel, SendError};
@@ -36,11 +40,18 @@ pub fn start_chain_services(builder: ChainServicesBuilder) -> ChainController {
 
     let is_pending_verify: Arc<DashSet<Byte32>> = Arc::new(DashSet::new());
 
+    let runtime_switch = Arc::new(RuntimeSwitch::new(builder.shared.consensus()));
+    let tip_block_cycles = Arc::new(TipBlockCycles::default());
+    let peer_block_stats = Arc::new(PeerBlockStats::default());
+    let verification_saturation = Arc::new(VerificationSaturation::default());
+
     let consumer_unverified_thread = thread::Builder::new()
         .name("verify_blocks".into())
         .spawn({
             let shared = builder.shared.clone();
             let is_pending_verify = Arc::clone(&is_pending_verify);
+            let runtime_switch = Arc::clone(&runtime_switch);
+            let tip_block_cycles = Arc::clone(&tip_block_cycles);
             move || {
                 let consume_unverified = ConsumeUnverifiedBlocks::new(
                     shared,
@@ -48,6 +59,8 @@ pub fn start_chain_services(builder: ChainServicesBuilder) -> ChainController {
                     truncate_block_rx,
                     builder.proposal_table,
                     is_pending_verify,
+                    runtime_switch,
+                    tip_block_cycles,
                     unverified_queue_stop_rx,
                 );
 
@@ -60,12 +73,14 @@ pub fn start_chain_services(builder: ChainServicesBuilder) -> ChainController {
         .name("preload_unverified_block".into())
         .spawn({
             let shared = builder.shared.clone();
+            let verification_saturation = Arc::clone(&verification_saturation);
             move || {
                 let preload_unverified_block = PreloadUnverifiedBlocksChannel::new(
                     shared,
                     preload_unverified_rx,
                     unverified_block_tx,
                     preload_unverified_stop_rx,
+                    verification_saturation,
                 );
                 preload_unverified_block.start()
             }
@@ -80,7 +95,12 @@ pub fn start_chain_services(builder: ChainServicesBuilder) -> ChainController {
         process_block_tx,
         truncate_block_tx,
         Arc::clone(&orphan_blocks_broker),
+        builder.shared.clone(),
         Arc::clone(&is_verifying_unverified_blocks_on_startup),
+        runtime_switch,
+        tip_block_cycles,
+        Arc::clone(&peer_block_stats),
+        Arc::clone(&verification_saturation),
     );
 
     let init_load_unverified_thread = thread::Builder::new()
@@ -105,10 +125,17 @@ pub fn start_chain_services(builder: ChainServicesBuilder) -> ChainController {
         orphan_blocks_broker,
         preload_unverified_tx,
         is_pending_verify,
+        builder.orphan_block_max_age,
+        verification_saturation,
     );
 
-    let chain_service: ChainService =
-        ChainService::new(builder.shared, process_block_rx, consume_orphan);
+    let chain_service: ChainService = ChainService::new(
+        builder.shared,
+        process_block_rx,
+        consume_orphan,
+        builder.duplicate_block_policy,
+        builder.log_peer_block_stats.then_some(peer_block_stats),
+    );
     let chain_service_thread = thread::Builder::new()
         .name("ChainService".into())
         .spawn({
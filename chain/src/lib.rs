@@ -8,6 +8,7 @@
 //! [`ChainService`]: chain/struct.ChainService.html
 //! [`ChainController`]: chain/struct.ChainController.html
 use ckb_error::Error;
+use ckb_network::PeerIndex;
 use ckb_types::core::service::Request;
 use ckb_types::core::{BlockNumber, BlockView, EpochNumber, HeaderView};
 use ckb_types::packed::Byte32;
@@ -31,6 +32,8 @@ use ckb_store::{ChainDB, ChainStore};
 use ckb_types::prelude::{Pack, Unpack};
 use ckb_types::{BlockNumberAndHash, H256};
 pub use init::start_chain_services;
+pub use utils::forkchanges::reorg_evicted_transactions;
+pub use utils::peer_block_stats::PeerBlockCounts;
 
 type ProcessBlockRequest = Request<LonelyBlock, ()>;
 type TruncateRequest = Request<Byte32, Result<(), Error>>;
@@ -52,6 +55,9 @@ pub struct RemoteBlock {
 
     /// Relayer and Synchronizer will have callback to ban peer
     pub verify_callback: VerifyCallback,
+
+    /// The peer which sent the block
+    pub peer_id: PeerIndex,
 }
 
 /// LonelyBlock is the block which we have not check weather its parent is stored yet
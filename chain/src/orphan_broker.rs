@@ -1,6 +1,8 @@
 #![allow(missing_docs)]
 
+use crate::utils::channel_backlog::{observe_channel, PRELOAD_UNVERIFIED_CHANNEL};
 use crate::utils::orphan_block_pool::{OrphanBlockPool, ParentHash};
+use crate::utils::verification_saturation::VerificationSaturation;
 use crate::{delete_unverified_block, LonelyBlockHash, VerifyResult};
 use ckb_channel::Sender;
 use ckb_error::InternalErrorKind;
@@ -11,7 +13,9 @@ use ckb_shared::Shared;
 use ckb_store::ChainStore;
 use ckb_types::{packed::Byte32, U256};
 use dashmap::DashSet;
+use std::collections::HashSet;
 use std::sync::Arc;
+use std::time::Duration;
 
 pub(crate) struct OrphanBroker {
     shared: Shared,
@@ -19,6 +23,9 @@ pub(crate) struct OrphanBroker {
     orphan_blocks_broker: Arc<OrphanBlockPool>,
     is_pending_verify: Arc<DashSet<Byte32>>,
     preload_unverified_tx: Sender<LonelyBlockHash>,
+    // the orphans whose parent doesn't arrive in time are evicted
+    max_age: Duration,
+    verification_saturation: Arc<VerificationSaturation>,
 }
 
 impl OrphanBroker {
@@ -27,15 +34,29 @@ impl OrphanBroker {
         orphan_block_pool: Arc<OrphanBlockPool>,
         preload_unverified_tx: Sender<LonelyBlockHash>,
         is_pending_verify: Arc<DashSet<Byte32>>,
+        max_age: Duration,
+        verification_saturation: Arc<VerificationSaturation>,
     ) -> OrphanBroker {
         OrphanBroker {
             shared,
             orphan_blocks_broker: orphan_block_pool,
             is_pending_verify,
             preload_unverified_tx,
+            max_age,
+            verification_saturation,
         }
     }
 
+    /// How long an orphan block waits for its parent before it's evicted
+    pub(crate) fn max_age(&self) -> Duration {
+        self.max_age
+    }
+
+    /// Whether the block has been inserted and is waiting for the contextual verification
+    pub(crate) fn is_pending_verify(&self, block_hash: &Byte32) -> bool {
+        self.is_pending_verify.contains(block_hash)
+    }
+
     fn search_orphan_leader(&self, leader_hash: ParentHash) {
         let leader_status = self.shared.get_block_status(&leader_hash);
 
@@ -150,9 +171,7 @@ impl OrphanBroker {
             .orphan_blocks_broker
             .clean_expired_blocks(tip_epoch_number);
         for expired_orphan in expired_orphans {
-            self.delete_block(&expired_orphan);
-            self.shared.remove_header_view(&expired_orphan.hash());
-            self.shared.remove_block_status(&expired_orphan.hash());
+            self.forget_orphan(&expired_orphan);
             info!(
                 "cleaned expired orphan: {}-{}",
                 expired_orphan.number(),
@@ -161,6 +180,49 @@ impl OrphanBroker {
         }
     }
 
+    pub(crate) fn evict_expired_orphans(&self) {
+        let evicted = self.orphan_blocks_broker.evict_expired(self.max_age);
+        let evicted_hashes: HashSet<Byte32> = evicted.iter().map(|orphan| orphan.hash()).collect();
+        for orphan in evicted {
+            self.forget_orphan(&orphan);
+            info!(
+                "evicted orphan whose parent never arrived: {}-{}",
+                orphan.number(),
+                orphan.hash()
+            );
+
+            // Nobody has ever sent even the parent header, the block is bogus. A known parent
+            // header only means the body is slow to come, probably from another peer during
+            // IBD, the peer of the orphan is not to blame.
+            let parent_hash = orphan.parent_hash();
+            if !evicted_hashes.contains(&parent_hash)
+                && self.shared.get_block_status(&parent_hash) == BlockStatus::UNKNOWN
+            {
+                let err: VerifyResult = Err(InternalErrorKind::Other
+                    .other(format!(
+                        "parent {} of orphan {}-{} never arrived",
+                        parent_hash,
+                        orphan.number(),
+                        orphan.hash()
+                    ))
+                    .into());
+                orphan.execute_callback(err);
+            }
+        }
+
+        if let Some(metrics) = ckb_metrics::handle() {
+            metrics
+                .ckb_chain_orphan_count
+                .set(self.orphan_blocks_broker.len() as i64)
+        }
+    }
+
+    fn forget_orphan(&self, orphan: &LonelyBlockHash) {
+        self.delete_block(orphan);
+        self.shared.remove_header_view(&orphan.hash());
+        self.shared.remove_block_status(&orphan.hash());
+    }
+
     fn send_unverified_block(&self, lonely_block: LonelyBlockHash) {
         let block_number = lonely_block.block_number_and_hash.number();
         let block_hash = lonely_block.block_number_and_hash.hash();
@@ -171,8 +233,22 @@ impl OrphanBroker {
                 .set(self.preload_unverified_tx.len() as i64)
         }
 
+        // flag the saturation before blocking on a full channel
+        self.verification_saturation.observe(
+            self.preload_unverified_tx.len() + 1,
+            self.preload_unverified_tx.capacity(),
+        );
         match self.preload_unverified_tx.send(lonely_block) {
             Ok(_) => {
+                observe_channel(
+                    PRELOAD_UNVERIFIED_CHANNEL,
+                    self.preload_unverified_tx.len(),
+                    self.preload_unverified_tx.capacity(),
+                );
+                self.verification_saturation.observe(
+                    self.preload_unverified_tx.len(),
+                    self.preload_unverified_tx.capacity(),
+                );
                 debug!(
                     "process desendant block success {}-{}",
                     block_number, block_hash
@@ -209,6 +285,8 @@ impl OrphanBroker {
         self.send_unverified_block(lonely_block)
     }
 
+    // the descendants are sorted in a breadth-first search manner, every block is sent after
+    // its parent, so the unverified tip never moves backwards
     fn accept_descendants(&self, descendants: Vec<LonelyBlockHash>) {
         for descendant_block in descendants {
             self.process_descendant(descendant_block);
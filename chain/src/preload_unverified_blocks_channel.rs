@@ -1,3 +1,7 @@
+use crate::utils::channel_backlog::{
+    observe_channel, PRELOAD_UNVERIFIED_CHANNEL, UNVERIFIED_CHANNEL,
+};
+use crate::utils::verification_saturation::VerificationSaturation;
 use crate::{LonelyBlockHash, UnverifiedBlock};
 use ckb_channel::{Receiver, Sender};
 use ckb_logger::{debug, error, info};
@@ -13,6 +17,8 @@ pub(crate) struct PreloadUnverifiedBlocksChannel {
     unverified_block_tx: Sender<UnverifiedBlock>,
 
     stop_rx: Receiver<()>,
+
+    verification_saturation: Arc<VerificationSaturation>,
 }
 
 impl PreloadUnverifiedBlocksChannel {
@@ -21,12 +27,14 @@ impl PreloadUnverifiedBlocksChannel {
         preload_unverified_rx: Receiver<LonelyBlockHash>,
         unverified_block_tx: Sender<UnverifiedBlock>,
         stop_rx: Receiver<()>,
+        verification_saturation: Arc<VerificationSaturation>,
     ) -> Self {
         PreloadUnverifiedBlocksChannel {
             shared,
             preload_unverified_rx,
             unverified_block_tx,
             stop_rx,
+            verification_saturation,
         }
     }
 
@@ -35,6 +43,8 @@ impl PreloadUnverifiedBlocksChannel {
             select! {
                 recv(self.preload_unverified_rx) -> msg => match msg {
                     Ok(preload_unverified_block_task) =>{
+                        observe_channel(PRELOAD_UNVERIFIED_CHANNEL, self.preload_unverified_rx.len(), self.preload_unverified_rx.capacity());
+                        self.verification_saturation.observe(self.preload_unverified_rx.len(), self.preload_unverified_rx.capacity());
                         self.preload_unverified_channel(preload_unverified_block_task);
                     },
                     Err(err) =>{
@@ -66,6 +76,11 @@ impl PreloadUnverifiedBlocksChannel {
                 "send unverified_block to unverified_block_tx failed, the receiver has been closed"
             );
         } else {
+            observe_channel(
+                UNVERIFIED_CHANNEL,
+                self.unverified_block_tx.len(),
+                self.unverified_block_tx.capacity(),
+            );
             debug!("preload unverified block {}-{}", block_number, block_hash,);
         }
     }
@@ -1,8 +1,9 @@
 use crate::tests::util::start_chain;
-use crate::ChainController;
+use crate::{ChainController, LonelyBlock, PeerBlockCounts, RemoteBlock, VerifyResult};
 use ckb_chain_spec::consensus::{Consensus, ConsensusBuilder};
 use ckb_dao_utils::genesis_dao_data;
 use ckb_error::assert_error_eq;
+use ckb_network::PeerIndex;
 use ckb_shared::shared::Shared;
 use ckb_store::ChainStore;
 use ckb_test_chain_utils::{
@@ -24,6 +25,7 @@ use ckb_types::{
 };
 use ckb_verification_traits::Switch;
 use std::sync::Arc;
+use std::time::Duration;
 
 #[test]
 fn repeat_process_block() {
@@ -58,6 +60,139 @@ fn repeat_process_block() {
     );
 }
 
+#[test]
+fn process_known_block_twice() {
+    let (chain_controller, shared, parent) = start_chain(None);
+    let mock_store = MockStore::new(&parent, shared.store());
+    let mut chain = MockChain::new(parent, shared.consensus());
+    chain.gen_empty_block_with_nonce(100u128, &mock_store);
+    let block = Arc::new(chain.blocks().last().unwrap().clone());
+
+    assert!(chain_controller
+        .blocking_process_block_with_switch(Arc::clone(&block), Switch::DISABLE_EXTENSION)
+        .expect("process block ok"));
+    let tip = shared.snapshot().tip_hash();
+
+    // the stored block is submitted twice, both are short-circuited as a known block
+    let (result_tx, result_rx) = ckb_channel::unbounded();
+    for _ in 0..2 {
+        let result_tx = result_tx.clone();
+        chain_controller.asynchronous_process_lonely_block(LonelyBlock {
+            block: Arc::clone(&block),
+            switch: Some(Switch::DISABLE_EXTENSION),
+            verify_callback: Some(Box::new(move |result: VerifyResult| {
+                let _ = result_tx.send(result);
+            })),
+        });
+    }
+    for _ in 0..2 {
+        let result = result_rx
+            .recv_timeout(Duration::from_secs(10))
+            .expect("receive verify result");
+        assert!(!result.expect("known block is not an error"));
+    }
+
+    assert_eq!(shared.snapshot().tip_hash(), tip);
+    assert_eq!(
+        shared
+            .store()
+            .get_block_ext(&block.header().hash())
+            .unwrap()
+            .verified,
+        Some(true)
+    );
+}
+
+#[test]
+fn count_remote_blocks_by_peer() {
+    let (chain_controller, shared, parent) = start_chain(None);
+    let mock_store = MockStore::new(&parent, shared.store());
+    let mut chain = MockChain::new(parent.clone(), shared.consensus());
+    chain.gen_empty_block_with_nonce(100u128, &mock_store);
+    let known_block = Arc::new(chain.blocks().last().unwrap().clone());
+    assert!(chain_controller
+        .blocking_process_block_with_switch(Arc::clone(&known_block), Switch::DISABLE_EXTENSION)
+        .expect("process block ok"));
+
+    // a block without cellbase fails the non-contextual verification
+    let invalid_block = Arc::new(
+        BlockBuilder::default()
+            .parent_hash(parent.hash())
+            .number((parent.number() + 1).pack())
+            .build(),
+    );
+
+    let (peer1, peer2) = (PeerIndex::new(1), PeerIndex::new(2));
+    let (result_tx, result_rx) = ckb_channel::unbounded();
+    for (peer_id, block) in [
+        (peer1, Arc::clone(&known_block)),
+        (peer1, Arc::clone(&invalid_block)),
+        (peer2, invalid_block),
+    ] {
+        let result_tx = result_tx.clone();
+        chain_controller.asynchronous_process_remote_block(RemoteBlock {
+            block,
+            peer_id,
+            verify_callback: Box::new(move |result: VerifyResult| {
+                let _ = result_tx.send(result);
+            }),
+        });
+    }
+    for _ in 0..3 {
+        result_rx
+            .recv_timeout(Duration::from_secs(10))
+            .expect("receive verify result");
+    }
+
+    assert_eq!(
+        chain_controller.peer_block_counts(peer1),
+        PeerBlockCounts {
+            accepted: 1,
+            rejected: 1
+        }
+    );
+    assert_eq!(
+        chain_controller.all_peer_block_counts(),
+        vec![
+            (
+                peer1,
+                PeerBlockCounts {
+                    accepted: 1,
+                    rejected: 1
+                }
+            ),
+            (
+                peer2,
+                PeerBlockCounts {
+                    accepted: 0,
+                    rejected: 1
+                }
+            ),
+        ]
+    );
+    assert_eq!(
+        chain_controller.peer_block_counts(PeerIndex::new(3)),
+        PeerBlockCounts::default()
+    );
+
+    // the counters of a disconnected peer are dropped
+    chain_controller.remove_peer_block_counts(peer1);
+    assert_eq!(
+        chain_controller.peer_block_counts(peer1),
+        PeerBlockCounts::default()
+    );
+    assert_eq!(
+        chain_controller.all_peer_block_counts(),
+        vec![(
+            peer2,
+            PeerBlockCounts {
+                accepted: 0,
+                rejected: 1
+            }
+        )]
+    );
+}
+
 #[test]
 fn process_genesis_block() {
     let tx = TransactionBuilder::default()
@@ -269,6 +404,124 @@ fn test_transaction_spend_in_same_block() {
     );
 }
 
+#[test]
+fn test_tip_block_cycles() {
+    let (chain_controller, shared, parent) = start_chain(None);
+    let mock_store = MockStore::new(&parent, shared.store());
+    let mut chain = MockChain::new(parent, shared.consensus());
+    chain.gen_empty_block(&mock_store);
+
+    let last_cellbase = &shared.consensus().genesis_block().transactions()[1];
+    let tx = create_multi_outputs_transaction(last_cellbase, vec![0], 2, vec![1]);
+    chain.gen_block_with_proposal_txs(vec![tx.clone()], &mock_store);
+    chain.gen_empty_block(&mock_store);
+    chain.gen_block_with_commit_txs(vec![tx], &mock_store, false);
+
+    let switch = Switch::DISABLE_EPOCH | Switch::DISABLE_EXTENSION;
+    for block in chain.blocks() {
+        chain_controller
+            .blocking_process_block_with_switch(Arc::new(block.clone()), switch)
+            .expect("process block ok");
+    }
+
+    let tip_hash = shared.snapshot().tip_hash();
+    let tx_cycles: u64 = shared
+        .store()
+        .get_block_ext(&tip_hash)
+        .and_then(|ext| ext.cycles)
+        .expect("cycles stored")
+        .iter()
+        .sum();
+    assert!(tx_cycles > 0);
+    assert_eq!(
+        chain_controller.tip_block_cycles(),
+        Some((tip_hash, tx_cycles))
+    );
+
+    // the tip's scripts are not verified
+    chain.gen_empty_block(&mock_store);
+    let block = chain.blocks().last().unwrap().clone();
+    chain_controller
+        .blocking_process_block_with_switch(Arc::new(block), switch | Switch::DISABLE_SCRIPT)
+        .expect("process block ok");
+    assert_eq!(chain_controller.tip_block_cycles(), None);
+}
+
+#[test]
+fn test_verify_transaction() {
+    let (chain_controller, shared, _parent) = start_chain(None);
+    let snapshot = shared.snapshot();
+    let tip = snapshot.tip_hash();
+
+    let live_cell_tx = &shared.consensus().genesis_block().transactions()[1];
+    let tx = create_transaction(&live_cell_tx.hash(), 1);
+    let cycles = chain_controller
+        .verify_transaction(&snapshot, tx.clone())
+        .expect("verify transaction ok");
+    assert!(cycles > 0);
+    // neither the tx-pool nor the chain is touched
+    assert_eq!(shared.snapshot().tip_hash(), tip);
+    let ids = shared
+        .tx_pool_controller()
+        .get_all_ids()
+        .expect("get all ids");
+    assert!(ids.pending.is_empty() && ids.proposed.is_empty());
+
+    let unknown_tx = create_transaction(&tx.hash(), 2);
+    let err = chain_controller
+        .verify_transaction(&snapshot, unknown_tx)
+        .unwrap_err();
+    assert_error_eq!(err, OutPointError::Unknown(OutPoint::new(tx.hash(), 0)));
+}
+
+#[test]
+fn test_resolve_block_transactions() {
+    let (chain_controller, shared, parent) = start_chain(None);
+    let mock_store = MockStore::new(&parent, shared.store());
+    let mut chain = MockChain::new(parent, shared.consensus());
+    chain.gen_empty_block(&mock_store);
+
+    let last_cellbase = &shared.consensus().genesis_block().transactions()[1];
+    let tx1 = create_multi_outputs_transaction(last_cellbase, vec![0], 2, vec![1]);
+    let tx2 = create_multi_outputs_transaction(&tx1, vec![0], 2, vec![2]);
+    let txs = vec![tx1.clone(), tx2.clone()];
+    chain.gen_block_with_proposal_txs(txs.clone(), &mock_store);
+    chain.gen_empty_block(&mock_store);
+    chain.gen_block_with_commit_txs(txs, &mock_store, false);
+
+    let (block, blocks) = chain.blocks().split_last().unwrap();
+    for block in blocks {
+        chain_controller
+            .blocking_process_block_with_switch(
+                Arc::new(block.clone()),
+                Switch::DISABLE_EPOCH | Switch::DISABLE_EXTENSION,
+            )
+            .expect("process block ok");
+    }
+
+    let snapshot = shared.snapshot();
+    let resolved = chain_controller
+        .resolve_block_transactions(&snapshot, block)
+        .expect("resolve block transactions");
+    assert_eq!(resolved.len(), 3);
+    assert!(resolved[0].transaction.is_cellbase());
+    // tx1 consumes a live cell of the store, tx2 consumes an output of tx1 in the same block
+    assert_eq!(resolved[1].transaction.hash(), tx1.hash());
+    assert_eq!(
+        resolved[1].resolved_inputs[0].out_point,
+        OutPoint::new(last_cellbase.hash(), 0)
+    );
+    assert_eq!(resolved[2].transaction.hash(), tx2.hash());
+    assert_eq!(
+        resolved[2].resolved_inputs[0].out_point,
+        OutPoint::new(tx1.hash(), 0)
+    );
+
+    // the block is only resolved, not verified
+    assert_eq!(shared.snapshot().tip_hash(), snapshot.tip_hash());
+    assert!(shared.store().get_block_ext(&block.hash()).is_none());
+}
+
 #[test]
 fn test_transaction_conflict_in_same_block() {
     let (chain_controller, shared, parent) = start_chain(None);
@@ -938,3 +1191,28 @@ fn test_next_epoch_ext() {
         );
     }
 }
+
+#[test]
+fn test_get_unverified_tip() {
+    let (chain_controller, shared, parent) = start_chain(None);
+    let unverified_tip = chain_controller.get_unverified_tip();
+    assert_eq!(unverified_tip.number(), parent.number());
+    assert_eq!(unverified_tip.hash(), parent.hash());
+
+    let mock_store = MockStore::new(&parent, shared.store());
+    let mut chain = MockChain::new(parent, shared.consensus());
+    for _ in 0..2 {
+        chain.gen_empty_block_with_nonce(100u128, &mock_store);
+    }
+    for block in chain.blocks() {
+        chain_controller
+            .blocking_process_block_with_switch(Arc::new(block.clone()), Switch::DISABLE_EXTENSION)
+            .expect("process block ok");
+    }
+
+    let tip = chain.tip_header();
+    let unverified_tip = chain_controller.get_unverified_tip();
+    assert_eq!(unverified_tip.number(), tip.number());
+    assert_eq!(unverified_tip.hash(), tip.hash());
+    assert_eq!(shared.snapshot().tip_hash(), tip.hash());
+}
@@ -10,15 +10,17 @@ use ckb_tx_pool::{block_assembler::CandidateUncles, PlugTarget, TxEntry};
 use ckb_types::{
     bytes::Bytes,
     core::{
-        BlockBuilder, BlockNumber, BlockView, Capacity, EpochExt, HeaderBuilder, HeaderView,
-        TransactionBuilder, TransactionView,
+        tx_pool::ExclusionReason, BlockBuilder, BlockNumber, BlockView, Capacity, EpochExt,
+        HeaderBuilder, HeaderView, TransactionBuilder, TransactionView,
     },
     h256,
     packed::{Block, CellInput, CellOutput, CellOutputBuilder, CellbaseWitness, OutPoint},
     prelude::*,
+    H256,
 };
 use ckb_verification::{BlockVerifier, HeaderVerifier};
 use ckb_verification_traits::{Switch, Verifier};
+use std::collections::HashMap;
 use std::sync::Arc;
 
 fn start_chain(consensus: Option<Consensus>) -> (ChainController, Shared) {
@@ -34,9 +36,14 @@ fn start_chain(consensus: Option<Consensus>) -> (ChainController, Shared) {
         use_binary_version_as_message_prefix: true,
         binary_version: "TEST".to_string(),
         update_interval_millis: 800,
+        new_transactions_threshold: 0,
         notify: vec![],
         notify_scripts: vec![],
         notify_timeout_millis: 800,
+        assembly_deadline_millis: None,
+        max_block_transactions: None,
+        reward_locks: vec![],
+        reward_lock_policy: Default::default(),
     };
     let (shared, mut pack) = builder
         .block_assembler_config(Some(config))
@@ -657,3 +664,151 @@ fn test_package_low_fee_descendants() {
     let txs = tx_pool.package_txs(None).unwrap();
     check_txs(&txs, vec![&tx1, &tx2, &tx3, &tx4, &tx5], "best scored txs");
 }
+
+#[test]
+fn test_block_template_totals() {
+    let mut consensus = Consensus::default();
+    consensus.genesis_epoch_ext.set_length(5);
+    let epoch = consensus.genesis_epoch_ext().clone();
+    let max_block_cycles = consensus.max_block_cycles();
+
+    let (chain_controller, shared) = start_chain(Some(consensus));
+
+    let genesis = shared
+        .store()
+        .get_block_header(&shared.store().get_block_hash(0).unwrap())
+        .unwrap();
+    let mut parent_header = genesis;
+    let mut blocks = vec![];
+    for _i in 0..4 {
+        let block = gen_block(&parent_header, 11, &epoch);
+        chain_controller
+            .blocking_process_block_with_switch(Arc::new(block.clone()), Switch::DISABLE_ALL)
+            .expect("process block");
+        parent_header = block.header().to_owned();
+        blocks.push(block);
+    }
+
+    let tx1 = build_tx(&blocks[0].transactions()[0], &[0], 2);
+    let tx2_1 = build_tx(&blocks[1].transactions()[0], &[0], 2);
+    let tx3_1 = build_tx(&blocks[2].transactions()[0], &[0], 2);
+    let over_budget = build_tx(&blocks[3].transactions()[0], &[0], 2);
+    let entries = vec![
+        TxEntry::dummy_resolve(tx1, 1000, Capacity::shannons(100), 100),
+        TxEntry::dummy_resolve(tx2_1, 2000, Capacity::shannons(200), 150),
+        TxEntry::dummy_resolve(tx3_1, 3000, Capacity::shannons(300), 200),
+        TxEntry::dummy_resolve(
+            over_budget.clone(),
+            max_block_cycles + 1,
+            Capacity::shannons(1000),
+            100,
+        ),
+    ];
+    let expected: HashMap<_, _> = entries
+        .iter()
+        .map(|entry| {
+            (
+                entry.transaction().hash().unpack(),
+                (entry.cycles, entry.size as u64, entry.fee.as_u64()),
+            )
+        })
+        .collect();
+
+    let tx_pool = shared.tx_pool_controller();
+    tx_pool.plug_entry(entries, PlugTarget::Proposed).unwrap();
+
+    let template = loop {
+        let template = tx_pool
+            .get_block_template(None, None, None)
+            .expect("must fetch block template result")
+            .expect("must have block template");
+        if template.transactions.len() == 3 {
+            break template;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    };
+
+    let over_budget_hash: H256 = over_budget.hash().unpack();
+    assert!(template
+        .transactions
+        .iter()
+        .all(|tx| tx.hash != over_budget_hash));
+
+    let (cycles, size, fees) =
+        template
+            .transactions
+            .iter()
+            .fold((0, 0, 0), |(cycles, size, fees), tx| {
+                let (tx_cycles, tx_size, tx_fee) = expected[&tx.hash];
+                (cycles + tx_cycles, size + tx_size, fees + tx_fee)
+            });
+    assert_eq!(cycles, 6000);
+    assert_eq!(template.total_cycles.value(), cycles);
+    assert_eq!(template.total_size.value(), size);
+    assert_eq!(template.total_fees.value(), fees);
+}
+
+#[test]
+fn test_explain_tx_exclusion() {
+    let mut consensus = Consensus::default();
+    consensus.genesis_epoch_ext.set_length(5);
+    let epoch = consensus.genesis_epoch_ext().clone();
+    let max_block_cycles = consensus.max_block_cycles();
+
+    let (chain_controller, shared) = start_chain(Some(consensus));
+
+    let genesis = shared
+        .store()
+        .get_block_header(&shared.store().get_block_hash(0).unwrap())
+        .unwrap();
+    let mut parent_header = genesis;
+    let mut blocks = vec![];
+    for _i in 0..4 {
+        let block = gen_block(&parent_header, 11, &epoch);
+        chain_controller
+            .blocking_process_block_with_switch(Arc::new(block.clone()), Switch::DISABLE_ALL)
+            .expect("process block");
+        parent_header = block.header().to_owned();
+        blocks.push(block);
+    }
+
+    let tx1 = build_tx(&blocks[0].transactions()[0], &[0], 2);
+    let tx2 = build_tx(&tx1, &[0], 2);
+    let tx2_1 = build_tx(&blocks[1].transactions()[0], &[0], 2);
+    let tx3_1 = build_tx(&blocks[2].transactions()[0], &[0], 2);
+    let unknown = build_tx(&blocks[3].transactions()[0], &[0], 2);
+
+    let tx_pool = shared.tx_pool_controller();
+    tx_pool
+        .plug_entry(
+            vec![TxEntry::dummy_resolve(
+                tx1.clone(),
+                0,
+                Capacity::shannons(100),
+                100,
+            )],
+            PlugTarget::Pending,
+        )
+        .unwrap();
+    let entries = vec![
+        TxEntry::dummy_resolve(tx2.clone(), 0, Capacity::shannons(100), 100),
+        TxEntry::dummy_resolve(tx2_1.clone(), 0, Capacity::shannons(100), 100),
+        TxEntry::dummy_resolve(
+            tx3_1.clone(),
+            max_block_cycles + 1,
+            Capacity::shannons(100),
+            100,
+        ),
+    ];
+    tx_pool.plug_entry(entries, PlugTarget::Proposed).unwrap();
+
+    let explain = |tx: &TransactionView| tx_pool.explain_tx_exclusion(tx.hash()).unwrap();
+    assert_eq!(explain(&tx1), ExclusionReason::NotProposed);
+    assert_eq!(
+        explain(&tx2),
+        ExclusionReason::DependencyExcluded(tx1.hash())
+    );
+    assert_eq!(explain(&tx2_1), ExclusionReason::Included);
+    assert_eq!(explain(&tx3_1), ExclusionReason::OverBudget);
+    assert_eq!(explain(&unknown), ExclusionReason::Unknown);
+}
@@ -0,0 +1,95 @@
+use crate::tests::util::start_chain;
+use crate::utils::channel_backlog::UNVERIFIED_CHANNEL;
+use crate::LonelyBlock;
+use ckb_metrics::testing;
+use ckb_types::{
+    core::{BlockBuilder, BlockView, EpochExt, HeaderView, TransactionBuilder},
+    packed::CellInput,
+    prelude::*,
+};
+use ckb_verification_traits::Switch;
+use std::sync::Arc;
+use std::time::Duration;
+
+const BACKLOG: i64 = 5;
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+fn gen_block(parent: &HeaderView, epoch: &EpochExt) -> BlockView {
+    let number = parent.number() + 1;
+    let cellbase = TransactionBuilder::default()
+        .input(CellInput::new_cellbase_input(number))
+        .build();
+    BlockBuilder::default()
+        .parent_hash(parent.hash())
+        .number(number.pack())
+        .timestamp((parent.timestamp() + 1).pack())
+        .epoch(epoch.number_with_fraction(number).pack())
+        .compact_target(epoch.compact_target().pack())
+        .transaction(cellbase)
+        .build()
+}
+
+#[test]
+fn test_unverified_channel_backlog() {
+    let metrics = testing::enable();
+    let unverified_len = |len: i64| {
+        testing::wait_for(TIMEOUT, |metrics| {
+            metrics
+                .ckb_chain_channel_len
+                .with_label_values(&[UNVERIFIED_CHANNEL])
+                .get()
+                == len
+        })
+    };
+
+    let (chain_controller, shared, mut parent) = start_chain(None);
+    let genesis_epoch = shared.consensus().genesis_epoch_ext().clone();
+
+    // the verify thread stalls in the callback of the first block, so its descendants are
+    // queued in the unverified channel
+    let (stalled_tx, stalled_rx) = ckb_channel::bounded(0);
+    let (release_tx, release_rx) = ckb_channel::bounded::<()>(0);
+    let mut blocks = Vec::new();
+    for _ in 0..=BACKLOG {
+        let block = gen_block(&parent, &genesis_epoch);
+        parent = block.header();
+        blocks.push(block);
+    }
+    let first = blocks.remove(0);
+    chain_controller.asynchronous_process_lonely_block(LonelyBlock {
+        block: Arc::new(first),
+        switch: Some(Switch::DISABLE_ALL),
+        verify_callback: Some(Box::new(move |_| {
+            stalled_tx.send(()).unwrap();
+            release_rx.recv().unwrap();
+        })),
+    });
+    stalled_rx.recv().unwrap();
+
+    let (verified_tx, verified_rx) = ckb_channel::unbounded();
+    for block in blocks {
+        let verified_tx = verified_tx.clone();
+        chain_controller.asynchronous_process_lonely_block(LonelyBlock {
+            block: Arc::new(block),
+            switch: Some(Switch::DISABLE_ALL),
+            verify_callback: Some(Box::new(move |verify_result| {
+                verified_tx.send(verify_result).unwrap();
+            })),
+        });
+    }
+    assert!(unverified_len(BACKLOG));
+    assert_eq!(
+        metrics
+            .ckb_chain_channel_capacity
+            .with_label_values(&[UNVERIFIED_CHANNEL])
+            .get(),
+        128
+    );
+
+    // the backlog is drained once the verify thread resumes
+    release_tx.send(()).unwrap();
+    for _ in 0..BACKLOG {
+        verified_rx.recv_timeout(TIMEOUT).unwrap().unwrap();
+    }
+    assert!(unverified_len(0));
+}
@@ -73,6 +73,11 @@ fn test_find_fork_case1() {
         shared: shared.clone(),
         is_pending_verify,
         proposal_table,
+        runtime_switch: Default::default(),
+        tip_block_cycles: Default::default(),
+        proposal_window_cache: Default::default(),
+        uncle_inclusion_cache: Default::default(),
+        time_relative_cache: Default::default(),
     };
 
     // fork1 total_difficulty 400
@@ -157,6 +162,11 @@ fn test_find_fork_case2() {
         shared: shared.clone(),
         is_pending_verify: Arc::new(DashSet::new()),
         proposal_table,
+        runtime_switch: Default::default(),
+        tip_block_cycles: Default::default(),
+        proposal_window_cache: Default::default(),
+        uncle_inclusion_cache: Default::default(),
+        time_relative_cache: Default::default(),
     };
 
     // fork1 total_difficulty 400
@@ -240,6 +250,11 @@ fn test_find_fork_case3() {
         shared: shared.clone(),
         is_pending_verify: Arc::new(DashSet::new()),
         proposal_table,
+        runtime_switch: Default::default(),
+        tip_block_cycles: Default::default(),
+        proposal_window_cache: Default::default(),
+        uncle_inclusion_cache: Default::default(),
+        time_relative_cache: Default::default(),
     };
     // fork1 total_difficulty 240
     for blk in fork1.blocks() {
@@ -321,6 +336,11 @@ fn test_find_fork_case4() {
         shared: shared.clone(),
         is_pending_verify: Arc::new(DashSet::new()),
         proposal_table,
+        runtime_switch: Default::default(),
+        tip_block_cycles: Default::default(),
+        proposal_window_cache: Default::default(),
+        uncle_inclusion_cache: Default::default(),
+        time_relative_cache: Default::default(),
     };
 
     // fork1 total_difficulty 200
@@ -403,6 +423,11 @@ fn repeatedly_switch_fork() {
         shared: shared.clone(),
         is_pending_verify: Arc::new(DashSet::new()),
         proposal_table,
+        runtime_switch: Default::default(),
+        tip_block_cycles: Default::default(),
+        proposal_window_cache: Default::default(),
+        uncle_inclusion_cache: Default::default(),
+        time_relative_cache: Default::default(),
     };
 
     for blk in fork1.blocks() {
@@ -1,5 +1,6 @@
 mod basic;
 mod block_assembler;
+mod channel_backlog;
 mod cell;
 mod delay_verify;
 mod dep_cell;
@@ -9,7 +10,11 @@ mod load_input_cell_data;
 mod load_input_data_hash_cell;
 mod non_contextual_block_txs_verify;
 mod orphan_block_pool;
+mod process_block_timeout;
+mod reorg_evicted;
 mod reward;
+mod runtime_switch;
 mod truncate;
 mod uncle;
 mod util;
+mod verification_saturation;
@@ -9,6 +9,7 @@ use ckb_types::prelude::*;
 use std::collections::HashSet;
 use std::sync::Arc;
 use std::thread;
+use std::time::Duration;
 
 use crate::utils::orphan_block_pool::OrphanBlockPool;
 
@@ -258,3 +259,55 @@ fn test_remove_expired_blocks() {
     assert_eq!(v.len(), 19);
     assert_eq!(pool.leaders_len(), 0);
 }
+
+#[test]
+fn test_evict_expired_blocks() {
+    let faketime_guard = ckb_systemtime::faketime();
+    let now = unix_time_as_millis();
+    faketime_guard.set_faketime(now);
+
+    let consensus = ConsensusBuilder::default().build();
+    let genesis = consensus.genesis_block().header();
+    let pool = OrphanBlockPool::with_capacity(16);
+
+    // 3 orphans on top of a missing parent
+    let gen_orphans = |pool: &OrphanBlockPool| {
+        let missing_parent = gen_lonely_block(&genesis);
+        let mut parent = missing_parent.block().header();
+        let mut hashes = HashSet::new();
+        for _ in 0..3 {
+            let lonely_block = gen_lonely_block(&parent);
+            parent = lonely_block.block().header();
+            hashes.insert(parent.hash());
+            pool.insert(lonely_block.into());
+        }
+        hashes
+    };
+    let old_orphans = gen_orphans(&pool);
+    faketime_guard.set_faketime(now + 60_000);
+    let young_orphans = gen_orphans(&pool);
+    assert_eq!(pool.len(), 6);
+    assert_eq!(pool.leaders_len(), 2);
+
+    let max_age = Duration::from_secs(60);
+    faketime_guard.set_faketime(now + 90_000);
+    let evicted: HashSet<Byte32> = pool
+        .evict_expired(max_age)
+        .iter()
+        .map(|lonely_block| lonely_block.hash())
+        .collect();
+    assert_eq!(evicted, old_orphans);
+    assert_eq!(pool.len(), 3);
+    assert_eq!(pool.leaders_len(), 1);
+    assert!(pool.evict_expired(max_age).is_empty());
+
+    faketime_guard.set_faketime(now + 180_000);
+    let evicted: HashSet<Byte32> = pool
+        .evict_expired(max_age)
+        .iter()
+        .map(|lonely_block| lonely_block.hash())
+        .collect();
+    assert_eq!(evicted, young_orphans);
+    assert_eq!(pool.len(), 0);
+    assert_eq!(pool.leaders_len(), 0);
+}
@@ -0,0 +1,66 @@
+use crate::utils::orphan_block_pool::OrphanBlockPool;
+use crate::{ChainController, ProcessBlockRequest};
+use ckb_error::{InternalError, InternalErrorKind};
+use ckb_shared::SharedBuilder;
+use ckb_types::core::{service::Request, BlockBuilder};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+// A chain service which takes `delay` to verify every block
+fn start_slow_chain(delay: Duration) -> (ChainController, thread::JoinHandle<()>) {
+    let (process_block_sender, process_block_receiver) =
+        ckb_channel::bounded::<ProcessBlockRequest>(1);
+    let (truncate_sender, _truncate_receiver) = ckb_channel::bounded(1);
+    let (shared, _) = SharedBuilder::with_temp_db().build().unwrap();
+    let chain_controller = ChainController::new(
+        process_block_sender,
+        truncate_sender,
+        Arc::new(OrphanBlockPool::with_capacity(1)),
+        shared,
+        Arc::new(AtomicBool::new(false)),
+        Default::default(),
+        Default::default(),
+        Default::default(),
+    );
+    let handle = thread::spawn(move || {
+        for Request {
+            responder,
+            arguments: lonely_block,
+        } in process_block_receiver
+        {
+            let _ = responder.send(());
+            thread::sleep(delay);
+            lonely_block.execute_callback(Ok(true));
+        }
+    });
+    (chain_controller, handle)
+}
+
+#[test]
+fn test_blocking_process_block_timeout() {
+    let (chain_controller, handle) = start_slow_chain(Duration::from_millis(500));
+    let block = Arc::new(BlockBuilder::default().build());
+
+    let err = chain_controller
+        .blocking_process_block_with_timeout(block, Duration::from_millis(10))
+        .unwrap_err();
+    let kind = err.downcast_ref::<InternalError>().map(InternalError::kind);
+    assert_eq!(kind, Some(InternalErrorKind::System));
+    assert!(err.to_string().contains("verify timed out"));
+
+    // the late verify result is discarded without panicking
+    drop(chain_controller);
+    handle.join().expect("chain service shouldn't panic");
+}
+
+#[test]
+fn test_blocking_process_block_within_timeout() {
+    let (chain_controller, _handle) = start_slow_chain(Duration::ZERO);
+    let block = Arc::new(BlockBuilder::default().build());
+    let verify_result = chain_controller
+        .blocking_process_block_with_timeout(block, Duration::from_secs(5))
+        .expect("verified in time");
+    assert!(verify_result);
+}
@@ -0,0 +1,71 @@
+use crate::reorg_evicted_transactions;
+use ckb_shared::SharedBuilder;
+use ckb_store::ChainStore;
+use ckb_types::{
+    bytes::Bytes,
+    core::{BlockBuilder, BlockView, HeaderView, TransactionBuilder, TransactionView},
+    packed::{self, CellInput, CellOutput},
+    prelude::*,
+};
+use std::collections::HashSet;
+
+fn gen_tx(tag: u64) -> TransactionView {
+    TransactionBuilder::default()
+        .input(CellInput::new(packed::OutPoint::null(), 0))
+        .output(CellOutput::new_builder().build())
+        .output_data(Bytes::from(tag.to_le_bytes().to_vec()).pack())
+        .build()
+}
+
+fn gen_block(parent: &HeaderView, nonce: u128, txs: Vec<TransactionView>) -> BlockView {
+    let number = parent.number() + 1;
+    BlockBuilder::default()
+        .parent_hash(parent.hash())
+        .number(number.pack())
+        .nonce(nonce.pack())
+        .transaction(gen_tx(u64::MAX - number))
+        .transactions(txs)
+        .build()
+}
+
+// 0--1--2--3    (old tip, commits tx1, tx2, tx3)
+//  \
+//   1'--2'      (new tip, commits tx2, tx4)
+#[test]
+fn test_reorg_evicted_transactions() {
+    let (shared, _pack) = SharedBuilder::with_temp_db().build().unwrap();
+    let store = shared.store();
+    let genesis = store
+        .get_block_header(&store.get_block_hash(0).unwrap())
+        .unwrap();
+
+    let (tx1, tx2, tx3, tx4) = (gen_tx(1), gen_tx(2), gen_tx(3), gen_tx(4));
+
+    let old_1 = gen_block(&genesis, 1, vec![tx1.clone()]);
+    let old_2 = gen_block(&old_1.header(), 1, vec![tx2.clone()]);
+    let old_3 = gen_block(&old_2.header(), 1, vec![tx3.clone()]);
+    let new_1 = gen_block(&genesis, 2, vec![tx2.clone()]);
+    let new_2 = gen_block(&new_1.header(), 2, vec![tx4.clone()]);
+
+    let db_txn = store.begin_transaction();
+    for block in [&old_1, &old_2, &old_3, &new_1, &new_2] {
+        db_txn.insert_block(block).unwrap();
+    }
+    db_txn.commit().unwrap();
+
+    let evicted = reorg_evicted_transactions(store, &old_3.hash(), &new_2.hash()).unwrap();
+    let expected: HashSet<_> = [tx1.hash(), tx3.hash()].into_iter().collect();
+    assert_eq!(evicted, expected);
+
+    // the reverse reorg evicts tx4 only, tx2 is committed on both branches
+    let evicted = reorg_evicted_transactions(store, &new_2.hash(), &old_3.hash()).unwrap();
+    let expected: HashSet<_> = [tx4.hash()].into_iter().collect();
+    assert_eq!(evicted, expected);
+
+    // extending the tip evicts nothing
+    let evicted = reorg_evicted_transactions(store, &old_1.hash(), &old_3.hash()).unwrap();
+    assert!(evicted.is_empty());
+
+    // unknown tip
+    assert!(reorg_evicted_transactions(store, &packed::Byte32::zero(), &old_3.hash()).is_none());
+}
@@ -0,0 +1,36 @@
+use crate::utils::runtime_switch::RuntimeSwitch;
+use ckb_chain_spec::consensus::{Consensus, ConsensusBuilder};
+use ckb_constant::hardfork::mainnet;
+use ckb_verification_traits::Switch;
+
+#[test]
+fn test_mainnet_refuses_weakening() {
+    let consensus = ConsensusBuilder::default()
+        .id(mainnet::CHAIN_SPEC_NAME.to_owned())
+        .build();
+    let runtime_switch = RuntimeSwitch::new(&consensus);
+
+    for switch in [
+        Switch::DISABLE_SCRIPT,
+        Switch::DISABLE_NON_CONTEXTUAL,
+        Switch::DISABLE_REWARD,
+        Switch::DISABLE_ALL,
+    ] {
+        assert!(runtime_switch.update(switch).is_err());
+        assert_eq!(runtime_switch.load(), Switch::NONE);
+    }
+
+    runtime_switch.update(Switch::DISABLE_EXTENSION).unwrap();
+    assert_eq!(runtime_switch.load(), Switch::DISABLE_EXTENSION);
+}
+
+#[test]
+fn test_testnet_allows_any_switch() {
+    let runtime_switch = RuntimeSwitch::new(&Consensus::default());
+
+    runtime_switch.update(Switch::DISABLE_ALL).unwrap();
+    assert_eq!(runtime_switch.load(), Switch::DISABLE_ALL);
+
+    runtime_switch.update(Switch::NONE).unwrap();
+    assert_eq!(runtime_switch.load(), Switch::NONE);
+}
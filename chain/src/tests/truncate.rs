@@ -1,8 +1,9 @@
-use crate::start_chain_services;
+use crate::{start_chain_services, LonelyBlock};
 use ckb_chain_spec::consensus::Consensus;
 use ckb_shared::SharedBuilder;
 use ckb_store::ChainStore;
 use ckb_test_chain_utils::{MockChain, MockStore};
+use ckb_types::{prelude::*, U256};
 use ckb_verification_traits::Switch;
 use std::sync::Arc;
 
@@ -47,3 +48,62 @@ fn test_truncate() {
 
     assert_eq!(shared.snapshot().tip_header(), &target);
 }
+
+#[test]
+fn test_truncate_removes_stale_orphans() {
+    let (shared, mut pack) = SharedBuilder::with_temp_db()
+        .consensus(Consensus::default())
+        .build()
+        .unwrap();
+    let chain_controller = start_chain_services(pack.take_chain_services_builder());
+
+    let genesis = shared.consensus().genesis_block().header();
+    let mock_store = MockStore::new(&genesis, shared.store());
+    let mut mock = MockChain::new(genesis, shared.consensus());
+    for _ in 0..20 {
+        mock.gen_empty_block_with_diff(40u64, &mock_store);
+    }
+    for blk in mock.blocks() {
+        chain_controller
+            .blocking_process_block_with_switch(Arc::new(blk.clone()), Switch::DISABLE_ALL)
+            .unwrap();
+    }
+    let target = mock.blocks()[9].header();
+
+    // orphans on top of the blocks to be truncated, the header of their missing parent is known
+    for _ in 0..3 {
+        mock.gen_empty_block_with_diff(40u64, &mock_store);
+    }
+    let stale_orphans = &mock.blocks()[21..];
+    // orphans on top of a fork of the target
+    let mut fork = MockChain::new(target.clone(), shared.consensus());
+    for _ in 0..2 {
+        fork.gen_empty_block_with_diff(20u64, &mock_store);
+    }
+    for leader in [&mock.blocks()[20], &fork.blocks()[0]] {
+        shared
+            .header_map()
+            .insert((leader.header(), U256::zero()).into());
+    }
+    for orphan in stale_orphans.iter().chain(&fork.blocks()[1..]) {
+        chain_controller.asynchronous_process_lonely_block(LonelyBlock {
+            block: Arc::new(orphan.clone()),
+            switch: Some(Switch::DISABLE_ALL),
+            verify_callback: None,
+        });
+    }
+    assert_eq!(chain_controller.orphan_blocks_len(), 3);
+
+    chain_controller.truncate(target.hash()).unwrap();
+
+    assert_eq!(shared.snapshot().tip_header(), &target);
+    assert_eq!(chain_controller.orphan_blocks_len(), 1);
+    for stale_orphan in stale_orphans {
+        assert!(chain_controller
+            .get_orphan_block(shared.store(), &stale_orphan.hash())
+            .is_none());
+    }
+    assert!(chain_controller
+        .get_orphan_block(shared.store(), &fork.tip().hash())
+        .is_some());
+}
@@ -0,0 +1,53 @@
+use crate::utils::verification_saturation::VerificationSaturation;
+use ckb_channel::{bounded, unbounded};
+
+#[test]
+fn test_verification_saturation_flips_and_clears() {
+    let saturation = VerificationSaturation::default();
+    let (tx, rx) = bounded::<usize>(10);
+    let observe = || saturation.observe(tx.len(), tx.capacity());
+
+    for i in 0..8 {
+        tx.send(i).unwrap();
+        observe();
+    }
+    assert!(!saturation.is_saturated());
+
+    // saturated at 90% of the capacity, and stays so while the channel is full
+    tx.send(8).unwrap();
+    observe();
+    assert!(saturation.is_saturated());
+    tx.send(9).unwrap();
+    assert!(tx.try_send(10).is_err());
+    observe();
+    assert!(saturation.is_saturated());
+
+    // cleared only after the backlog drains below half of the capacity
+    for _ in 0..5 {
+        rx.recv().unwrap();
+        observe();
+    }
+    assert_eq!(rx.len(), 5);
+    assert!(saturation.is_saturated());
+    rx.recv().unwrap();
+    observe();
+    assert!(!saturation.is_saturated());
+
+    // saturated again when the backlog piles up again
+    for i in 0..5 {
+        tx.send(i).unwrap();
+        observe();
+    }
+    assert!(saturation.is_saturated());
+}
+
+#[test]
+fn test_verification_saturation_ignores_unbounded_channel() {
+    let saturation = VerificationSaturation::default();
+    let (tx, _rx) = unbounded::<usize>();
+    for i in 0..100 {
+        tx.send(i).unwrap();
+        saturation.observe(tx.len(), tx.capacity());
+    }
+    assert!(!saturation.is_saturated());
+}
@@ -0,0 +1,25 @@
+//! Backlog metrics of the channels between the chain service threads.
+
+/// Blocks sent by `ChainController` to `ChainService`
+pub(crate) const PROCESS_BLOCK_CHANNEL: &str = "process_block";
+/// Stored blocks sent by `OrphanBroker` to be preloaded
+pub(crate) const PRELOAD_UNVERIFIED_CHANNEL: &str = "preload_unverified";
+/// Preloaded blocks sent to `ConsumeUnverifiedBlocks`
+pub(crate) const UNVERIFIED_CHANNEL: &str = "unverified";
+
+/// Report the current length and capacity of `channel`, called on both ends of the channel
+/// so the gauges follow the backlog as blocks are sent and consumed
+pub(crate) fn observe_channel(channel: &str, len: usize, capacity: Option<usize>) {
+    if let Some(metrics) = ckb_metrics::handle() {
+        metrics
+            .ckb_chain_channel_len
+            .with_label_values(&[channel])
+            .set(len as i64);
+        if let Some(capacity) = capacity {
+            metrics
+                .ckb_chain_channel_capacity
+                .with_label_values(&[channel])
+                .set(capacity as i64);
+        }
+    }
+}
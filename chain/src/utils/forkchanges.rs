@@ -1,6 +1,7 @@
+use ckb_store::ChainStore;
 use ckb_types::core::hardfork::HardForks;
-use ckb_types::core::{BlockExt, BlockView};
-use ckb_types::packed::ProposalShortId;
+use ckb_types::core::{BlockExt, BlockView, HeaderView};
+use ckb_types::packed::{Byte32, ProposalShortId};
 #[cfg(debug_assertions)]
 use is_sorted::IsSorted;
 use std::collections::{HashSet, VecDeque};
@@ -83,3 +84,42 @@ impl ForkChanges {
         }
     }
 }
+
+/// Compute the transactions evicted by a reorg from `old_tip` to `new_tip`.
+///
+/// Returns the hashes of the transactions committed in the detached blocks but absent
+/// from the attached blocks, cellbases are excluded. Returns `None` if any block between
+/// the tips and their common ancestor is missing from the store.
+pub fn reorg_evicted_transactions<S: ChainStore>(
+    store: &S,
+    old_tip: &Byte32,
+    new_tip: &Byte32,
+) -> Option<HashSet<Byte32>> {
+    let mut old_header = store.get_block_header(old_tip)?;
+    let mut new_header = store.get_block_header(new_tip)?;
+    let mut detached = HashSet::new();
+    let mut attached = HashSet::new();
+
+    let step_back = |header: &HeaderView, txs: &mut HashSet<Byte32>| {
+        txs.extend(
+            store
+                .get_block_txs_hashes(&header.hash())
+                .into_iter()
+                .skip(1),
+        );
+        store.get_block_header(&header.parent_hash())
+    };
+
+    while old_header.number() > new_header.number() {
+        old_header = step_back(&old_header, &mut detached)?;
+    }
+    while new_header.number() > old_header.number() {
+        new_header = step_back(&new_header, &mut attached)?;
+    }
+    while old_header.hash() != new_header.hash() {
+        old_header = step_back(&old_header, &mut detached)?;
+        new_header = step_back(&new_header, &mut attached)?;
+    }
+
+    Some(detached.difference(&attached).cloned().collect())
+}
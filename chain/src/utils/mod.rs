@@ -1,2 +1,7 @@
+pub(crate) mod channel_backlog;
 pub mod forkchanges;
 pub mod orphan_block_pool;
+pub mod peer_block_stats;
+pub mod runtime_switch;
+pub mod tip_block_cycles;
+pub mod verification_saturation;
@@ -1,11 +1,13 @@
 use crate::LonelyBlockHash;
 use ckb_logger::debug;
 use ckb_store::{ChainDB, ChainStore};
+use ckb_systemtime::unix_time_as_millis;
 use ckb_types::core::{BlockView, EpochNumber};
 use ckb_types::packed;
 use ckb_util::{parking_lot::RwLock, shrink_to_fit};
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
+use std::time::Duration;
 
 pub type ParentHash = packed::Byte32;
 
@@ -22,6 +24,8 @@ struct InnerPool {
     parents: HashMap<packed::Byte32, ParentHash>,
     // Leaders are blocks not in the orphan pool but having at least a child in the pool.
     leaders: HashSet<ParentHash>,
+    // The time in milliseconds when each block in the pool was inserted.
+    inserted_at: HashMap<packed::Byte32, u64>,
 }
 
 impl InnerPool {
@@ -30,6 +34,7 @@ impl InnerPool {
             blocks: HashMap::with_capacity(capacity),
             parents: HashMap::new(),
             leaders: HashSet::new(),
+            inserted_at: HashMap::new(),
         }
     }
 
@@ -50,6 +55,7 @@ impl InnerPool {
             // and it has at least one child, the new inserted block, so add it to leaders.
             self.leaders.insert(parent_hash.clone());
         }
+        self.inserted_at.insert(hash.clone(), unix_time_as_millis());
         self.parents.insert(hash, parent_hash);
     }
 
@@ -68,6 +74,7 @@ impl InnerPool {
                 let (hashes, blocks): (Vec<_>, Vec<_>) = orphaned.into_iter().unzip();
                 for hash in hashes.iter() {
                     self.parents.remove(hash);
+                    self.inserted_at.remove(hash);
                 }
                 queue.extend(hashes);
                 removed.extend(blocks);
@@ -84,6 +91,7 @@ impl InnerPool {
         shrink_to_fit!(self.blocks, SHRINK_THRESHOLD);
         shrink_to_fit!(self.parents, SHRINK_THRESHOLD);
         shrink_to_fit!(self.leaders, SHRINK_THRESHOLD);
+        shrink_to_fit!(self.inserted_at, SHRINK_THRESHOLD);
         removed
     }
 
@@ -109,6 +117,35 @@ impl InnerPool {
         result
     }
 
+    /// cleanup the leaders whose parent has not arrived within `max_age` since their first
+    /// child was inserted, with all the descendants
+    pub fn evict_expired(&mut self, max_age: Duration) -> Vec<LonelyBlockHash> {
+        let now = unix_time_as_millis();
+        let max_age = max_age.as_millis() as u64;
+        let expired: Vec<ParentHash> = self
+            .leaders
+            .iter()
+            .filter(|leader| self.is_expired(leader, now, max_age))
+            .cloned()
+            .collect();
+        expired
+            .iter()
+            .flat_map(|leader| self.remove_blocks_by_parent(leader))
+            .collect()
+    }
+
+    fn is_expired(&self, parent_hash: &ParentHash, now: u64, max_age: u64) -> bool {
+        self.blocks
+            .get(parent_hash)
+            .map(|children| {
+                children
+                    .keys()
+                    .filter_map(|hash| self.inserted_at.get(hash))
+                    .any(|inserted_at| now.saturating_sub(*inserted_at) > max_age)
+            })
+            .unwrap_or_default()
+    }
+
     /// get 1st block belongs to that parent and check if it's expired block
     fn need_clean(&self, parent_hash: &packed::Byte32, tip_epoch: EpochNumber) -> bool {
         self.blocks
@@ -156,6 +193,11 @@ impl OrphanBlockPool {
         self.inner.write().clean_expired_blocks(epoch)
     }
 
+    /// Remove the orphans whose parent has not arrived within `max_age`
+    pub fn evict_expired(&self, max_age: Duration) -> Vec<LonelyBlockHash> {
+        self.inner.write().evict_expired(max_age)
+    }
+
     pub fn len(&self) -> usize {
         self.inner.read().parents.len()
     }
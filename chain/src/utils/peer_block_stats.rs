@@ -0,0 +1,58 @@
+//! The contribution of every peer to the blocks submitted to the chain service.
+use crate::VerifyResult;
+use ckb_error::is_internal_db_error;
+use ckb_network::PeerIndex;
+use dashmap::DashMap;
+
+/// The number of blocks from a peer which were accepted or rejected by the verification.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PeerBlockCounts {
+    /// Blocks verified successfully, or already known
+    pub accepted: u64,
+    /// Blocks which failed to verify
+    pub rejected: u64,
+}
+
+/// Per-peer block counters, fed by the verify results of the remote blocks.
+///
+/// An internal database error says nothing about the peer, it's not counted.
+#[derive(Default)]
+pub struct PeerBlockStats {
+    inner: DashMap<PeerIndex, PeerBlockCounts>,
+}
+
+impl PeerBlockStats {
+    pub(crate) fn record(&self, peer: PeerIndex, verify_result: &VerifyResult) {
+        match verify_result {
+            Ok(_) => self.inner.entry(peer).or_default().accepted += 1,
+            Err(err) if !is_internal_db_error(err) => {
+                self.inner.entry(peer).or_default().rejected += 1
+            }
+            Err(_) => {}
+        }
+    }
+
+    /// Forget the counters of `peer`, once it's disconnected
+    pub(crate) fn remove(&self, peer: PeerIndex) {
+        self.inner.remove(&peer);
+    }
+
+    /// The counters of `peer`
+    pub fn get(&self, peer: PeerIndex) -> PeerBlockCounts {
+        self.inner
+            .get(&peer)
+            .map(|counts| *counts)
+            .unwrap_or_default()
+    }
+
+    /// The counters of every peer, sorted by peer index
+    pub fn all(&self) -> Vec<(PeerIndex, PeerBlockCounts)> {
+        let mut all: Vec<_> = self
+            .inner
+            .iter()
+            .map(|entry| (*entry.key(), *entry.value()))
+            .collect();
+        all.sort_by_key(|(peer, _)| *peer);
+        all
+    }
+}
@@ -0,0 +1,49 @@
+//! Runtime overrides of the verification phases.
+use ckb_chain_spec::consensus::Consensus;
+use ckb_constant::hardfork::mainnet;
+use ckb_error::{Error, InternalErrorKind};
+use ckb_verification_traits::Switch;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// A `Switch` which can be reloaded at runtime, it is merged into the switch of every
+/// subsequently-verified block.
+///
+/// On mainnet, script, non-contextual and reward verification can never be disabled.
+#[derive(Default)]
+pub struct RuntimeSwitch {
+    bits: AtomicU32,
+    is_mainnet: bool,
+}
+
+impl RuntimeSwitch {
+    pub fn new(consensus: &Consensus) -> Self {
+        RuntimeSwitch {
+            bits: AtomicU32::new(Switch::NONE.bits()),
+            is_mainnet: consensus.id == mainnet::CHAIN_SPEC_NAME,
+        }
+    }
+
+    /// The phases which are not allowed to be disabled on mainnet
+    pub fn mainnet_guarded() -> Switch {
+        Switch::DISABLE_SCRIPT | Switch::DISABLE_NON_CONTEXTUAL | Switch::DISABLE_REWARD
+    }
+
+    /// Current runtime switch
+    pub fn load(&self) -> Switch {
+        Switch::from_bits_truncate(self.bits.load(Ordering::Acquire))
+    }
+
+    /// Replace the runtime switch, refuse to weaken the guarded phases on mainnet
+    pub fn update(&self, switch: Switch) -> Result<(), Error> {
+        if self.is_mainnet && switch.intersects(Self::mainnet_guarded()) {
+            return Err(InternalErrorKind::Config
+                .other(format!(
+                    "refuse to disable {:?} on mainnet",
+                    switch & Self::mainnet_guarded()
+                ))
+                .into());
+        }
+        self.bits.store(switch.bits(), Ordering::Release);
+        Ok(())
+    }
+}
@@ -0,0 +1,27 @@
+//! The total cycles consumed by the verified tip block.
+use ckb_types::{core::Cycle, packed::Byte32};
+use ckb_util::Mutex;
+
+/// Retains the total cycles returned by the contextual verification of the current tip block.
+///
+/// It's empty when the tip's scripts were not verified, e.g. blocks before the assume valid
+/// target, or the tip was set by `truncate`.
+#[derive(Default)]
+pub struct TipBlockCycles {
+    inner: Mutex<Option<(Byte32, Cycle)>>,
+}
+
+impl TipBlockCycles {
+    /// The tip block hash and its total cycles
+    pub fn load(&self) -> Option<(Byte32, Cycle)> {
+        self.inner.lock().clone()
+    }
+
+    pub(crate) fn update(&self, tip_hash: Byte32, cycles: Option<Cycle>) {
+        *self.inner.lock() = cycles.map(|cycles| (tip_hash, cycles));
+    }
+
+    pub(crate) fn clear(&self) {
+        self.inner.lock().take();
+    }
+}
@@ -0,0 +1,54 @@
+//! Backpressure from the block verification to the block download.
+use ckb_logger::warn;
+use ckb_util::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+// warn when the verification stays saturated for this long, and again every such period
+const SATURATION_WARN_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Tracks whether the blocks waiting for the verification pile up in a bounded channel.
+///
+/// It's saturated once the backlog reaches 90% of the capacity, and it's cleared only after
+/// the backlog drains below half of the capacity, so the flag doesn't flap around the limit.
+/// The synchronizer stops fetching more blocks while it's saturated.
+#[derive(Default)]
+pub struct VerificationSaturation {
+    saturated: AtomicBool,
+    // when the saturation started and when it was last warned
+    since: Mutex<Option<(Instant, Instant)>>,
+}
+
+impl VerificationSaturation {
+    /// Whether the verification can't keep up with the received blocks
+    pub fn is_saturated(&self) -> bool {
+        self.saturated.load(Ordering::Acquire)
+    }
+
+    /// Update the flag by the backlog `len` of a channel bounded by `capacity`, called on
+    /// both ends of the channel
+    pub(crate) fn observe(&self, len: usize, capacity: Option<usize>) {
+        let capacity = match capacity {
+            Some(capacity) if capacity > 0 => capacity,
+            _ => return,
+        };
+        let mut since = self.since.lock();
+        if len * 10 >= capacity * 9 {
+            let now = Instant::now();
+            let (started, warned) = since.get_or_insert((now, now));
+            self.saturated.store(true, Ordering::Release);
+            if now.duration_since(*warned) >= SATURATION_WARN_INTERVAL {
+                *warned = now;
+                warn!(
+                    "block verification has been saturated for {:?}, {}/{} blocks waiting, \
+                     slow down the block download",
+                    now.duration_since(*started),
+                    len,
+                    capacity
+                );
+            }
+        } else if len * 2 < capacity && since.take().is_some() {
+            self.saturated.store(false, Ordering::Release);
+        }
+    }
+}
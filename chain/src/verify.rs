@@ -1,3 +1,6 @@
+use crate::utils::channel_backlog::{observe_channel, UNVERIFIED_CHANNEL};
+use crate::utils::runtime_switch::RuntimeSwitch;
+use crate::utils::tip_block_cycles::TipBlockCycles;
 use crate::{delete_unverified_block, UnverifiedBlock};
 use crate::{utils::forkchanges::ForkChanges, GlobalIndex, TruncateRequest, VerifyResult};
 use ckb_channel::{select, Receiver};
@@ -13,15 +16,22 @@ use ckb_store::{attach_block_cell, detach_block_cell, ChainStore, StoreTransacti
 use ckb_systemtime::unix_time_as_millis;
 use ckb_tx_pool::TxPoolController;
 use ckb_types::core::cell::{
-    resolve_transaction, BlockCellProvider, HeaderChecker, OverlayCellProvider, ResolvedTransaction,
+    resolve_transaction, BlockCellProvider, CellProvider, HeaderChecker, OverlayCellProvider,
+    ResolvedTransaction,
+};
+use ckb_types::core::{
+    service::{Request, VerificationPhase},
+    BlockExt, BlockNumber, BlockView, Cycle, HeaderView,
 };
-use ckb_types::core::{service::Request, BlockExt, BlockNumber, BlockView, Cycle, HeaderView};
 use ckb_types::packed::Byte32;
 use ckb_types::utilities::merkle_mountain_range::ChainRootMMR;
 use ckb_types::H256;
 use ckb_verification::cache::Completed;
-use ckb_verification::InvalidParentError;
-use ckb_verification_contextual::{ContextualBlockVerifier, VerifyContext};
+use ckb_verification::{block_rejection, InvalidParentError};
+use ckb_verification_contextual::{
+    ContextualBlockVerifier, ProposalWindowCache, TimeRelativeCache, UncleInclusionCache,
+    VerifyContext,
+};
 use ckb_verification_traits::Switch;
 use dashmap::DashSet;
 use std::cmp;
@@ -32,6 +42,11 @@ pub(crate) struct ConsumeUnverifiedBlockProcessor {
     pub(crate) shared: Shared,
     pub(crate) is_pending_verify: Arc<DashSet<Byte32>>,
     pub(crate) proposal_table: ProposalTable,
+    pub(crate) runtime_switch: Arc<RuntimeSwitch>,
+    pub(crate) tip_block_cycles: Arc<TipBlockCycles>,
+    pub(crate) proposal_window_cache: Arc<ProposalWindowCache>,
+    pub(crate) uncle_inclusion_cache: Arc<UncleInclusionCache>,
+    pub(crate) time_relative_cache: Arc<TimeRelativeCache>,
 }
 
 pub(crate) struct ConsumeUnverifiedBlocks {
@@ -51,6 +66,8 @@ impl ConsumeUnverifiedBlocks {
         truncate_block_rx: Receiver<TruncateRequest>,
         proposal_table: ProposalTable,
         is_pending_verify: Arc<DashSet<Byte32>>,
+        runtime_switch: Arc<RuntimeSwitch>,
+        tip_block_cycles: Arc<TipBlockCycles>,
         stop_rx: Receiver<()>,
     ) -> Self {
         ConsumeUnverifiedBlocks {
@@ -62,6 +79,11 @@ impl ConsumeUnverifiedBlocks {
                 shared,
                 is_pending_verify,
                 proposal_table,
+                runtime_switch,
+                tip_block_cycles,
+                proposal_window_cache: Default::default(),
+                uncle_inclusion_cache: Default::default(),
+                time_relative_cache: Default::default(),
             },
         }
     }
@@ -72,6 +94,7 @@ impl ConsumeUnverifiedBlocks {
             select! {
                 recv(self.unverified_block_rx) -> msg => match msg {
                     Ok(unverified_task) => {
+                        observe_channel(UNVERIFIED_CHANNEL, self.unverified_block_rx.len(), self.unverified_block_rx.capacity());
                         // process this unverified block
                         if let Some(handle) = ckb_metrics::handle() {
                             handle.ckb_chain_consume_unverified_block_waiting_block_duration.observe(trace_begin_loop.elapsed().as_secs_f64())
@@ -223,6 +246,8 @@ impl ConsumeUnverifiedBlockProcessor {
                 None => Switch::NONE,
             }
         });
+        // merge the phases disabled at runtime
+        let switch = switch | self.runtime_switch.load();
 
         let block_hash = block.hash();
         let parent_hash = block.parent_hash();
@@ -293,6 +318,7 @@ impl ConsumeUnverifiedBlockProcessor {
         let new_best_block = cannon_total_difficulty > current_total_difficulty;
 
         let mut fork = ForkChanges::default();
+        let mut tip_cycles = None;
 
         let next_block_epoch = self
             .shared
@@ -330,7 +356,7 @@ impl ConsumeUnverifiedBlockProcessor {
             // update and verify chain root
             // MUST update index before reconcile_main_chain
             let begin_reconcile_main_chain = std::time::Instant::now();
-            self.reconcile_main_chain(Arc::clone(&db_txn), &mut fork, switch)?;
+            tip_cycles = self.reconcile_main_chain(Arc::clone(&db_txn), &mut fork, switch)?;
             trace!(
                 "reconcile_main_chain cost {:?}",
                 begin_reconcile_main_chain.elapsed()
@@ -367,6 +393,7 @@ impl ConsumeUnverifiedBlockProcessor {
                     .new_snapshot(tip_header, cannon_total_difficulty, epoch, new_proposals);
 
             self.shared.store_snapshot(Arc::clone(&new_snapshot));
+            self.tip_block_cycles.update(tip_header.hash(), tip_cycles);
 
             let tx_pool_controller = self.shared.tx_pool_controller();
             if tx_pool_controller.service_started() {
@@ -593,14 +620,16 @@ impl ConsumeUnverifiedBlockProcessor {
     }
 
     // we found new best_block
+    //
+    // Returns the total cycles of the new tip block, if its scripts are verified here
     pub(crate) fn reconcile_main_chain(
         &self,
         txn: Arc<StoreTransaction>,
         fork: &mut ForkChanges,
         switch: Switch,
-    ) -> Result<(), Error> {
+    ) -> Result<Option<Cycle>, Error> {
         if fork.attached_blocks().is_empty() {
-            return Ok(());
+            return Ok(None);
         }
 
         let txs_verify_cache = self.shared.txs_verify_cache();
@@ -633,6 +662,7 @@ impl ConsumeUnverifiedBlockProcessor {
         let verify_context = VerifyContext::new(Arc::clone(&txn), consensus);
 
         let mut found_error = None;
+        let mut tip_cycles = None;
         for (ext, b) in fork
             .dirty_exts
             .iter()
@@ -641,7 +671,7 @@ impl ConsumeUnverifiedBlockProcessor {
             if !switch.disable_all() {
                 if found_error.is_none() {
                     let log_now = std::time::Instant::now();
-                    let resolved = self.resolve_block_transactions(&txn, b, &verify_context);
+                    let resolved = resolve_block_transactions(b, txn.as_ref(), &verify_context);
                     debug!(
                         "resolve_block_transactions {} cost: {:?}",
                         b.hash(),
@@ -656,7 +686,10 @@ impl ConsumeUnverifiedBlockProcessor {
                                     switch,
                                     Arc::clone(&txs_verify_cache),
                                     &mmr,
-                                );
+                                )
+                                .proposal_window_cache(Arc::clone(&self.proposal_window_cache))
+                                .uncle_inclusion_cache(Arc::clone(&self.uncle_inclusion_cache))
+                                .time_relative_cache(Arc::clone(&self.time_relative_cache));
                                 let log_now = std::time::Instant::now();
                                 let verify_result = contextual_block_verifier.verify(&resolved, b);
                                 debug!(
@@ -668,6 +701,7 @@ impl ConsumeUnverifiedBlockProcessor {
                             };
                             match verified {
                                 Ok((cycles, cache_entries)) => {
+                                    tip_cycles = (!switch.disable_script()).then_some(cycles);
                                     let txs_sizes = resolved
                                         .iter()
                                         .map(|rtx| {
@@ -698,12 +732,14 @@ impl ConsumeUnverifiedBlockProcessor {
                                 }
                                 Err(err) => {
                                     self.print_error(b, &err);
+                                    self.notify_block_rejection(b, &err);
                                     found_error = Some(err);
                                     self.insert_failure_ext(&txn, &b.header().hash(), ext.clone())?;
                                 }
                             }
                         }
                         Err(err) => {
+                            self.notify_block_rejection(b, &err);
                             found_error = Some(err);
                             self.insert_failure_ext(&txn, &b.header().hash(), ext.clone())?;
                         }
@@ -717,6 +753,7 @@ impl ConsumeUnverifiedBlockProcessor {
                 mmr.push(b.digest())
                     .map_err(|e| InternalErrorKind::MMR.other(e))?;
                 self.insert_ok_ext(&txn, &b.header().hash(), ext.clone(), None, None)?;
+                tip_cycles = None;
             }
         }
 
@@ -726,31 +763,10 @@ impl ConsumeUnverifiedBlockProcessor {
             trace!("light-client: commit");
             // Before commit, all new MMR nodes are in memory only.
             mmr.commit().map_err(|e| InternalErrorKind::MMR.other(e))?;
-            Ok(())
+            Ok(tip_cycles)
         }
     }
 
-    fn resolve_block_transactions<HC: HeaderChecker>(
-        &self,
-        txn: &StoreTransaction,
-        block: &BlockView,
-        verify_context: &HC,
-    ) -> Result<Vec<Arc<ResolvedTransaction>>, Error> {
-        let mut seen_inputs = HashSet::new();
-        let block_cp = BlockCellProvider::new(block)?;
-        let transactions = block.transactions();
-        let cell_provider = OverlayCellProvider::new(&block_cp, txn);
-        let resolved = transactions
-            .iter()
-            .cloned()
-            .map(|tx| {
-                resolve_transaction(tx, &mut seen_inputs, &cell_provider, verify_context)
-                    .map(Arc::new)
-            })
-            .collect::<Result<Vec<Arc<ResolvedTransaction>>, _>>()?;
-        Ok(resolved)
-    }
-
     fn insert_ok_ext(
         &self,
         txn: &StoreTransaction,
@@ -826,6 +842,15 @@ impl ConsumeUnverifiedBlockProcessor {
         }
     }
 
+    fn notify_block_rejection(&self, b: &BlockView, err: &Error) {
+        if is_internal_db_error(err) {
+            return;
+        }
+        self.shared
+            .notify_controller()
+            .notify_block_rejection(block_rejection(b, VerificationPhase::Contextual, err));
+    }
+
     // TODO: beatify
     fn print_chain(&self, len: u64) {
         debug!("Chain {{");
@@ -898,6 +923,7 @@ impl ConsumeUnverifiedBlockProcessor {
         );
 
         self.shared.store_snapshot(Arc::clone(&new_snapshot));
+        self.tip_block_cycles.clear();
 
         Ok(())
     }
@@ -910,3 +936,23 @@ fn is_sorted_assert(fork: &ForkChanges) {
 
 #[cfg(not(debug_assertions))]
 fn is_sorted_assert(_fork: &ForkChanges) {}
+
+/// Resolve the transactions of `block` against `cell_provider`, the cells created by the
+/// block itself are visible to its later transactions.
+pub(crate) fn resolve_block_transactions<CP: CellProvider, HC: HeaderChecker>(
+    block: &BlockView,
+    cell_provider: &CP,
+    header_checker: &HC,
+) -> Result<Vec<Arc<ResolvedTransaction>>, Error> {
+    let mut seen_inputs = HashSet::new();
+    let block_cp = BlockCellProvider::new(block)?;
+    let cell_provider = OverlayCellProvider::new(&block_cp, cell_provider);
+    let resolved = block
+        .transactions()
+        .into_iter()
+        .map(|tx| {
+            resolve_transaction(tx, &mut seen_inputs, &cell_provider, header_checker).map(Arc::new)
+        })
+        .collect::<Result<Vec<Arc<ResolvedTransaction>>, _>>()?;
+    Ok(resolved)
+}
@@ -7,10 +7,15 @@ use ckb_stop_handler::{
     broadcast_exit_signals, new_crossbeam_exit_rx, register_thread, wait_all_ckb_services_exit,
 };
 use std::thread;
+use std::time::Duration;
 
 pub fn miner(args: MinerArgs, async_handle: Handle) -> Result<(), ExitCode> {
     let (new_work_tx, new_work_rx) = unbounded();
-    let MinerConfig { client, workers } = args.config;
+    let MinerConfig {
+        client,
+        workers,
+        stale_grace_millis,
+    } = args.config;
 
     let client = Client::new(new_work_tx, client, async_handle);
     let mut miner = Miner::new(
@@ -19,6 +24,7 @@ pub fn miner(args: MinerArgs, async_handle: Handle) -> Result<(), ExitCode> {
         new_work_rx,
         &workers,
         args.limit,
+        Duration::from_millis(stale_grace_millis),
     );
 
     ckb_memory_tracker::track_current_process_simple(args.memory_tracker.interval);
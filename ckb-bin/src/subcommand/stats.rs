@@ -1,7 +1,7 @@
 use ckb_app_config::{ExitCode, StatsArgs};
 use ckb_async_runtime::Handle;
 use ckb_shared::{Shared, SharedBuilder};
-use ckb_store::ChainStore;
+use ckb_store::{find_epoch_regression, ChainStore};
 use ckb_types::{
     core::{BlockNumber, ScriptHashType},
     packed::CellbaseWitness,
@@ -15,6 +15,7 @@ pub fn stats(args: StatsArgs, async_handle: Handle) -> Result<(), ExitCode> {
     let stats = Statics::build(args, async_handle)?;
     stats.print_uncle_rate()?;
     stats.print_miner_statics()?;
+    stats.print_epoch_consistency();
     Ok(())
 }
 
@@ -134,4 +135,17 @@ impl Statics {
         }
         Ok(())
     }
+
+    fn print_epoch_consistency(&self) {
+        match find_epoch_regression(self.shared.store(), self.from, self.to) {
+            Some(regression) => println!(
+                "epoch_consistency: block({}-{}) epoch {} is less than its parent's epoch {}",
+                regression.number,
+                regression.hash,
+                regression.epoch.number(),
+                regression.parent_epoch.number()
+            ),
+            None => println!("epoch_consistency: ok"),
+        }
+    }
 }
@@ -129,6 +129,15 @@ impl RocksDB {
         Self::open_with_check(&config, columns).unwrap_or_else(|err| panic!("{err}"))
     }
 
+    /// Same as `open_in`, but returns the error instead of panicking.
+    pub fn try_open_in<P: AsRef<Path>>(path: P, columns: u32) -> Result<Self> {
+        let config = DBConfig {
+            path: path.as_ref().to_path_buf(),
+            ..Default::default()
+        };
+        Self::open_with_check(&config, columns)
+    }
+
     /// Set appropriate parameters for bulk loading.
     pub fn prepare_for_bulk_load_open<P: AsRef<Path>>(
         path: P,
@@ -51,6 +51,30 @@ pub enum RpcError {
     NoRespData,
 }
 
+/// Why the node rejected a submitted block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SubmitRejection {
+    /// The block doesn't extend a chain the node knows, or the node can't be reached, a
+    /// template from the current tip is needed
+    StaleTip,
+    /// The node verified the block and rejected it, sealing the same template again is
+    /// pointless
+    InvalidTemplate,
+}
+
+impl SubmitRejection {
+    pub(crate) fn classify(err: &RpcError) -> Self {
+        match err {
+            RpcError::Fail(RpcFail { message, .. })
+                if !message.contains("not found") && !message.contains("UnknownParent") =>
+            {
+                SubmitRejection::InvalidTemplate
+            }
+            _ => SubmitRejection::StaleTip,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Rpc {
     sender: mpsc::Sender<RpcRequest>,
@@ -137,7 +161,8 @@ impl Rpc {
 
 pub enum Works {
     New(Work),
-    FailSubmit(Byte32),
+    /// The submission of a block on the parent failed
+    FailSubmit(Byte32, RpcError),
 }
 
 /// TODO(doc): @quake
@@ -184,7 +209,15 @@ impl Client {
         let parent = block.header().raw().parent_hash();
         let future = self
             .send_submit_block_request(work_id, block)
-            .and_then(parse_response::<H256>);
+            .and_then(parse_response::<H256>)
+            .inspect_ok(|_| {
+                if let Some(metrics) = ckb_metrics::handle() {
+                    metrics
+                        .ckb_miner_blocks
+                        .with_label_values(&["accepted"])
+                        .inc();
+                }
+            });
 
         if self.config.block_on_submit {
             self.handle.block_on(future).map(|_| ())
@@ -192,8 +225,7 @@ impl Client {
             let sender = self.new_work_tx.clone();
             self.handle.spawn(async move {
                 if let Err(e) = future.await {
-                    error!("rpc call submit_block error: {:?}", e);
-                    sender.send(Works::FailSubmit(parent)).unwrap()
+                    sender.send(Works::FailSubmit(parent, e)).unwrap()
                 }
             });
             Ok(())
@@ -304,6 +336,16 @@ Otherwise ckb-miner will malfunction and stop submitting valid blocks after a ce
             .fetch_update(Ordering::SeqCst, Ordering::SeqCst, updated)
             .is_ok()
         {
+            let cycles = block_template.total_cycles.value();
+            let cycles_limit = block_template.cycles_limit.value().max(1);
+            debug!(
+                "new work #{}: txs-{} cycles {}/{} ({:.1}%)",
+                block_template.number.value(),
+                block_template.transactions.len(),
+                cycles,
+                cycles_limit,
+                cycles as f64 * 100.0 / cycles_limit as f64
+            );
             let work: Work = block_template.into();
             if let Err(e) = self.new_work_tx.send(Works::New(work)) {
                 error!("notify_new_block error: {:?}", e);
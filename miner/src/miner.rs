@@ -1,22 +1,26 @@
-use crate::client::{Client, Works};
+use crate::client::{Client, RpcError, SubmitRejection, Works};
 use crate::worker::{start_worker, WorkerController, WorkerMessage};
 use crate::Work;
 use ckb_app_config::MinerWorkerConfig;
-use ckb_channel::{select, unbounded, Receiver};
-use ckb_logger::{debug, error, info};
+use ckb_channel::{after, never, select, unbounded, Receiver};
+use ckb_logger::{debug, error, info, warn};
 use ckb_pow::PowEngine;
 use ckb_stop_handler::broadcast_exit_signals;
 use ckb_types::{
     packed::{Byte32, Header},
     prelude::*,
-    utilities::compact_to_target,
+    utilities::{compact_to_difficulty, compact_to_target},
 };
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use lru::LruCache;
 use std::sync::Arc;
 use std::thread;
+use std::time::{Duration, Instant};
 
 const WORK_CACHE_SIZE: usize = 32;
+// the delay after the first rejected submission, doubled by every consecutive one
+const SUBMIT_BACKOFF_BASE: Duration = Duration::from_millis(100);
+const SUBMIT_BACKOFF_MAX: Duration = Duration::from_secs(30);
 
 /// TODO(doc): @quake
 pub struct Miner {
@@ -31,6 +35,18 @@ pub struct Miner {
     pub(crate) nonces_found: u128,
     pub(crate) stderr_is_tty: bool,
     pub(crate) limit: u128,
+    /// How long the workers keep sealing a template after it becomes stale
+    pub(crate) stale_grace: Duration,
+    /// The parent hash of the work the workers are sealing
+    pub(crate) current_parent: Option<Byte32>,
+    /// The latest work deferred by the grace window, and when to switch to it
+    pub(crate) pending_work: Option<(Work, Instant)>,
+    /// The number of submissions rejected since the last accepted one or the last new tip
+    pub(crate) consecutive_rejections: u32,
+    /// Whether the workers are stopped until a new work arrives, the current one is invalid
+    pub(crate) halted: bool,
+    /// The latest rejected submission, and when to fetch a new template after backing off
+    pub(crate) pending_retry: Option<(SubmitRejection, Instant)>,
 }
 
 impl Miner {
@@ -41,6 +57,7 @@ impl Miner {
         work_rx: Receiver<Works>,
         workers: &[MinerWorkerConfig],
         limit: u128,
+        stale_grace: Duration,
     ) -> Miner {
         let (nonce_tx, nonce_rx) = unbounded();
         let mp = MultiProgress::new();
@@ -70,19 +87,31 @@ impl Miner {
             pb,
             stderr_is_tty,
             limit,
+            stale_grace,
+            current_parent: None,
+            pending_work: None,
+            consecutive_rejections: 0,
+            halted: false,
+            pending_retry: None,
         }
     }
 
     /// TODO(doc): @quake
     pub fn run(&mut self, stop_rx: Receiver<()>) {
         loop {
+            let grace_timeout = match self.pending_work {
+                Some((_, deadline)) => after(deadline.saturating_duration_since(Instant::now())),
+                None => never(),
+            };
+            let retry_timeout = match self.pending_retry {
+                Some((_, deadline)) => after(deadline.saturating_duration_since(Instant::now())),
+                None => never(),
+            };
             select! {
                 recv(self.work_rx) -> msg => match msg {
                     Ok(work) => {
                         match work {
-                            Works::FailSubmit(hash) => {
-                                self.legacy_work.pop(&hash);
-                            },
+                            Works::FailSubmit(hash, err) => self.on_submit_rejected(hash, err),
                             Works::New(work) => self.notify_new_work(work),
                         }
                     },
@@ -94,6 +123,10 @@ impl Miner {
                 recv(self.nonce_rx) -> msg => match msg {
                     Ok((pow_hash, work, nonce)) => {
                         self.submit_nonce(pow_hash, work, nonce);
+                        // the stale template is sealed, no need to wait for the grace window
+                        if let Some((work, _)) = self.pending_work.take() {
+                            self.switch_work(work);
+                        }
                         if self.limit != 0 && self.nonces_found >= self.limit {
                             debug!("miner nonce limit reached, terminate ...");
                             broadcast_exit_signals();
@@ -104,6 +137,17 @@ impl Miner {
                         break;
                     },
                 },
+                recv(grace_timeout) -> _ => {
+                    if let Some((work, _)) = self.pending_work.take() {
+                        debug!("stale grace window elapsed, switch to the new work");
+                        self.switch_work(work);
+                    }
+                },
+                recv(retry_timeout) -> _ => {
+                    if let Some((rejection, _)) = self.pending_retry.take() {
+                        self.retry_rejected(rejection);
+                    }
+                },
                 recv(stop_rx) -> _msg => {
                     info!("miner received exit signal, stopped");
                     break;
@@ -114,15 +158,61 @@ impl Miner {
 
     fn notify_new_work(&mut self, work: Work) {
         let parent_hash = work.block.header().into_view().parent_hash();
-        if !self.legacy_work.contains(&parent_hash) {
-            let pow_hash = work.block.header().calc_pow_hash();
-            let (target, _) =
-                compact_to_target(work.block.header().raw().compact_target().unpack());
-            self.notify_workers(WorkerMessage::NewWork {
-                pow_hash,
-                work,
-                target,
-            });
+        if self.legacy_work.contains(&parent_hash) {
+            return;
+        }
+
+        // A work on another parent makes the current one stale, keep sealing the stale one
+        // until the grace window elapses, a newer work replaces the pending one but the
+        // deadline is kept.
+        let is_stale = matches!(&self.current_parent, Some(current) if current != &parent_hash);
+        if is_stale && !self.stale_grace.is_zero() {
+            let deadline = match self.pending_work.take() {
+                Some((_, deadline)) => deadline,
+                None => {
+                    debug!(
+                        "current work becomes stale, keep sealing it for {:?}",
+                        self.stale_grace
+                    );
+                    Instant::now() + self.stale_grace
+                }
+            };
+            self.pending_work = Some((work, deadline));
+            return;
+        }
+
+        self.pending_work = None;
+        self.switch_work(work);
+    }
+
+    fn switch_work(&mut self, work: Work) {
+        let pow_hash = work.block.header().calc_pow_hash();
+        let compact_target: u32 = work.block.header().raw().compact_target().unpack();
+        let (target, _) = compact_to_target(compact_target);
+        if let Some(metrics) = ckb_metrics::handle() {
+            let difficulty = compact_to_difficulty(compact_target);
+            // U256 limbs are little endian
+            let difficulty = difficulty
+                .0
+                .iter()
+                .rev()
+                .fold(0f64, |acc, limb| acc * 2f64.powi(64) + *limb as f64);
+            metrics.ckb_miner_target_difficulty.set(difficulty);
+        }
+        let parent_hash = work.block.header().into_view().parent_hash();
+        if self.current_parent.as_ref() != Some(&parent_hash) {
+            // the node moved on to a new tip, it isn't the one rejecting everything
+            self.consecutive_rejections = 0;
+        }
+        self.current_parent = Some(parent_hash);
+        self.notify_workers(WorkerMessage::NewWork {
+            pow_hash,
+            work,
+            target,
+        });
+        if self.halted {
+            self.halted = false;
+            self.notify_workers(WorkerMessage::Start);
         }
     }
 
@@ -148,6 +238,9 @@ impl Miner {
                 pow_hash,
                 block.header()
             );
+            if let Some(metrics) = ckb_metrics::handle() {
+                metrics.ckb_miner_blocks.with_label_values(&["stale"]).inc();
+            }
             self.notify_workers(WorkerMessage::Start);
             return;
         } else {
@@ -168,15 +261,25 @@ impl Miner {
 
         // submit block and poll new work
         {
-            if let Err(e) = self
+            if let Some(metrics) = ckb_metrics::handle() {
+                metrics
+                    .ckb_miner_blocks
+                    .with_label_values(&["submitted"])
+                    .inc();
+            }
+            match self
                 .client
                 .submit_block(&work.work_id.to_string(), block.data())
             {
-                self.legacy_work.pop(&block.parent_hash());
-                error!("rpc call submit_block error: {:?}", e);
+                Ok(()) => {
+                    if self.client.config.block_on_submit {
+                        self.consecutive_rejections = 0;
+                    }
+                    self.client.blocking_fetch_block_template();
+                    self.notify_workers(WorkerMessage::Start);
+                }
+                Err(err) => self.on_submit_rejected(block.parent_hash(), err),
             }
-            self.client.blocking_fetch_block_template();
-            self.notify_workers(WorkerMessage::Start);
         }
 
         // draw progress bar
@@ -190,9 +293,100 @@ impl Miner {
         }
     }
 
+    // A stale tip only needs a template from the new tip, while an invalid template is not
+    // sealed again, the workers stay stopped until the node provides another work. Either way
+    // the miner backs off exponentially with the consecutive rejections before going on, the
+    // retry is scheduled in the `run` loop, so the exit signal and new works are still handled
+    // meanwhile.
+    fn on_submit_rejected(&mut self, parent_hash: Byte32, err: RpcError) {
+        self.legacy_work.pop(&parent_hash);
+        self.consecutive_rejections = self.consecutive_rejections.saturating_add(1);
+        if let Some(metrics) = ckb_metrics::handle() {
+            metrics
+                .ckb_miner_blocks
+                .with_label_values(&["rejected"])
+                .inc();
+        }
+        let backoff = submit_backoff(self.consecutive_rejections);
+        let rejection = SubmitRejection::classify(&err);
+        match rejection {
+            SubmitRejection::StaleTip => {
+                warn!(
+                    "submit_block rejected for a stale tip: {:?}, rebuild the template in {:?}",
+                    err, backoff
+                );
+            }
+            SubmitRejection::InvalidTemplate => {
+                error!(
+                    "submit_block rejected the template: {:?}, stop sealing it, fetch a new one in {:?}",
+                    err, backoff
+                );
+                self.notify_workers(WorkerMessage::Stop);
+                self.halted = true;
+            }
+        }
+        self.pending_retry = Some((rejection, Instant::now() + backoff));
+    }
+
+    fn retry_rejected(&mut self, rejection: SubmitRejection) {
+        self.client.blocking_fetch_block_template();
+        if rejection == SubmitRejection::StaleTip {
+            self.notify_workers(WorkerMessage::Start);
+        }
+    }
+
     fn notify_workers(&self, message: WorkerMessage) {
         for controller in self.worker_controllers.iter() {
             controller.send_message(message.clone());
         }
     }
 }
+
+/// The delay before going on after `rejections` consecutive rejected submissions
+pub(crate) fn submit_backoff(rejections: u32) -> Duration {
+    if rejections == 0 {
+        return Duration::ZERO;
+    }
+    SUBMIT_BACKOFF_BASE
+        .saturating_mul(1 << (rejections - 1).min(16))
+        .min(SUBMIT_BACKOFF_MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonrpc_core::error::{Error as RpcFail, ErrorCode};
+
+    fn rpc_fail(message: &str) -> RpcError {
+        RpcError::Fail(RpcFail {
+            code: ErrorCode::ServerError(-302),
+            message: message.to_string(),
+            data: None,
+        })
+    }
+
+    #[test]
+    fn test_submit_rejection_classify() {
+        assert_eq!(
+            SubmitRejection::classify(&rpc_fail("Block parent 0x01 of 2-0x02 not found")),
+            SubmitRejection::StaleTip
+        );
+        assert_eq!(
+            SubmitRejection::classify(&RpcError::NoRespData),
+            SubmitRejection::StaleTip
+        );
+        assert_eq!(
+            SubmitRejection::classify(&rpc_fail("Invalid: Block(InvalidDAO)")),
+            SubmitRejection::InvalidTemplate
+        );
+    }
+
+    #[test]
+    fn test_submit_backoff() {
+        assert_eq!(submit_backoff(0), Duration::ZERO);
+        assert_eq!(submit_backoff(1), SUBMIT_BACKOFF_BASE);
+        assert_eq!(submit_backoff(2), SUBMIT_BACKOFF_BASE * 2);
+        assert_eq!(submit_backoff(4), SUBMIT_BACKOFF_BASE * 8);
+        assert_eq!(submit_backoff(u32::MAX), SUBMIT_BACKOFF_MAX);
+    }
+}
@@ -100,6 +100,11 @@ impl Dummy {
         loop {
             thread::sleep(Duration::from_millis(10));
             if instant.elapsed() > delay {
+                // the dummy worker "hashes" once per sealed block
+                if let Some(metrics) = ckb_metrics::handle() {
+                    metrics.ckb_miner_hash_attempts.inc();
+                    metrics.ckb_miner_nonces_found.inc();
+                }
                 if let Err(err) = self.nonce_tx.send((pow_hash, work, nonce)) {
                     error!("nonce_tx send error {:?}", err);
                 }
@@ -133,3 +138,75 @@ impl Worker for Dummy {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ckb_channel::unbounded;
+    use ckb_pow::Pow;
+    use ckb_types::{
+        core::{BlockBuilder, BlockView},
+        packed::Header,
+        prelude::*,
+        U256,
+    };
+
+    // Start a dummy worker sealing `block` without delay, only the worker thread collects the
+    // metrics when `collect_metrics`, the other tests of the process are unaffected
+    fn start_dummy_worker(
+        block: &BlockView,
+        collect_metrics: bool,
+    ) -> Receiver<(Byte32, Work, u128)> {
+        let (nonce_tx, nonce_rx) = unbounded();
+        let (worker_tx, worker_rx) = unbounded();
+        let mut worker =
+            Dummy::try_new(&DummyConfig::Constant { value: 0 }, nonce_tx, worker_rx).unwrap();
+        thread::spawn(move || {
+            if collect_metrics {
+                ckb_metrics::testing::enable_in_current_thread();
+            }
+            worker.run(rand::random, ProgressBar::hidden())
+        });
+
+        worker_tx
+            .send(WorkerMessage::NewWork {
+                pow_hash: block.header().data().calc_pow_hash(),
+                work: Work {
+                    work_id: 0,
+                    block: block.data(),
+                },
+                target: U256::max_value(),
+            })
+            .unwrap();
+        nonce_rx
+    }
+
+    #[test]
+    fn test_dummy_worker_seals_block() {
+        let block = BlockBuilder::default().build();
+        let nonce_rx = start_dummy_worker(&block, false);
+        let (pow_hash, work, nonce) = nonce_rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert_eq!(pow_hash, block.header().data().calc_pow_hash());
+
+        let header = Header::new_builder()
+            .raw(work.block.header().raw())
+            .nonce(nonce.pack())
+            .build();
+        assert!(Pow::Dummy.engine().verify(&header));
+    }
+
+    #[test]
+    fn test_dummy_worker_metrics() {
+        let metrics = ckb_metrics::testing::enable_in_current_thread();
+        let attempts = metrics.ckb_miner_hash_attempts.get();
+        let nonces_found = metrics.ckb_miner_nonces_found.get();
+
+        let block = BlockBuilder::default().build();
+        let nonce_rx = start_dummy_worker(&block, true);
+        for _ in 0..2 {
+            nonce_rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        }
+        assert!(metrics.ckb_miner_hash_attempts.get() >= attempts + 2);
+        assert!(metrics.ckb_miner_nonces_found.get() >= nonces_found + 2);
+    }
+}
@@ -12,6 +12,7 @@ use std::thread;
 use std::time::{Duration, Instant};
 
 pub struct EaglesongSimple {
+    name: String,
     start: bool,
     pow_work: Option<(Byte32, Work)>,
     target: U256,
@@ -23,11 +24,13 @@ pub struct EaglesongSimple {
 
 impl EaglesongSimple {
     pub fn new(
+        name: String,
         nonce_tx: Sender<(Byte32, Work, u128)>,
         worker_rx: Receiver<WorkerMessage>,
         extra_hash_function: Option<ExtraHashFunction>,
     ) -> Self {
         Self {
+            name,
             start: true,
             pow_work: None,
             target: U256::zero(),
@@ -79,6 +82,9 @@ impl EaglesongSimple {
                 error!("nonce_tx send error {:?}", err);
             }
             self.nonces_found += 1;
+            if let Some(metrics) = ckb_metrics::handle() {
+                metrics.ckb_miner_nonces_found.inc();
+            }
         }
     }
 }
@@ -102,11 +108,20 @@ impl Worker for EaglesongSimple {
                             + u64::from(elapsed.subsec_nanos()))
                             as f64
                             / 1_000_000_000.0;
+                        let hash_rate = state_update_counter as f64 / elapsed_nanos;
                         progress_bar.set_message(format!(
                             "hash rate: {:>10.3} / nonces found: {:>10}",
-                            state_update_counter as f64 / elapsed_nanos,
-                            self.nonces_found,
+                            hash_rate, self.nonces_found,
                         ));
+                        if let Some(metrics) = ckb_metrics::handle() {
+                            metrics
+                                .ckb_miner_hash_attempts
+                                .inc_by(state_update_counter as u64);
+                            metrics
+                                .ckb_miner_hash_rate
+                                .with_label_values(&[&self.name])
+                                .set(hash_rate);
+                        }
                         progress_bar.inc(1);
                         state_update_counter = 0;
                         start = Instant::now();
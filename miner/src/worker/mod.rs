@@ -4,7 +4,7 @@ mod eaglesong_simple;
 use crate::Work;
 use ckb_app_config::MinerWorkerConfig;
 use ckb_channel::{unbounded, Sender};
-use ckb_logger::error;
+use ckb_logger::{error, info};
 use ckb_pow::{DummyPowEngine, EaglesongBlake2bPowEngine, EaglesongPowEngine, PowEngine};
 use ckb_types::{packed::Byte32, U256};
 use dummy::Dummy;
@@ -72,6 +72,7 @@ pub fn start_worker(
     match config {
         MinerWorkerConfig::Dummy(config) => {
             if pow.as_any().downcast_ref::<DummyPowEngine>().is_some() {
+                info!("Start the Dummy worker, blocks are sealed without solving PoW");
                 let worker_name = "Dummy-Worker";
                 let pb = mp.add(ProgressBar::new(100));
                 pb.set_style(ProgressStyle::default_bar().template(PROGRESS_BAR_TEMPLATE));
@@ -100,6 +101,7 @@ pub fn start_worker(
                     .downcast_ref::<EaglesongBlake2bPowEngine>()
                     .is_some()
             {
+                info!("Start {} EaglesongSimple worker(s)", config.threads);
                 let worker_txs = (0..config.threads)
                     .map(|i| {
                         let worker_name = format!("EaglesongSimple-Worker-{i}");
@@ -113,10 +115,14 @@ pub fn start_worker(
                         let (worker_tx, worker_rx) = unbounded();
                         let nonce_tx = nonce_tx.clone();
                         thread::Builder::new()
-                            .name(worker_name)
+                            .name(worker_name.clone())
                             .spawn(move || {
-                                let mut worker =
-                                    EaglesongSimple::new(nonce_tx, worker_rx, extra_hash_function);
+                                let mut worker = EaglesongSimple::new(
+                                    worker_name,
+                                    nonce_tx,
+                                    worker_rx,
+                                    extra_hash_function,
+                                );
                                 let rng = nonce_generator(nonce_range);
                                 worker.run(rng, pb);
                             })
@@ -5,7 +5,7 @@ use ckb_logger::{debug, error, info, trace};
 use ckb_stop_handler::{new_tokio_exit_rx, CancellationToken};
 use ckb_types::packed::Byte32;
 use ckb_types::{
-    core::{tx_pool::Reject, BlockView},
+    core::{service::BlockRejection, tx_pool::Reject, BlockView},
     packed::Alert,
 };
 use std::{collections::HashMap, time::Duration};
@@ -99,6 +99,8 @@ pub struct NotifyController {
     reject_transaction_notifier: Sender<(PoolTransactionEntry, Reject)>,
     network_alert_register: NotifyRegister<Alert>,
     network_alert_notifier: Sender<Alert>,
+    block_rejection_register: NotifyRegister<BlockRejection>,
+    block_rejection_notifier: Sender<BlockRejection>,
     handle: Handle,
 }
 
@@ -111,6 +113,7 @@ pub struct NotifyService {
     proposed_transaction_subscribers: HashMap<String, Sender<PoolTransactionEntry>>,
     reject_transaction_subscribers: HashMap<String, Sender<(PoolTransactionEntry, Reject)>>,
     network_alert_subscribers: HashMap<String, Sender<Alert>>,
+    block_rejection_subscribers: HashMap<String, Sender<BlockRejection>>,
     timeout: NotifyTimeout,
     handle: Handle,
 }
@@ -128,6 +131,7 @@ impl NotifyService {
             proposed_transaction_subscribers: HashMap::default(),
             reject_transaction_subscribers: HashMap::default(),
             network_alert_subscribers: HashMap::default(),
+            block_rejection_subscribers: HashMap::default(),
             timeout,
             handle,
         }
@@ -163,6 +167,11 @@ impl NotifyService {
             mpsc::channel(REGISTER_CHANNEL_SIZE);
         let (network_alert_sender, mut network_alert_receiver) = mpsc::channel(NOTIFY_CHANNEL_SIZE);
 
+        let (block_rejection_register, mut block_rejection_register_receiver) =
+            mpsc::channel(REGISTER_CHANNEL_SIZE);
+        let (block_rejection_sender, mut block_rejection_receiver) =
+            mpsc::channel(NOTIFY_CHANNEL_SIZE);
+
         handle.spawn(async move {
             loop {
                 tokio::select! {
@@ -177,6 +186,8 @@ impl NotifyService {
                     Some(msg) = reject_transaction_receiver.recv() => { self.handle_notify_reject_transaction(msg) },
                     Some(msg) = network_alert_register_receiver.recv() => { self.handle_register_network_alert(msg) },
                     Some(msg) = network_alert_receiver.recv() => { self.handle_notify_network_alert(msg) },
+                    Some(msg) = block_rejection_register_receiver.recv() => { self.handle_register_block_rejection(msg) },
+                    Some(msg) = block_rejection_receiver.recv() => { self.handle_notify_block_rejection(msg) },
                     _ = signal_receiver.cancelled() => {
                         info!("NotifyService received exit signal, exit now");
                         break;
@@ -198,6 +209,8 @@ impl NotifyService {
             reject_transaction_notifier: reject_transaction_sender,
             network_alert_register,
             network_alert_notifier: network_alert_sender,
+            block_rejection_register,
+            block_rejection_notifier: block_rejection_sender,
             handle,
         }
     }
@@ -406,6 +419,31 @@ impl NotifyService {
             });
         }
     }
+
+    fn handle_register_block_rejection(&mut self, msg: Request<String, Receiver<BlockRejection>>) {
+        let Request {
+            responder,
+            arguments: name,
+        } = msg;
+        debug!("Register block_rejection {:?}", name);
+        let (sender, receiver) = mpsc::channel(NOTIFY_CHANNEL_SIZE);
+        self.block_rejection_subscribers.insert(name, sender);
+        let _ = responder.send(receiver);
+    }
+
+    fn handle_notify_block_rejection(&self, rejection: BlockRejection) {
+        trace!("Block rejection event {:?}", rejection);
+        // notify all subscribers
+        for subscriber in self.block_rejection_subscribers.values() {
+            let rejection = rejection.clone();
+            let subscriber = subscriber.clone();
+            self.handle.spawn(async move {
+                if let Err(e) = subscriber.send(rejection).await {
+                    error!("Failed to notify block rejection, error: {}", e);
+                }
+            });
+        }
+    }
 }
 
 impl NotifyController {
@@ -509,4 +547,24 @@ impl NotifyController {
             }
         });
     }
+
+    /// Subscribe the structured records of rejected blocks
+    pub async fn subscribe_block_rejection<S: ToString>(
+        &self,
+        name: S,
+    ) -> Receiver<BlockRejection> {
+        Request::call(&self.block_rejection_register, name.to_string())
+            .await
+            .expect("Subscribe block rejection should be OK")
+    }
+
+    /// Notify a block is rejected by verification
+    pub fn notify_block_rejection(&self, rejection: BlockRejection) {
+        let block_rejection_notifier = self.block_rejection_notifier.clone();
+        self.handle.spawn(async move {
+            if let Err(e) = block_rejection_notifier.send(rejection).await {
+                error!("notify_block_rejection channel is closed: {}", e);
+            }
+        });
+    }
 }
@@ -1,9 +1,29 @@
 use async_trait::async_trait;
-use ckb_jsonrpc_types::{ExtraLoggerConfig, MainLoggerConfig};
+use ckb_chain::ChainController;
+use ckb_jsonrpc_types::{ExtraLoggerConfig, MainLoggerConfig, VerificationPhase};
 use ckb_logger_service::Logger;
+use ckb_verification_traits::Switch;
 use jsonrpc_core::{Error, ErrorCode::InternalError, Result};
 use jsonrpc_utils::rpc;
 use std::time;
+
+// The `Switch` flag disabling every verification phase
+const VERIFICATION_PHASES: [(VerificationPhase, Switch); 8] = [
+    (VerificationPhase::Epoch, Switch::DISABLE_EPOCH),
+    (VerificationPhase::Uncles, Switch::DISABLE_UNCLES),
+    (
+        VerificationPhase::TwoPhaseCommit,
+        Switch::DISABLE_TWO_PHASE_COMMIT,
+    ),
+    (VerificationPhase::DaoHeader, Switch::DISABLE_DAOHEADER),
+    (VerificationPhase::Reward, Switch::DISABLE_REWARD),
+    (
+        VerificationPhase::NonContextual,
+        Switch::DISABLE_NON_CONTEXTUAL,
+    ),
+    (VerificationPhase::Script, Switch::DISABLE_SCRIPT),
+    (VerificationPhase::Extension, Switch::DISABLE_EXTENSION),
+];
 /// RPC Module Debug for internal RPC methods.
 ///
 /// **This module is for CKB developers and will not guarantee compatibility.** The methods here
@@ -33,10 +53,25 @@ pub trait DebugRpc {
     /// Removes the logger when this is null.
     #[rpc(name = "set_extra_logger")]
     fn set_extra_logger(&self, name: String, config_opt: Option<ExtraLoggerConfig>) -> Result<()>;
+    /// Disables the verification phases of the blocks verified from now on.
+    ///
+    /// The phases which are not listed are enabled again. Mainnet refuses to disable the
+    /// `non_contextual`, `reward` and `script` phases.
+    ///
+    /// ## Params
+    ///
+    /// * `phases` - The verification phases to disable, an empty list enables every phase.
+    #[rpc(name = "set_disabled_verification_phases")]
+    fn set_disabled_verification_phases(&self, phases: Vec<VerificationPhase>) -> Result<()>;
+    /// Returns the verification phases disabled by `set_disabled_verification_phases`.
+    #[rpc(name = "get_disabled_verification_phases")]
+    fn get_disabled_verification_phases(&self) -> Result<Vec<VerificationPhase>>;
 }
 
 #[derive(Clone)]
-pub(crate) struct DebugRpcImpl {}
+pub(crate) struct DebugRpcImpl {
+    pub chain: ChainController,
+}
 
 #[async_trait]
 impl DebugRpc for DebugRpcImpl {
@@ -92,4 +127,23 @@ impl DebugRpc for DebugRpcImpl {
             data: None,
         })
     }
+
+    fn set_disabled_verification_phases(&self, phases: Vec<VerificationPhase>) -> Result<()> {
+        let switch = VERIFICATION_PHASES
+            .iter()
+            .filter(|(phase, _)| phases.contains(phase))
+            .fold(Switch::NONE, |switch, (_, disabled)| switch | *disabled);
+        self.chain
+            .set_verification_switch(switch)
+            .map_err(|err| Error::invalid_params(err.to_string()))
+    }
+
+    fn get_disabled_verification_phases(&self) -> Result<Vec<VerificationPhase>> {
+        let switch = self.chain.verification_switch();
+        Ok(VERIFICATION_PHASES
+            .iter()
+            .filter(|(_, disabled)| switch.contains(*disabled))
+            .map(|(phase, _)| *phase)
+            .collect())
+    }
 }
@@ -106,6 +106,9 @@ pub trait MinerRpc {
     ///     "number": "0x401",
     ///     "parent_hash": "0xa5f5c85987a15de25661e5a214f2c1449cd803f071acc7999820f25246471f40",
     ///     "proposals": ["0xa0ef4eb5f4ceeb08a4c8"],
+    ///     "total_cycles": "0x0",
+    ///     "total_fees": "0x0",
+    ///     "total_size": "0x0",
     ///     "transactions": [],
     ///     "uncles": [
     ///       {
@@ -4,6 +4,7 @@ use ckb_chain_spec::consensus::Consensus;
 use ckb_constant::hardfork::{mainnet, testnet};
 use ckb_jsonrpc_types::{
     EntryCompleted, OutputsValidator, PoolTxDetailInfo, RawTxPool, Script, Transaction, TxPoolInfo,
+    VerifyQueueInfo,
 };
 use ckb_logger::error;
 use ckb_shared::shared::Shared;
@@ -349,6 +350,37 @@ pub trait PoolRpc {
     #[rpc(name = "clear_tx_verify_queue")]
     fn clear_tx_verify_queue(&self) -> Result<()>;
 
+    /// Returns the verification progress of the transactions in the verification queue.
+    ///
+    /// ## Examples
+    ///
+    /// Request
+    ///
+    /// ```json
+    /// {
+    ///   "id": 42,
+    ///   "jsonrpc": "2.0",
+    ///   "method": "get_verify_queue_info",
+    ///   "params": []
+    /// }
+    /// ```
+    ///
+    /// Response
+    ///
+    /// ```json
+    /// {
+    ///   "id": 42,
+    ///   "jsonrpc": "2.0",
+    ///   "result": {
+    ///     "completed": "0x0",
+    ///     "fresh": "0x0",
+    ///     "verifying": "0x0"
+    ///   }
+    /// }
+    /// ```
+    #[rpc(name = "get_verify_queue_info")]
+    fn get_verify_queue_info(&self) -> Result<VerifyQueueInfo>;
+
     /// Returns all transaction ids in tx pool as a json array of string transaction ids.
     /// ## Params
     ///
@@ -698,6 +730,17 @@ impl PoolRpc for PoolRpcImpl {
         Ok(())
     }
 
+    fn get_verify_queue_info(&self) -> Result<VerifyQueueInfo> {
+        let tx_pool = self.shared.tx_pool_controller();
+        tx_pool
+            .get_verify_queue_info()
+            .map(Into::into)
+            .map_err(|e| {
+                error!("Send get_verify_queue_info request error {}", e);
+                RPCError::ckb_internal_error(e)
+            })
+    }
+
     fn get_raw_tx_pool(&self, verbose: Option<bool>) -> Result<RawTxPool> {
         let tx_pool = self.shared.tx_pool_controller();
 
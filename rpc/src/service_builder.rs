@@ -183,8 +183,8 @@ impl<'a> ServiceBuilder<'a> {
     }
 
     /// Mounts methods from module Debug if it is enabled in the config.
-    pub fn enable_debug(mut self) -> Self {
-        let methods = DebugRpcImpl {};
+    pub fn enable_debug(mut self, chain: ChainController) -> Self {
+        let methods = DebugRpcImpl { chain };
         set_rpc_module_methods!(self, "Debug", debug_enable, add_debug_rpc_methods, methods)
     }
 
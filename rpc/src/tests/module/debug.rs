@@ -0,0 +1,45 @@
+use ckb_jsonrpc_types::VerificationPhase;
+use ckb_test_chain_utils::always_success_consensus;
+use ckb_verification_traits::Switch;
+use serde_json::json;
+
+use crate::tests::{setup, RpcTestRequest};
+
+#[test]
+fn test_disabled_verification_phases() {
+    let suite = setup(always_success_consensus());
+    let rpc = |method: &str, params: Vec<serde_json::Value>| {
+        suite.rpc(&RpcTestRequest {
+            id: 42,
+            jsonrpc: "2.0".to_string(),
+            method: method.to_string(),
+            params,
+        })
+    };
+    let disabled_phases = || {
+        let response = rpc("get_disabled_verification_phases", vec![]);
+        assert_eq!(response.error.to_string(), "null".to_string());
+        serde_json::from_value::<Vec<VerificationPhase>>(response.result).expect("phases")
+    };
+    assert!(disabled_phases().is_empty());
+
+    let response = rpc(
+        "set_disabled_verification_phases",
+        vec![json!(["uncles", "epoch"])],
+    );
+    assert_eq!(response.error.to_string(), "null".to_string());
+    assert_eq!(
+        suite.chain_controller.verification_switch(),
+        Switch::DISABLE_EPOCH | Switch::DISABLE_UNCLES
+    );
+    assert_eq!(
+        disabled_phases(),
+        vec![VerificationPhase::Epoch, VerificationPhase::Uncles]
+    );
+
+    // an empty list enables every phase again
+    let response = rpc("set_disabled_verification_phases", vec![json!([])]);
+    assert_eq!(response.error.to_string(), "null".to_string());
+    assert_eq!(suite.chain_controller.verification_switch(), Switch::NONE);
+    assert!(disabled_phases().is_empty());
+}
@@ -1,3 +1,4 @@
+mod debug;
 mod miner;
 mod pool;
 mod test;
@@ -1,3 +1,4 @@
+use ckb_jsonrpc_types::VerifyQueueInfo;
 use ckb_store::ChainStore;
 use ckb_test_chain_utils::{always_success_cell, always_success_consensus, ckb_testnet_consensus};
 use ckb_types::{
@@ -184,6 +185,27 @@ fn test_send_transaction_exceeded_maximum_ancestors_count() {
     }
 }
 
+#[test]
+fn test_get_verify_queue_info() {
+    let suite = setup(always_success_consensus());
+
+    let response = suite.rpc(&RpcTestRequest {
+        id: 42,
+        jsonrpc: "2.0".to_string(),
+        method: "get_verify_queue_info".to_string(),
+        params: vec![],
+    });
+    assert_eq!(response.error.to_string(), "null".to_string());
+    let info: VerifyQueueInfo = serde_json::from_value(response.result).expect("VerifyQueueInfo");
+    let expected = suite
+        .shared
+        .tx_pool_controller()
+        .get_verify_queue_info()
+        .expect("get_verify_queue_info");
+    assert_eq!(info, VerifyQueueInfo::from(expected));
+    assert_eq!(info, Default::default());
+}
+
 fn build_tx(
     code_hash: &packed::Byte32,
     hash_type: core::ScriptHashType,
@@ -82,9 +82,14 @@ pub(crate) fn setup_rpc_test_suite(height: u64, consensus: Option<Consensus>) ->
             use_binary_version_as_message_prefix: true,
             binary_version: "TEST".to_string(),
             update_interval_millis: 800,
+            new_transactions_threshold: 0,
             notify: vec![],
             notify_scripts: vec![],
             notify_timeout_millis: 800,
+            assembly_deadline_millis: None,
+            max_block_transactions: None,
+            reward_locks: vec![],
+            reward_lock_policy: Default::default(),
         }))
         .build()
         .unwrap();
@@ -222,7 +227,7 @@ pub(crate) fn setup_rpc_test_suite(height: u64, consensus: Option<Consensus>) ->
             vec![],
             vec![],
         )
-        .enable_debug()
+        .enable_debug(chain_controller.clone())
         .enable_alert(alert_verifier, alert_notifier, network_controller);
 
     let io_handler = builder.build();
@@ -1,7 +1,7 @@
 use crate::cost_model::transferred_byte_cycles;
 use crate::syscalls::{
-    INVALID_FD, MAX_FDS_CREATED, MAX_VMS_SPAWNED, OTHER_END_CLOSED, SPAWN_EXTRA_CYCLES_BASE,
-    SUCCESS, WAIT_FAILURE,
+    CYCLES_LIMIT_EXCEEDED, INVALID_FD, MAX_FDS_CREATED, MAX_VMS_SPAWNED, OTHER_END_CLOSED,
+    SPAWN_EXTRA_CYCLES_BASE, SUCCESS, WAIT_FAILURE,
 };
 use crate::types::MachineContext;
 use crate::verify::TransactionScriptsSyscallsGenerator;
@@ -24,7 +24,7 @@ use ckb_vm::{
     snapshot2::Snapshot2,
     Error, Register,
 };
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::sync::{Arc, Mutex};
 
 /// Root process's id.
@@ -61,6 +61,9 @@ where
     /// Current iteration cycles. This value is periodically added to
     /// total_cycles and cleared
     pub current_iteration_cycles: Cycle,
+    /// Cycles consumed by each VM, the sum always equals to total_cycles
+    /// once an iteration is finished.
+    pub vm_cycles: BTreeMap<VmId, Cycle>,
     /// Next vm id used by spawn.
     pub next_vm_id: VmId,
     /// Next fd used by pipe.
@@ -77,6 +80,10 @@ where
     pub suspended: BTreeMap<VmId, Snapshot2<DataPieceId>>,
     /// Terminated vms.
     pub terminated_vms: BTreeMap<VmId, i8>,
+    /// Cycles limits of the spawned vms which have one.
+    pub vm_cycles_limits: BTreeMap<VmId, Cycle>,
+    /// Terminated vms which exceeded their own cycles limit.
+    pub cycles_limit_exceeded_vms: BTreeSet<VmId>,
 
     /// MessageBox is expected to be empty before returning from `run`
     /// function, there is no need to persist messages.
@@ -100,6 +107,7 @@ where
             syscalls_generator,
             total_cycles: 0,
             current_iteration_cycles: 0,
+            vm_cycles: BTreeMap::default(),
             next_vm_id: FIRST_VM_ID,
             next_fd_slot: FIRST_FD_SLOT,
             states: BTreeMap::default(),
@@ -109,6 +117,8 @@ where
             suspended: BTreeMap::default(),
             message_box,
             terminated_vms: BTreeMap::default(),
+            vm_cycles_limits: BTreeMap::default(),
+            cycles_limit_exceeded_vms: BTreeSet::default(),
         }
     }
 
@@ -126,6 +136,29 @@ where
         Ok(())
     }
 
+    /// Return cycles consumed by each VM, spawned VMs included.
+    pub fn consumed_cycles_by_vm(&self) -> &BTreeMap<VmId, Cycle> {
+        &self.vm_cycles
+    }
+
+    // Charge cycles of the current iteration to the given VM.
+    fn iteration_cycles_add(&mut self, id: VmId, cycles: Cycle) -> Result<(), Error> {
+        self.current_iteration_cycles = self
+            .current_iteration_cycles
+            .checked_add(cycles)
+            .ok_or(Error::CyclesOverflow)?;
+        let vm_cycles = self.vm_cycles.entry(id).or_default();
+        *vm_cycles = vm_cycles.checked_add(cycles).ok_or(Error::CyclesOverflow)?;
+        Ok(())
+    }
+
+    // Cycles the given VM may still consume on its own, None if it's unlimited.
+    fn remaining_vm_cycles(&self, id: &VmId) -> Option<Cycle> {
+        self.vm_cycles_limits
+            .get(id)
+            .map(|limit| limit.saturating_sub(self.vm_cycles.get(id).copied().unwrap_or_default()))
+    }
+
     /// Resume a previously suspended scheduler state
     pub fn resume(
         tx_data: TxData<DL>,
@@ -140,6 +173,7 @@ where
             syscalls_generator,
             total_cycles: full.total_cycles,
             current_iteration_cycles: 0,
+            vm_cycles: full.vm_cycles.into_iter().collect(),
             next_vm_id: full.next_vm_id,
             next_fd_slot: full.next_fd_slot,
             states: full
@@ -157,6 +191,8 @@ where
                 .collect(),
             message_box,
             terminated_vms: full.terminated_vms.into_iter().collect(),
+            vm_cycles_limits: full.vm_cycles_limits.into_iter().collect(),
+            cycles_limit_exceeded_vms: full.cycles_limit_exceeded_vms.into_iter().collect(),
         };
         scheduler
             .ensure_vms_instantiated(&full.instantiated_ids)
@@ -181,6 +217,7 @@ where
         }
         Ok(FullSuspendedState {
             total_cycles: self.total_cycles,
+            vm_cycles: self.vm_cycles.into_iter().collect(),
             next_vm_id: self.next_vm_id,
             next_fd_slot: self.next_fd_slot,
             vms,
@@ -188,6 +225,8 @@ where
             inherited_fd: self.inherited_fd.into_iter().collect(),
             terminated_vms: self.terminated_vms.into_iter().collect(),
             instantiated_ids,
+            vm_cycles_limits: self.vm_cycles_limits.into_iter().collect(),
+            cycles_limit_exceeded_vms: self.cycles_limit_exceeded_vms.into_iter().collect(),
         })
     }
 
@@ -254,6 +293,12 @@ where
             Error::Unexpected("A deadlock situation has been reached!".to_string())
         })?;
         let total_cycles = self.total_cycles;
+        self.ensure_vms_instantiated(&[vm_id_to_run])?;
+        // A spawned VM with its own cycles limit runs until whichever limit comes first
+        let limit_cycles = match self.remaining_vm_cycles(&vm_id_to_run) {
+            Some(remaining) => remaining.min(limit_cycles),
+            None => limit_cycles,
+        };
         let (context, machine) = self.ensure_get_instantiated(&vm_id_to_run)?;
         context.set_base_cycles(total_cycles);
         machine.set_max_cycles(limit_cycles);
@@ -268,10 +313,21 @@ where
         result: Result<i8, Error>,
         cycles: u64,
     ) -> Result<(), Error> {
-        self.current_iteration_cycles = self
-            .current_iteration_cycles
-            .checked_add(cycles)
-            .ok_or(Error::CyclesOverflow)?;
+        // The machine ran out of cycles at a limit equal to the VM's remaining cycles,
+        // only the VM itself is terminated, the other VMs keep running.
+        let result = match (result, self.remaining_vm_cycles(&vm_id_to_run)) {
+            (Err(Error::CyclesExceeded), Some(remaining))
+                if self
+                    .instantiated
+                    .get(&vm_id_to_run)
+                    .is_some_and(|(_, machine)| machine.machine.max_cycles() >= remaining) =>
+            {
+                self.cycles_limit_exceeded_vms.insert(vm_id_to_run);
+                Ok(0)
+            }
+            (result, _) => result,
+        };
+        self.iteration_cycles_add(vm_id_to_run, cycles)?;
         // Process message box, update VM states accordingly
         self.process_message_box()?;
         assert!(self.message_box.lock().expect("lock").is_empty());
@@ -302,18 +358,27 @@ where
                         .collect();
                     // For all joining VMs, update exit code, then mark them as
                     // runnable state.
+                    let cycles_limit_exceeded =
+                        self.cycles_limit_exceeded_vms.contains(&vm_id_to_run);
                     for (vm_id, exit_code_addr) in joining_vms {
                         let (_, machine) = self.ensure_get_instantiated(&vm_id)?;
-                        machine
-                            .machine
-                            .memory_mut()
-                            .store8(&exit_code_addr, &u64::from_i8(code))?;
-                        machine.machine.set_register(A0, SUCCESS as u64);
+                        if cycles_limit_exceeded {
+                            machine
+                                .machine
+                                .set_register(A0, CYCLES_LIMIT_EXCEEDED as u64);
+                        } else {
+                            machine
+                                .machine
+                                .memory_mut()
+                                .store8(&exit_code_addr, &u64::from_i8(code))?;
+                            machine.machine.set_register(A0, SUCCESS as u64);
+                        }
                         self.states.insert(vm_id, VmState::Runnable);
                     }
                     // Close fds
                     self.fds.retain(|_, vm_id| *vm_id != vm_id_to_run);
                     // Clear terminated VM states
+                    self.vm_cycles_limits.remove(&vm_id_to_run);
                     self.states.remove(&vm_id_to_run);
                     self.instantiated.remove(&vm_id_to_run);
                     self.suspended.remove(&vm_id_to_run);
@@ -336,6 +401,10 @@ where
         self.iterate_process_results(id, result, cycles)
     }
 
+    // Every syscall posting a message yields right away, so the box holds no more than the
+    // message of the VM just run when it's drained here, there's no backlog for a VM to
+    // flood. Which VM runs next is decided in `iterate_prepare_machine`, the order is part
+    // of the consensus and must not change.
     fn process_message_box(&mut self) -> Result<(), Error> {
         let messages: Vec<Message> = self.message_box.lock().expect("lock").drain(..).collect();
         for message in messages {
@@ -354,6 +423,9 @@ where
                     }
                     let spawned_vm_id =
                         self.boot_vm(&args.data_piece_id, args.offset, args.length, &args.argv)?;
+                    if let Some(limit) = args.cycles_limit {
+                        self.vm_cycles_limits.insert(spawned_vm_id, limit);
+                    }
                     // Move passed fds from spawner to spawnee
                     for fd in &args.fds {
                         self.fds.insert(*fd, spawned_vm_id);
@@ -371,12 +443,20 @@ where
                 }
                 Message::Wait(vm_id, args) => {
                     if let Some(exit_code) = self.terminated_vms.get(&args.target_id).copied() {
+                        let cycles_limit_exceeded =
+                            self.cycles_limit_exceeded_vms.remove(&args.target_id);
                         let (_, machine) = self.ensure_get_instantiated(&vm_id)?;
-                        machine
-                            .machine
-                            .memory_mut()
-                            .store8(&args.exit_code_addr, &u64::from_i8(exit_code))?;
-                        machine.machine.set_register(A0, SUCCESS as u64);
+                        if cycles_limit_exceeded {
+                            machine
+                                .machine
+                                .set_register(A0, CYCLES_LIMIT_EXCEEDED as u64);
+                        } else {
+                            machine
+                                .machine
+                                .memory_mut()
+                                .store8(&args.exit_code_addr, &u64::from_i8(exit_code))?;
+                            machine.machine.set_register(A0, SUCCESS as u64);
+                        }
                         self.states.insert(vm_id, VmState::Runnable);
                         self.terminated_vms.retain(|id, _| id != &args.target_id);
                         continue;
@@ -703,11 +783,8 @@ where
         if !self.suspended.contains_key(id) {
             return Err(Error::Unexpected(format!("VM {:?} is not suspended!", id)));
         }
+        self.iteration_cycles_add(*id, SPAWN_EXTRA_CYCLES_BASE)?;
         let snapshot = &self.suspended[id];
-        self.current_iteration_cycles = self
-            .current_iteration_cycles
-            .checked_add(SPAWN_EXTRA_CYCLES_BASE)
-            .ok_or(Error::CyclesExceeded)?;
         let (context, mut machine) = self.create_dummy_vm(id)?;
         {
             let mut sc = context.snapshot2_context().lock().expect("lock");
@@ -726,10 +803,7 @@ where
                 id
             )));
         }
-        self.current_iteration_cycles = self
-            .current_iteration_cycles
-            .checked_add(SPAWN_EXTRA_CYCLES_BASE)
-            .ok_or(Error::CyclesExceeded)?;
+        self.iteration_cycles_add(*id, SPAWN_EXTRA_CYCLES_BASE)?;
         let (context, machine) = self
             .instantiated
             .get_mut(id)
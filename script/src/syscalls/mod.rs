@@ -68,6 +68,9 @@ pub const INVALID_FD: u8 = 6;
 pub const OTHER_END_CLOSED: u8 = 7;
 pub const MAX_VMS_SPAWNED: u8 = 8;
 pub const MAX_FDS_CREATED: u8 = 9;
+// CYCLES_LIMIT_EXCEEDED is returned by wait when the target VM is terminated for
+// exceeding its own cycles limit, see `TransactionScriptsVerifier::set_child_cycles_limit`.
+pub const CYCLES_LIMIT_EXCEEDED: u8 = 10;
 
 pub const VM_VERSION: u64 = 2041;
 pub const CURRENT_CYCLES: u64 = 2042;
@@ -5,7 +5,7 @@ use crate::syscalls::{
 };
 use crate::types::{DataPieceId, Fd, Message, SpawnArgs, TxData, VmId};
 use ckb_traits::{CellDataProvider, ExtensionProvider, HeaderProvider};
-use ckb_types::core::error::ARGV_TOO_LONG_TEXT;
+use ckb_types::core::{error::ARGV_TOO_LONG_TEXT, Cycle};
 use ckb_vm::{
     machine::SupportMachine,
     memory::Memory,
@@ -23,6 +23,7 @@ where
     id: VmId,
     message_box: Arc<Mutex<Vec<Message>>>,
     snapshot2_context: Arc<Mutex<Snapshot2Context<DataPieceId, TxData<DL>>>>,
+    cycles_limit: Option<Cycle>,
 }
 
 impl<DL> Spawn<DL>
@@ -33,11 +34,13 @@ where
         id: VmId,
         message_box: Arc<Mutex<Vec<Message>>>,
         snapshot2_context: Arc<Mutex<Snapshot2Context<DataPieceId, TxData<DL>>>>,
+        cycles_limit: Option<Cycle>,
     ) -> Self {
         Self {
             id,
             message_box,
             snapshot2_context,
+            cycles_limit,
         }
     }
 }
@@ -175,6 +178,7 @@ where
                     argv,
                     fds,
                     process_id_addr,
+                    cycles_limit: self.cycles_limit,
                 },
             ));
         Err(VMError::Yield)
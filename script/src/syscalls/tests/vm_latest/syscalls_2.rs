@@ -18,6 +18,7 @@ use std::sync::{Arc, Mutex};
 
 use super::SCRIPT_VERSION;
 use crate::syscalls::*;
+use crate::types::{Fd, FdArgs, Message, FIRST_FD_SLOT};
 
 #[test]
 fn test_vm_version() {
@@ -59,6 +60,77 @@ fn test_current_cycles() {
     assert_eq!(machine.registers()[A0], cycles);
 }
 
+#[test]
+fn test_current_cycles_monotonic_with_base() {
+    let mut machine = SCRIPT_VERSION.init_core_machine_without_limit();
+    // cycles consumed by the other VMs before the spawned one
+    let base = Arc::new(Mutex::new(100_000));
+    let mut current_cycles = CurrentCycles::new(Arc::clone(&base));
+
+    machine.set_register(A7, CURRENT_CYCLES);
+    machine.set_cycles(100);
+    assert!(current_cycles.ecall(&mut machine).unwrap());
+    let first = machine.registers()[A0];
+    assert_eq!(first, 100_100);
+
+    machine.set_register(A7, CURRENT_CYCLES);
+    machine.add_cycles_no_checking(50).unwrap();
+    assert!(current_cycles.ecall(&mut machine).unwrap());
+    let second = machine.registers()[A0];
+    assert!(second > first);
+    assert_eq!(second, 100_150);
+
+    *base.lock().unwrap() = u64::MAX;
+    machine.set_register(A7, CURRENT_CYCLES);
+    assert!(current_cycles.ecall(&mut machine).is_err());
+}
+
+#[test]
+fn test_pipe_read_write_messages() {
+    let mut machine = SCRIPT_VERSION.init_core_machine_without_limit();
+    let message_box = Arc::new(Mutex::new(Vec::new()));
+    let (read_fd, write_fd, _) = Fd::create(FIRST_FD_SLOT);
+    let vm_id = 1;
+    let length_addr: u64 = 0;
+    let buffer_addr: u64 = 100;
+    machine
+        .memory_mut()
+        .store64(&length_addr, &11)
+        .expect("store length");
+
+    // a write fd can't be read
+    machine.set_register(A0, write_fd.0);
+    machine.set_register(A1, buffer_addr);
+    machine.set_register(A2, length_addr);
+    machine.set_register(A7, READ);
+    let result = Read::new(vm_id, Arc::clone(&message_box)).ecall(&mut machine);
+    assert!(result.unwrap());
+    assert_eq!(machine.registers()[A0], u64::from(INVALID_FD));
+    assert_eq!(machine.cycles(), 0);
+    assert!(message_box.lock().unwrap().is_empty());
+
+    machine.set_register(A0, write_fd.0);
+    machine.set_register(A7, WRITE);
+    let result = Write::new(vm_id, Arc::clone(&message_box)).ecall(&mut machine);
+    assert!(matches!(result, Err(ckb_vm::Error::Yield)));
+    assert_eq!(machine.cycles(), SPAWN_YIELD_CYCLES_BASE);
+
+    machine.set_register(A0, read_fd.0);
+    machine.set_register(A7, READ);
+    let result = Read::new(vm_id, Arc::clone(&message_box)).ecall(&mut machine);
+    assert!(matches!(result, Err(ckb_vm::Error::Yield)));
+    assert_eq!(machine.cycles(), SPAWN_YIELD_CYCLES_BASE * 2);
+
+    let messages: Vec<Message> = message_box.lock().unwrap().drain(..).collect();
+    assert!(matches!(
+        messages.as_slice(),
+        [
+            Message::FdWrite(1, FdArgs { fd: write, length: 11, buffer_addr: 100, length_addr: 0 }),
+            Message::FdRead(1, FdArgs { fd: read, length: 11, buffer_addr: 100, length_addr: 0 }),
+        ] if *write == write_fd && *read == read_fd
+    ));
+}
+
 fn _test_load_extension(
     data: &[u8],
     index: u64,
@@ -358,6 +358,8 @@ pub struct SpawnArgs {
     pub argv: Vec<Bytes>,
     pub fds: Vec<Fd>,
     pub process_id_addr: u64,
+    /// Cycles the spawned VM may consume on its own, `None` means unlimited.
+    pub cycles_limit: Option<Cycle>,
 }
 
 #[derive(Clone, Debug)]
@@ -442,6 +444,7 @@ impl TryFrom<(u64, u64, u64)> for DataPieceId {
 #[derive(Clone, Debug)]
 pub struct FullSuspendedState {
     pub total_cycles: Cycle,
+    pub vm_cycles: Vec<(VmId, Cycle)>,
     pub next_vm_id: VmId,
     pub next_fd_slot: u64,
     pub vms: Vec<(VmId, VmState, Snapshot2<DataPieceId>)>,
@@ -449,6 +452,8 @@ pub struct FullSuspendedState {
     pub inherited_fd: Vec<(VmId, Vec<Fd>)>,
     pub terminated_vms: Vec<(VmId, i8)>,
     pub instantiated_ids: Vec<VmId>,
+    pub vm_cycles_limits: Vec<(VmId, Cycle)>,
+    pub cycles_limit_exceeded_vms: Vec<VmId>,
 }
 
 impl FullSuspendedState {
@@ -478,6 +483,9 @@ impl FullSuspendedState {
             + (self.inherited_fd.len() * (size_of::<Fd>())) as u64
             + (self.terminated_vms.len() * (size_of::<VmId>() + size_of::<i8>())) as u64
             + (self.instantiated_ids.len() * size_of::<VmId>()) as u64
+            + (self.vm_cycles.len() * (size_of::<VmId>() + size_of::<Cycle>())) as u64
+            + (self.vm_cycles_limits.len() * (size_of::<VmId>() + size_of::<Cycle>())) as u64
+            + (self.cycles_limit_exceeded_vms.len() * size_of::<VmId>()) as u64
     }
 }
 
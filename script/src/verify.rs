@@ -1,4 +1,4 @@
-use crate::scheduler::Scheduler;
+use crate::scheduler::{Scheduler, ROOT_VM_ID};
 #[cfg(test)]
 use crate::syscalls::Pause;
 use crate::syscalls::{InheritedFd, ProcessID, EXEC_LOAD_ELF_V2_CYCLES_BASE};
@@ -147,6 +147,7 @@ where
     DL: CellDataProvider + HeaderProvider + ExtensionProvider + Send + Sync + Clone + 'static,
 {
     pub(crate) base_cycles: Arc<Mutex<u64>>,
+    pub(crate) child_cycles_limit: Option<Cycle>,
     pub(crate) data_loader: DL,
     pub(crate) debug_printer: DebugPrinter,
     pub(crate) message_box: Arc<Mutex<Vec<Message>>>,
@@ -255,7 +256,12 @@ where
         &self,
         snapshot2_context: Arc<Mutex<Snapshot2Context<DataPieceId, TxData<DL>>>>,
     ) -> Spawn<DL> {
-        Spawn::new(self.vm_id, Arc::clone(&self.message_box), snapshot2_context)
+        Spawn::new(
+            self.vm_id,
+            Arc::clone(&self.message_box),
+            snapshot2_context,
+            self.child_cycles_limit,
+        )
     }
 
     /// Build syscall: wait
@@ -480,6 +486,7 @@ where
 
         let syscalls_generator = TransactionScriptsSyscallsGenerator {
             base_cycles: Arc::new(Mutex::new(0)),
+            child_cycles_limit: None,
             data_loader: data_loader.clone(),
             debug_printer: Arc::clone(&debug_printer),
             message_box: Arc::new(Mutex::new(Vec::new())),
@@ -518,6 +525,18 @@ where
         self.syscalls_generator.debug_printer = Arc::new(func);
     }
 
+    /// Sets the cycles every spawned VM may consume on its own.
+    ///
+    /// A spawned VM exceeding the limit is terminated, and its parent observes
+    /// `CYCLES_LIMIT_EXCEEDED` from the wait syscall instead of aborting the whole
+    /// script. The cycles charged to the parent for spawning are not counted.
+    ///
+    /// This is meant for debugging and testing tools, consensus verification
+    /// never sets a limit.
+    pub fn set_child_cycles_limit(&mut self, limit: Option<Cycle>) {
+        self.syscalls_generator.child_cycles_limit = limit;
+    }
+
     #[cfg(test)]
     pub(crate) fn set_skip_pause(&self, skip_pause: bool) {
         self.skip_pause.store(skip_pause, Ordering::SeqCst);
@@ -642,6 +661,36 @@ where
         Ok(cycles)
     }
 
+    /// Verifies the transaction like `verify`, and also reports how the cycles split across
+    /// the VMs spawned by each script group.
+    ///
+    /// ## Returns
+    ///
+    /// It returns the total consumed cycles together with the per-`VmId` cycles of every
+    /// script group on success, the per-VM cycles of all groups sum up to the total cycles.
+    pub fn verify_with_vm_cycles(
+        &self,
+        max_cycles: Cycle,
+    ) -> Result<(Cycle, Vec<(Byte32, BTreeMap<VmId, Cycle>)>), Error> {
+        let mut cycles: Cycle = 0;
+        let mut groups_vm_cycles = Vec::new();
+
+        for (hash, group) in self.groups() {
+            let vm_cycles = self
+                .verify_script_group_with_vm_cycles(group, max_cycles - cycles)
+                .map_err(|e| {
+                    #[cfg(feature = "logging")]
+                    logging::on_script_error(hash, &self.hash(), &e);
+                    e.source(group)
+                })?;
+            let used_cycles = vm_cycles.values().sum();
+
+            cycles = wrapping_cycles_add(cycles, used_cycles, group)?;
+            groups_vm_cycles.push((hash.clone(), vm_cycles));
+        }
+        Ok((cycles, groups_vm_cycles))
+    }
+
     /// Performing a resumable verification on the transaction scripts.
     ///
     /// ## Params
@@ -979,6 +1028,35 @@ where
             self.run(group, max_cycles)
         }
     }
+
+    fn verify_script_group_with_vm_cycles(
+        &self,
+        group: &ScriptGroup,
+        max_cycles: Cycle,
+    ) -> Result<BTreeMap<VmId, Cycle>, ScriptError> {
+        if group.script.code_hash() == TYPE_ID_CODE_HASH.pack()
+            && Into::<u8>::into(group.script.hash_type()) == Into::<u8>::into(ScriptHashType::Type)
+        {
+            let verifier = TypeIdSystemScript {
+                rtx: &self.rtx,
+                script_group: group,
+                max_cycles,
+            };
+            let cycles = verifier.verify()?;
+            Ok(BTreeMap::from([(ROOT_VM_ID, cycles)]))
+        } else {
+            let mut scheduler = self.create_scheduler(group)?;
+            let (code, _) = scheduler
+                .run(RunMode::LimitCycles(max_cycles))
+                .map_err(|err| self.map_vm_internal_error(err, max_cycles))?;
+            if code == 0 {
+                Ok(scheduler.consumed_cycles_by_vm().clone())
+            } else {
+                Err(ScriptError::validation_failure(&group.script, code))
+            }
+        }
+    }
+
     /// Returns all script groups.
     pub fn groups(&self) -> impl Iterator<Item = (&'_ Byte32, &'_ ScriptGroup)> {
         self.lock_groups.iter().chain(self.type_groups.iter())
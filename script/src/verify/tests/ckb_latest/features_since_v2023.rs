@@ -1,6 +1,6 @@
 use super::SCRIPT_VERSION;
 use crate::scheduler::{MAX_FDS, MAX_VMS_COUNT};
-use crate::syscalls::SOURCE_GROUP_FLAG;
+use crate::syscalls::{CYCLES_LIMIT_EXCEEDED, SOURCE_GROUP_FLAG};
 use crate::verify::{tests::utils::*, *};
 use ckb_types::{
     core::{capacity_bytes, cell::CellMetaBuilder, Capacity, TransactionBuilder},
@@ -213,6 +213,90 @@ fn check_spawn_out_of_cycles() {
     }
 }
 
+#[test]
+fn check_spawn_child_cycles_limit() {
+    let script_version = SCRIPT_VERSION;
+
+    let (spawn_caller_cell, spawn_caller_data_hash) =
+        load_cell_from_path("testdata/spawn_caller_out_of_cycles");
+    let (spawn_callee_cell, _spawn_callee_data_hash) =
+        load_cell_from_path("testdata/spawn_callee_out_of_cycles");
+
+    let spawn_caller_script = Script::new_builder()
+        .hash_type(script_version.data_hash_type().into())
+        .code_hash(spawn_caller_data_hash)
+        .build();
+    let output = CellOutputBuilder::default()
+        .capacity(capacity_bytes!(100).pack())
+        .lock(spawn_caller_script)
+        .build();
+    let input = CellInput::new(OutPoint::null(), 0);
+
+    let transaction = TransactionBuilder::default().input(input).build();
+    let dummy_cell = create_dummy_cell(output);
+
+    let rtx = ResolvedTransaction {
+        transaction,
+        resolved_cell_deps: vec![spawn_caller_cell, spawn_callee_cell],
+        resolved_inputs: vec![dummy_cell],
+        resolved_dep_groups: vec![],
+    };
+    let verifier = TransactionScriptsVerifierWithEnv::new();
+    let result = verifier.verify_map(script_version, &rtx, |mut verifier| {
+        verifier.set_child_cycles_limit(Some(1_000_000));
+        verifier.verify(0xffffff)
+    });
+    if script_version >= ScriptVersion::V2 {
+        // the endless callee is terminated alone, the caller gets the error from wait
+        let msg = result.unwrap_err().to_string();
+        assert!(msg.contains(&format!("error code {}", CYCLES_LIMIT_EXCEEDED)));
+        assert!(!msg.contains("ExceededMaximumCycles"));
+    } else {
+        assert!(result.is_err())
+    }
+}
+
+#[test]
+fn check_spawn_child_cycles_limit_not_reached() {
+    let script_version = SCRIPT_VERSION;
+
+    let (spawn_caller_cell, spawn_caller_data_hash) =
+        load_cell_from_path("testdata/spawn_caller_current_cycles");
+    let (spawn_callee_cell, _spawn_callee_data_hash) =
+        load_cell_from_path("testdata/spawn_callee_current_cycles");
+
+    let spawn_caller_script = Script::new_builder()
+        .hash_type(script_version.data_hash_type().into())
+        .code_hash(spawn_caller_data_hash)
+        .build();
+    let output = CellOutputBuilder::default()
+        .capacity(capacity_bytes!(100).pack())
+        .lock(spawn_caller_script)
+        .build();
+    let input = CellInput::new(OutPoint::null(), 0);
+
+    let transaction = TransactionBuilder::default().input(input).build();
+    let dummy_cell = create_dummy_cell(output);
+
+    let rtx = ResolvedTransaction {
+        transaction,
+        resolved_cell_deps: vec![spawn_caller_cell, spawn_callee_cell],
+        resolved_inputs: vec![dummy_cell],
+        resolved_dep_groups: vec![],
+    };
+    let verifier = TransactionScriptsVerifierWithEnv::new();
+    let without_limit = verifier.verify_without_limit(script_version, &rtx);
+    let with_limit = verifier.verify_map(script_version, &rtx, |mut verifier| {
+        verifier.set_child_cycles_limit(Some(10_000_000));
+        verifier.verify(u64::MAX)
+    });
+    assert_eq!(with_limit.is_ok(), script_version >= ScriptVersion::V2);
+    if script_version >= ScriptVersion::V2 {
+        // the cycles charged to the caller for spawning don't count in the limit
+        assert_eq!(with_limit.unwrap(), without_limit.unwrap());
+    }
+}
+
 #[test]
 fn check_spawn_exec() {
     let script_version = SCRIPT_VERSION;
@@ -1277,6 +1361,50 @@ fn check_spawn_cycles() {
     }
 }
 
+#[test]
+fn check_spawn_cycles_by_vm() {
+    let script_version = SCRIPT_VERSION;
+    if script_version < ScriptVersion::V2 {
+        return;
+    }
+
+    let (spawn_caller_cell, spawn_caller_data_hash) = load_cell_from_path("testdata/spawn_cycles");
+    let (spawn_callee_cell, _spawn_callee_data_hash) = load_cell_from_path("testdata/spawn_cycles");
+
+    let spawn_caller_script = Script::new_builder()
+        .hash_type(script_version.data_hash_type().into())
+        .code_hash(spawn_caller_data_hash)
+        .build();
+    let output = CellOutputBuilder::default()
+        .capacity(capacity_bytes!(100).pack())
+        .lock(spawn_caller_script)
+        .build();
+    let input = CellInput::new(OutPoint::null(), 0);
+
+    let transaction = TransactionBuilder::default().input(input).build();
+    let dummy_cell = create_dummy_cell(output);
+
+    let rtx = ResolvedTransaction {
+        transaction,
+        resolved_cell_deps: vec![spawn_caller_cell, spawn_callee_cell],
+        resolved_inputs: vec![dummy_cell],
+        resolved_dep_groups: vec![],
+    };
+    let verifier = TransactionScriptsVerifierWithEnv::new();
+    let (cycles, groups_vm_cycles) = verifier
+        .verify_map(script_version, &rtx, |verifier| {
+            verifier.verify_with_vm_cycles(u64::MAX)
+        })
+        .unwrap();
+    assert_eq!(cycles, 1525087);
+    assert_eq!(groups_vm_cycles.len(), 1);
+
+    let vm_cycles = &groups_vm_cycles[0].1;
+    assert!(vm_cycles.len() > 1, "spawned VMs should be accounted");
+    assert!(vm_cycles.contains_key(&ROOT_VM_ID));
+    assert_eq!(vm_cycles.values().sum::<Cycle>(), cycles);
+}
+
 fn spawn_io_test(io_size: u64, enable_check: bool) -> Result<u64, Error> {
     let script_version = SCRIPT_VERSION;
 
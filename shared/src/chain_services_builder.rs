@@ -1,11 +1,21 @@
 //! chain_services_builder provide ChainServicesBuilder to build Chain Services
 #![allow(missing_docs)]
 use crate::Shared;
+use ckb_app_config::DuplicateBlockPolicy;
 use ckb_proposal_table::ProposalTable;
+use std::time::Duration;
+
+/// The orphans whose parent doesn't arrive in time are evicted
+pub const DEFAULT_ORPHAN_BLOCK_MAX_AGE: Duration = Duration::from_secs(10 * 60);
+/// The max age is also the eviction interval, a shorter one would keep the broker busy
+pub const MIN_ORPHAN_BLOCK_MAX_AGE: Duration = Duration::from_secs(1);
 
 pub struct ChainServicesBuilder {
     pub shared: Shared,
     pub proposal_table: ProposalTable,
+    pub orphan_block_max_age: Duration,
+    pub duplicate_block_policy: DuplicateBlockPolicy,
+    pub log_peer_block_stats: bool,
 }
 
 impl ChainServicesBuilder {
@@ -13,6 +23,24 @@ impl ChainServicesBuilder {
         ChainServicesBuilder {
             shared,
             proposal_table,
+            orphan_block_max_age: DEFAULT_ORPHAN_BLOCK_MAX_AGE,
+            duplicate_block_policy: DuplicateBlockPolicy::default(),
+            log_peer_block_stats: false,
         }
     }
+
+    pub fn orphan_block_max_age(mut self, max_age: Duration) -> Self {
+        self.orphan_block_max_age = max_age.max(MIN_ORPHAN_BLOCK_MAX_AGE);
+        self
+    }
+
+    pub fn duplicate_block_policy(mut self, policy: DuplicateBlockPolicy) -> Self {
+        self.duplicate_block_policy = policy;
+        self
+    }
+
+    pub fn log_peer_block_stats(mut self, enabled: bool) -> Self {
+        self.log_peer_block_stats = enabled;
+        self
+    }
 }
@@ -12,5 +12,7 @@ pub use shared_builder::{SharedBuilder, SharedPackage};
 pub mod block_status;
 pub mod types;
 
-pub use types::header_map::HeaderMap;
-pub use types::{HeaderIndex, HeaderIndexView};
+#[cfg(feature = "stats")]
+pub use types::header_map::{BadSkipHash, HeaderMapCoverage};
+pub use types::header_map::{HeaderMap, HeaderMapStats};
+pub use types::{HeaderIndex, HeaderIndexView, HeaderMapDecodeError};
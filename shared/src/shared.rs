@@ -28,7 +28,7 @@ use ckb_verification::cache::TxVerificationCache;
 use dashmap::DashMap;
 use std::cmp;
 use std::collections::BTreeMap;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
@@ -61,6 +61,8 @@ pub struct Shared {
     pub(crate) snapshot_mgr: Arc<SnapshotMgr>,
     pub(crate) async_handle: Handle,
     pub(crate) ibd_finished: Arc<AtomicBool>,
+    pub(crate) min_peers_to_exit_ibd: usize,
+    pub(crate) sync_peers: Arc<AtomicUsize>,
 
     pub(crate) assume_valid_targets: Arc<Mutex<Option<Vec<H256>>>>,
     pub(crate) assume_valid_target_specified: Arc<Option<H256>>,
@@ -82,6 +84,7 @@ impl Shared {
         snapshot_mgr: Arc<SnapshotMgr>,
         async_handle: Handle,
         ibd_finished: Arc<AtomicBool>,
+        min_peers_to_exit_ibd: usize,
 
         assume_valid_targets: Arc<Mutex<Option<Vec<H256>>>>,
         assume_valid_target_specified: Arc<Option<H256>>,
@@ -106,6 +109,8 @@ impl Shared {
             snapshot_mgr,
             async_handle,
             ibd_finished,
+            min_peers_to_exit_ibd,
+            sync_peers: Arc::new(AtomicUsize::new(0)),
             assume_valid_targets,
             assume_valid_target_specified,
             header_map,
@@ -376,12 +381,16 @@ impl Shared {
     }
 
     /// Return whether chain is in initial block download
+    ///
+    /// The chain doesn't exit IBD until at least `min_peers_to_exit_ibd` sync peers are connected,
+    /// so a single peer can't make a fresh node believe it's at the tip.
     pub fn is_initial_block_download(&self) -> bool {
         // Once this function has returned false, it must remain false.
         if self.ibd_finished.load(Ordering::Acquire) {
             false
         } else if unix_time_as_millis().saturating_sub(self.snapshot().tip_header().timestamp())
             > MAX_TIP_AGE
+            || self.sync_peers.load(Ordering::Acquire) < self.min_peers_to_exit_ibd
         {
             true
         } else {
@@ -390,6 +399,11 @@ impl Shared {
         }
     }
 
+    /// Update the number of connected sync peers
+    pub fn set_sync_peers(&self, count: usize) {
+        self.sync_peers.store(count, Ordering::Release);
+    }
+
     /// Generate and return block_template
     pub fn get_block_template(
         &self,
@@ -1,4 +1,6 @@
 //! shared_builder provide SharedBuilder and SharedPacakge
+use crate::chain_services_builder::DEFAULT_ORPHAN_BLOCK_MAX_AGE;
+use crate::types::header_map::{DEFAULT_BACKEND_RETRY_ATTEMPTS, DEFAULT_TRIM_INTERVAL};
 use crate::ChainServicesBuilder;
 use crate::{HeaderMap, Shared};
 use ckb_app_config::{
@@ -13,7 +15,7 @@ use ckb_db::RocksDB;
 use ckb_db_schema::COLUMNS;
 use ckb_error::{Error, InternalErrorKind};
 use ckb_fee_estimator::FeeEstimator;
-use ckb_logger::{error, info};
+use ckb_logger::{error, info, warn};
 use ckb_migrate::migrate::Migrate;
 use ckb_notify::{NotifyController, NotifyService};
 use ckb_proposal_table::ProposalTable;
@@ -37,6 +39,7 @@ use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
+use std::time::Duration;
 use tempfile::TempDir;
 
 /// Shared builder for construct new shared.
@@ -374,12 +377,52 @@ impl SharedBuilder {
 
         let ibd_finished = Arc::new(AtomicBool::new(false));
 
-        let header_map = Arc::new(HeaderMap::new(
-            header_map_tmp_dir,
-            header_map_memory_limit,
-            &async_handle,
-            Arc::clone(&ibd_finished),
-        ));
+        let header_map_retry_attempts = sync_config
+            .header_map
+            .backend_retry_attempts
+            .unwrap_or(DEFAULT_BACKEND_RETRY_ATTEMPTS);
+        let header_map_trim_interval = sync_config
+            .header_map
+            .trim_interval_ms
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_TRIM_INTERVAL);
+        let header_map = match header_map_tmp_dir {
+            Some(ref tmp_dir) if sync_config.header_map.persistent => HeaderMap::open_persistent(
+                tmp_dir.join("header_map"),
+                header_map_memory_limit,
+                sync_config.header_map.backend,
+                header_map_retry_attempts,
+                header_map_trim_interval,
+                &async_handle,
+                Arc::clone(&ibd_finished),
+            ),
+            _ => HeaderMap::with_backend(
+                header_map_tmp_dir,
+                header_map_memory_limit,
+                sync_config.header_map.backend,
+                header_map_retry_attempts,
+                header_map_trim_interval,
+                &async_handle,
+                Arc::clone(&ibd_finished),
+            ),
+        };
+        let header_map = match header_map {
+            Ok(header_map) => header_map,
+            Err(e) if sync_config.header_map.memory_fallback => {
+                warn!("{e}, keep all headers in memory instead");
+                HeaderMap::memory_only(
+                    header_map_memory_limit,
+                    header_map_trim_interval,
+                    &async_handle,
+                    Arc::clone(&ibd_finished),
+                )
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                return Err(ExitCode::Failure);
+            }
+        };
+        let header_map = Arc::new(header_map);
 
         let notify_controller = start_notify_service(notify_config, async_handle.clone());
 
@@ -471,13 +514,22 @@ impl SharedBuilder {
             snapshot_mgr,
             async_handle,
             ibd_finished,
+            sync_config.min_peers_to_exit_ibd,
             assume_valid_targets,
             assume_valid_target_specified,
             header_map,
             block_status_map,
         );
 
-        let chain_services_builder = ChainServicesBuilder::new(shared.clone(), table);
+        let chain_services_builder = ChainServicesBuilder::new(shared.clone(), table)
+            .orphan_block_max_age(
+                sync_config
+                    .orphan_block_max_age_secs
+                    .map(Duration::from_secs)
+                    .unwrap_or(DEFAULT_ORPHAN_BLOCK_MAX_AGE),
+            )
+            .duplicate_block_policy(sync_config.duplicate_block_policy)
+            .log_peer_block_stats(sync_config.log_peer_block_stats);
 
         let pack = SharedPackage {
             chain_services_builder: Some(chain_services_builder),
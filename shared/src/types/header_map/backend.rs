@@ -1,12 +1,74 @@
+use std::fmt::Display;
 use std::path;
 
+use ckb_app_config::HeaderMapBackend;
+use ckb_error::{Error, InternalErrorKind};
+use ckb_logger::warn;
 use ckb_types::packed::Byte32;
+use tempfile::TempDir;
 
-use crate::types::HeaderIndexView;
+use super::{MemoryBackend, RocksdbBackend, SledBackend};
+use crate::types::{HeaderIndexView, HEADER_INDEX_VIEW_FORMAT};
+
+/// The key of the layout version in a persistent backend
+pub(crate) const VERSION_KEY: &[u8] = b"version";
+/// A persistent backend written with another `HeaderIndexView` format is wiped on open.
+pub(crate) const VERSION: &[u8] = &[HEADER_INDEX_VIEW_FORMAT];
+/// The key of the best known header hash in a persistent backend
+pub(crate) const BEST_KEY: &[u8] = b"best";
+
+/// Create the temporary directory of a backend under `tmp_path`, or the system temporary
+/// directory if it's `None`.
+pub(crate) fn create_tmpdir<P>(tmp_path: Option<P>) -> Result<TempDir, Error>
+where
+    P: AsRef<path::Path>,
+{
+    let mut builder = tempfile::Builder::new();
+    builder.prefix("ckb-tmp-");
+    match tmp_path {
+        Some(ref path) => builder
+            .tempdir_in(path)
+            .map_err(|err| backend_error("create a tempdir in", path.as_ref(), err)),
+        None => builder
+            .tempdir()
+            .map_err(|err| backend_error("create a tempdir in", &std::env::temp_dir(), err)),
+    }
+}
+
+/// The error of creating or opening a backend at `path`.
+pub(crate) fn backend_error(action: &str, path: &path::Path, err: impl Display) -> Error {
+    InternalErrorKind::Database
+        .other(format!(
+            "failed to {} {} for the header map: {}",
+            action,
+            path.display(),
+            err
+        ))
+        .into()
+}
+
+/// Decode the value stored under `key`, a corrupted value is logged and treated as missing, the
+/// caller is expected to remove it.
+pub(crate) fn decode_value(key: &Byte32, slice: &[u8]) -> Option<HeaderIndexView> {
+    HeaderIndexView::from_slice(key.as_slice(), slice)
+        .map_err(|err| {
+            warn!(
+                "header map backend drops the corrupted header {}: {}",
+                key, err
+            )
+        })
+        .ok()
+}
 
 #[allow(dead_code)]
-pub(crate) trait KeyValueBackend {
-    fn new<P>(tmpdir: Option<P>) -> Self
+pub(crate) trait KeyValueBackend: Sized {
+    /// `retry_attempts` bounds the retries of a transient backend error
+    fn new<P>(tmpdir: Option<P>, retry_attempts: usize) -> Result<Self, Error>
+    where
+        P: AsRef<path::Path>;
+    /// Open the backend at `path` and keep the headers already stored there, they are dropped
+    /// if they were written by another layout version
+    fn open<P>(path: P, retry_attempts: usize) -> Result<Self, Error>
     where
         P: AsRef<path::Path>;
 
@@ -17,8 +79,195 @@ pub(crate) trait KeyValueBackend {
 
     fn contains_key(&self, key: &Byte32) -> bool;
     fn get(&self, key: &Byte32) -> Option<HeaderIndexView>;
+    /// Look up `keys` together, the results are in the same order as `keys`
+    fn get_batch(&self, keys: &[Byte32]) -> Vec<Option<HeaderIndexView>> {
+        keys.iter().map(|key| self.get(key)).collect()
+    }
     fn insert(&self, value: &HeaderIndexView) -> Option<()>;
     fn insert_batch(&self, values: &[HeaderIndexView]);
     fn remove(&self, key: &Byte32) -> Option<HeaderIndexView>;
     fn remove_no_return(&self, key: &Byte32);
+    /// Remove `keys` together, returns how many of them were present
+    fn remove_batch(&self, keys: &[Byte32]) -> usize;
+    /// The bytes the backend takes on disk
+    fn disk_bytes(&self) -> u64 {
+        0
+    }
+    /// The hash saved by `set_best`
+    fn best(&self) -> Option<Byte32> {
+        None
+    }
+    /// Save the hash of the best known header, it's kept only by a backend on disk
+    fn set_best(&self, _hash: &Byte32) {}
+}
+
+/// The total size of the files under `path`, unreadable entries are skipped.
+pub(crate) fn dir_size(path: &path::Path) -> u64 {
+    std::fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .filter_map(|entry| {
+                    let metadata = entry.metadata().ok()?;
+                    Some(if metadata.is_dir() {
+                        dir_size(&entry.path())
+                    } else {
+                        metadata.len()
+                    })
+                })
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+/// The backend chosen by `HeaderMapBackend` when the header map is constructed.
+pub(crate) enum SelectedBackend {
+    Sled(SledBackend),
+    Rocksdb(RocksdbBackend),
+    /// No disk at all, see `HeaderMap::memory_only`
+    Memory(MemoryBackend),
+}
+
+impl SelectedBackend {
+    pub(crate) fn with_kind<P>(
+        kind: HeaderMapBackend,
+        tmpdir: Option<P>,
+        retry_attempts: usize,
+    ) -> Result<Self, Error>
+    where
+        P: AsRef<path::Path>,
+    {
+        Ok(match kind {
+            HeaderMapBackend::Sled => Self::Sled(SledBackend::new(tmpdir, retry_attempts)?),
+            HeaderMapBackend::Rocksdb => {
+                Self::Rocksdb(RocksdbBackend::new(tmpdir, retry_attempts)?)
+            }
+        })
+    }
+
+    pub(crate) fn open_with_kind<P>(
+        kind: HeaderMapBackend,
+        path: P,
+        retry_attempts: usize,
+    ) -> Result<Self, Error>
+    where
+        P: AsRef<path::Path>,
+    {
+        Ok(match kind {
+            HeaderMapBackend::Sled => Self::Sled(SledBackend::open(path, retry_attempts)?),
+            HeaderMapBackend::Rocksdb => Self::Rocksdb(RocksdbBackend::open(path, retry_attempts)?),
+        })
+    }
+}
+
+impl KeyValueBackend for SelectedBackend {
+    fn new<P>(tmpdir: Option<P>, retry_attempts: usize) -> Result<Self, Error>
+    where
+        P: AsRef<path::Path>,
+    {
+        Self::with_kind(HeaderMapBackend::default(), tmpdir, retry_attempts)
+    }
+
+    fn open<P>(path: P, retry_attempts: usize) -> Result<Self, Error>
+    where
+        P: AsRef<path::Path>,
+    {
+        Self::open_with_kind(HeaderMapBackend::default(), path, retry_attempts)
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Self::Sled(backend) => backend.len(),
+            Self::Rocksdb(backend) => backend.len(),
+            Self::Memory(backend) => backend.len(),
+        }
+    }
+
+    fn contains_key(&self, key: &Byte32) -> bool {
+        match self {
+            Self::Sled(backend) => backend.contains_key(key),
+            Self::Rocksdb(backend) => backend.contains_key(key),
+            Self::Memory(backend) => backend.contains_key(key),
+        }
+    }
+
+    fn get(&self, key: &Byte32) -> Option<HeaderIndexView> {
+        match self {
+            Self::Sled(backend) => backend.get(key),
+            Self::Rocksdb(backend) => backend.get(key),
+            Self::Memory(backend) => backend.get(key),
+        }
+    }
+
+    fn get_batch(&self, keys: &[Byte32]) -> Vec<Option<HeaderIndexView>> {
+        match self {
+            Self::Sled(backend) => backend.get_batch(keys),
+            Self::Rocksdb(backend) => backend.get_batch(keys),
+            Self::Memory(backend) => backend.get_batch(keys),
+        }
+    }
+
+    fn insert(&self, value: &HeaderIndexView) -> Option<()> {
+        match self {
+            Self::Sled(backend) => backend.insert(value),
+            Self::Rocksdb(backend) => backend.insert(value),
+            Self::Memory(backend) => backend.insert(value),
+        }
+    }
+
+    fn insert_batch(&self, values: &[HeaderIndexView]) {
+        match self {
+            Self::Sled(backend) => backend.insert_batch(values),
+            Self::Rocksdb(backend) => backend.insert_batch(values),
+            Self::Memory(backend) => backend.insert_batch(values),
+        }
+    }
+
+    fn remove(&self, key: &Byte32) -> Option<HeaderIndexView> {
+        match self {
+            Self::Sled(backend) => backend.remove(key),
+            Self::Rocksdb(backend) => backend.remove(key),
+            Self::Memory(backend) => backend.remove(key),
+        }
+    }
+
+    fn remove_no_return(&self, key: &Byte32) {
+        match self {
+            Self::Sled(backend) => backend.remove_no_return(key),
+            Self::Rocksdb(backend) => backend.remove_no_return(key),
+            Self::Memory(backend) => backend.remove_no_return(key),
+        }
+    }
+
+    fn remove_batch(&self, keys: &[Byte32]) -> usize {
+        match self {
+            Self::Sled(backend) => backend.remove_batch(keys),
+            Self::Rocksdb(backend) => backend.remove_batch(keys),
+            Self::Memory(backend) => backend.remove_batch(keys),
+        }
+    }
+
+    fn disk_bytes(&self) -> u64 {
+        match self {
+            Self::Sled(backend) => backend.disk_bytes(),
+            Self::Rocksdb(backend) => backend.disk_bytes(),
+            Self::Memory(backend) => backend.disk_bytes(),
+        }
+    }
+
+    fn best(&self) -> Option<Byte32> {
+        match self {
+            Self::Sled(backend) => backend.best(),
+            Self::Rocksdb(backend) => backend.best(),
+            Self::Memory(backend) => backend.best(),
+        }
+    }
+
+    fn set_best(&self, hash: &Byte32) {
+        match self {
+            Self::Sled(backend) => backend.set_best(hash),
+            Self::Rocksdb(backend) => backend.set_best(hash),
+            Self::Memory(backend) => backend.set_best(hash),
+        }
+    }
 }
@@ -0,0 +1,71 @@
+use super::KeyValueBackend;
+use crate::types::HeaderIndexView;
+use ckb_error::Error;
+use ckb_types::packed::Byte32;
+use ckb_util::RwLock;
+use std::collections::HashMap;
+use std::path;
+
+/// Keep the "spilled" headers in memory as well, it's the fallback when no disk backend can be
+/// created, so the memory usage is not bounded by `memory_limit` any more.
+#[derive(Default)]
+pub(crate) struct MemoryBackend {
+    headers: RwLock<HashMap<Byte32, HeaderIndexView>>,
+}
+
+impl KeyValueBackend for MemoryBackend {
+    fn new<P>(_tmpdir: Option<P>, _retry_attempts: usize) -> Result<Self, Error>
+    where
+        P: AsRef<path::Path>,
+    {
+        Ok(Self::default())
+    }
+
+    fn open<P>(_path: P, _retry_attempts: usize) -> Result<Self, Error>
+    where
+        P: AsRef<path::Path>,
+    {
+        Ok(Self::default())
+    }
+
+    fn len(&self) -> usize {
+        self.headers.read().len()
+    }
+
+    fn contains_key(&self, key: &Byte32) -> bool {
+        self.headers.read().contains_key(key)
+    }
+
+    fn get(&self, key: &Byte32) -> Option<HeaderIndexView> {
+        self.headers.read().get(key).cloned()
+    }
+
+    fn insert(&self, value: &HeaderIndexView) -> Option<()> {
+        self.headers
+            .write()
+            .insert(value.hash(), value.clone())
+            .map(|_| ())
+    }
+
+    fn insert_batch(&self, values: &[HeaderIndexView]) {
+        let mut headers = self.headers.write();
+        for value in values {
+            headers.insert(value.hash(), value.clone());
+        }
+    }
+
+    fn remove(&self, key: &Byte32) -> Option<HeaderIndexView> {
+        self.headers.write().remove(key)
+    }
+
+    fn remove_no_return(&self, key: &Byte32) {
+        self.headers.write().remove(key);
+    }
+
+    fn remove_batch(&self, keys: &[Byte32]) -> usize {
+        let mut headers = self.headers.write();
+        keys.iter()
+            .filter(|key| headers.remove(key).is_some())
+            .count()
+    }
+}
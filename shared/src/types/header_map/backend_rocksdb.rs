@@ -0,0 +1,287 @@
+use super::backend::{
+    backend_error, create_tmpdir, decode_value, dir_size, KeyValueBackend, BEST_KEY, VERSION,
+    VERSION_KEY,
+};
+use crate::types::HeaderIndexView;
+use ckb_db::{RocksDB, RocksDBWriteBatch};
+use ckb_db_schema::Col;
+use ckb_error::Error;
+#[cfg(feature = "stats")]
+use ckb_metrics::HistogramTimer;
+use ckb_types::{packed::Byte32, prelude::*};
+use ckb_util::Mutex;
+use std::collections::HashSet;
+use std::path;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use tempfile::TempDir;
+
+// the column of the headers
+const COLUMN_HEADER_MAP: Col = "0";
+// the column of the layout version, the count of headers and the best known header
+const COLUMN_META: Col = "1";
+const COLUMNS: u32 = 2;
+const COUNT_KEY: &[u8] = b"count";
+
+/// Keep the spilled headers in a RocksDB instance, with the same key and value encodings as
+/// `SledBackend`.
+///
+/// RocksDB retries IO errors internally, so an error reaching here is never transient and
+/// always panics.
+///
+/// The count of headers is persisted along with every write, the writes are serialized by
+/// `write_lock` so that checking a key and writing it count once.
+pub(crate) struct RocksdbBackend {
+    count: AtomicUsize,
+    write_lock: Mutex<()>,
+    db: RocksDB,
+    path: path::PathBuf,
+    _tmpdir: Option<TempDir>,
+}
+
+impl RocksdbBackend {
+    // `None` for a missing key, `Some(None)` for a corrupted value
+    fn read(&self, key: &Byte32) -> Option<Option<HeaderIndexView>> {
+        let slice = self
+            .db
+            .get_pinned(COLUMN_HEADER_MAP, key.as_slice())
+            .expect("header map backend get should be ok")?;
+        Some(decode_value(key, slice.as_ref()))
+    }
+
+    // a corrupted value is removed and read as missing
+    fn get_slice(&self, key: &Byte32) -> Option<HeaderIndexView> {
+        let value = self.read(key)?;
+        if value.is_none() {
+            let _guard = self.write_lock.lock();
+            // removed by another thread meanwhile
+            if self.contains_key(key) {
+                self.delete_locked(key);
+            }
+        }
+        value
+    }
+
+    fn delete(&self, key: &Byte32) -> Option<HeaderIndexView> {
+        let _guard = self.write_lock.lock();
+        let old_value = self.read(key)?;
+        self.delete_locked(key);
+        old_value
+    }
+
+    // the caller holds `write_lock` and has checked the key exists
+    fn delete_locked(&self, key: &Byte32) {
+        let mut batch = self.db.new_write_batch();
+        batch
+            .delete(COLUMN_HEADER_MAP, key.as_slice())
+            .expect("header map backend delete should be ok");
+        self.write_locked(batch, self.len() - 1);
+    }
+
+    // the caller holds `write_lock`, `count` is the count of headers after the `batch`
+    fn write_locked(&self, mut batch: RocksDBWriteBatch, count: usize) {
+        batch
+            .put(COLUMN_META, COUNT_KEY, &(count as u64).to_le_bytes())
+            .expect("header map backend put should be ok");
+        self.db
+            .write(&batch)
+            .expect("header map backend write should be ok");
+        self.count.store(count, Ordering::SeqCst);
+    }
+}
+
+impl KeyValueBackend for RocksdbBackend {
+    fn new<P>(tmp_path: Option<P>, _retry_attempts: usize) -> Result<Self, Error>
+    where
+        P: AsRef<path::Path>,
+    {
+        let tmpdir = create_tmpdir(tmp_path)?;
+        let db = RocksDB::try_open_in(tmpdir.path(), COLUMNS)
+            .map_err(|err| backend_error("open a RocksDB database in", tmpdir.path(), err))?;
+
+        Ok(Self {
+            db,
+            path: tmpdir.path().to_path_buf(),
+            _tmpdir: Some(tmpdir),
+            count: AtomicUsize::new(0),
+            write_lock: Mutex::new(()),
+        })
+    }
+
+    fn open<P>(path: P, _retry_attempts: usize) -> Result<Self, Error>
+    where
+        P: AsRef<path::Path>,
+    {
+        let path = path.as_ref();
+        let mut db = RocksDB::try_open_in(path, COLUMNS)
+            .map_err(|err| backend_error("open a RocksDB database in", path, err))?;
+        let meta_error = |err: Error| backend_error("check the version of", path, err);
+
+        let version = db
+            .get_pinned(COLUMN_META, VERSION_KEY)
+            .map_err(meta_error)?
+            .map(|version| version.to_vec());
+        let count = db
+            .get_pinned(COLUMN_META, COUNT_KEY)
+            .map_err(meta_error)?
+            .and_then(|count| <[u8; 8]>::try_from(count.as_ref()).ok())
+            .map(|count| u64::from_le_bytes(count) as usize);
+        let count = match count {
+            Some(count) if version.as_deref() == Some(VERSION) => count,
+            // wipe the headers of another layout, or of no known count, without reading them
+            // one by one
+            _ => {
+                db.drop_cf(COLUMN_HEADER_MAP).map_err(meta_error)?;
+                db.create_cf(COLUMN_HEADER_MAP).map_err(meta_error)?;
+                let mut batch = db.new_write_batch();
+                batch
+                    .put(COLUMN_META, VERSION_KEY, VERSION)
+                    .map_err(meta_error)?;
+                batch
+                    .put(COLUMN_META, COUNT_KEY, &0u64.to_le_bytes())
+                    .map_err(meta_error)?;
+                batch.delete(COLUMN_META, BEST_KEY).map_err(meta_error)?;
+                db.write(&batch).map_err(meta_error)?;
+                0
+            }
+        };
+
+        Ok(Self {
+            count: AtomicUsize::new(count),
+            write_lock: Mutex::new(()),
+            db,
+            path: path.to_path_buf(),
+            _tmpdir: None,
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.count.load(Ordering::SeqCst)
+    }
+
+    fn contains_key(&self, key: &Byte32) -> bool {
+        #[cfg(feature = "stats")]
+        let _trace_timer = backend_timer("contains_key");
+        self.db
+            .get_pinned(COLUMN_HEADER_MAP, key.as_slice())
+            .expect("header map backend contains_key should be ok")
+            .is_some()
+    }
+
+    fn get(&self, key: &Byte32) -> Option<HeaderIndexView> {
+        #[cfg(feature = "stats")]
+        let _trace_timer = backend_timer("get");
+        self.get_slice(key)
+    }
+
+    // all keys are read from a single snapshot
+    fn get_batch(&self, keys: &[Byte32]) -> Vec<Option<HeaderIndexView>> {
+        #[cfg(feature = "stats")]
+        let _trace_timer = backend_timer("get_batch");
+        let snapshot = self.db.get_snapshot();
+        keys.iter()
+            .map(|key| {
+                let slice = snapshot
+                    .get_pinned(COLUMN_HEADER_MAP, key.as_slice())
+                    .expect("header map backend get should be ok")?;
+                // read the corrupted value again to remove it
+                decode_value(key, slice.as_ref()).or_else(|| self.get_slice(key))
+            })
+            .collect()
+    }
+
+    fn insert(&self, value: &HeaderIndexView) -> Option<()> {
+        #[cfg(feature = "stats")]
+        let _trace_timer = backend_timer("insert");
+        let key = value.hash();
+        let _guard = self.write_lock.lock();
+        let last_value = self.contains_key(&key);
+        let mut batch = self.db.new_write_batch();
+        batch
+            .put(COLUMN_HEADER_MAP, key.as_slice(), &value.to_vec())
+            .expect("header map backend put should be ok");
+        self.write_locked(batch, self.len() + usize::from(!last_value));
+        last_value.then_some(())
+    }
+
+    fn insert_batch(&self, values: &[HeaderIndexView]) {
+        #[cfg(feature = "stats")]
+        let _trace_timer = backend_timer("insert_batch");
+        let _guard = self.write_lock.lock();
+        let mut new_keys = HashSet::new();
+        let mut batch = self.db.new_write_batch();
+        for value in values {
+            let key = value.hash();
+            if !self.contains_key(&key) {
+                new_keys.insert(key.clone());
+            }
+            batch
+                .put(COLUMN_HEADER_MAP, key.as_slice(), &value.to_vec())
+                .expect("header map backend put should be ok");
+        }
+        self.write_locked(batch, self.len() + new_keys.len());
+    }
+
+    fn remove(&self, key: &Byte32) -> Option<HeaderIndexView> {
+        #[cfg(feature = "stats")]
+        let _trace_timer = backend_timer("remove");
+        self.delete(key)
+    }
+
+    fn remove_no_return(&self, key: &Byte32) {
+        #[cfg(feature = "stats")]
+        let _trace_timer = backend_timer("remove_no_return");
+        self.delete(key);
+    }
+
+    fn remove_batch(&self, keys: &[Byte32]) -> usize {
+        #[cfg(feature = "stats")]
+        let _trace_timer = backend_timer("remove_batch");
+        let _guard = self.write_lock.lock();
+        let mut removed = HashSet::new();
+        let mut batch = self.db.new_write_batch();
+        for key in keys {
+            if !removed.contains(key) && self.contains_key(key) {
+                removed.insert(key.clone());
+                batch
+                    .delete(COLUMN_HEADER_MAP, key.as_slice())
+                    .expect("header map backend delete should be ok");
+            }
+        }
+        if !removed.is_empty() {
+            self.write_locked(batch, self.len() - removed.len());
+        }
+        removed.len()
+    }
+
+    fn disk_bytes(&self) -> u64 {
+        dir_size(&self.path)
+    }
+
+    fn best(&self) -> Option<Byte32> {
+        self.db
+            .get_pinned(COLUMN_META, BEST_KEY)
+            .expect("header map backend get should be ok")
+            .and_then(|slice| Byte32::from_slice(slice.as_ref()).ok())
+    }
+
+    fn set_best(&self, hash: &Byte32) {
+        let mut batch = self.db.new_write_batch();
+        batch
+            .put(COLUMN_META, BEST_KEY, hash.as_slice())
+            .expect("header map backend put should be ok");
+        self.db
+            .write(&batch)
+            .expect("header map backend write should be ok");
+    }
+}
+
+#[cfg(feature = "stats")]
+fn backend_timer(operation: &str) -> Option<HistogramTimer> {
+    ckb_metrics::handle().map(|metrics| {
+        metrics
+            .ckb_header_map_backend_ops_duration
+            .with_label_values(&[operation])
+            .start_timer()
+    })
+}
@@ -1,46 +1,121 @@
-use super::KeyValueBackend;
+use super::backend::{
+    backend_error, create_tmpdir, decode_value, KeyValueBackend, BEST_KEY, VERSION, VERSION_KEY,
+};
 use crate::types::HeaderIndexView;
+use ckb_error::Error;
+use ckb_logger::debug;
+#[cfg(feature = "stats")]
+use ckb_metrics::HistogramTimer;
 use ckb_types::{packed::Byte32, prelude::*};
 use sled::{Config, Db, Mode};
+use std::io::ErrorKind;
 use std::path;
 use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::Duration;
 use tempfile::TempDir;
 
+// the backoff before the first retry, it's doubled on every retry
+const RETRY_BACKOFF: Duration = Duration::from_millis(10);
+
 pub(crate) struct SledBackend {
     count: AtomicUsize,
     db: Db,
-    _tmpdir: TempDir,
+    retry_attempts: usize,
+    _tmpdir: Option<TempDir>,
+}
+
+// the tree keeping the layout version and the best known header, apart from the headers in the
+// default tree
+const META_TREE: &[u8] = b"meta";
+
+fn open_db(path: &path::Path) -> Result<Db, Error> {
+    // use a smaller system page cache here since we are using sled as a temporary storage,
+    // most of the time we will only read header from memory.
+    Config::new()
+        .mode(Mode::HighThroughput)
+        .cache_capacity(64 * 1024 * 1024)
+        .path(path)
+        .open()
+        .map_err(|err| backend_error("open a sled database in", path, err))
+}
+
+impl SledBackend {
+    // Retry `operation` with an exponential backoff while it fails transiently, a permanent
+    // error (corruption, unsupported, bug) or running out of attempts still panics.
+    fn with_retry<T>(&self, name: &str, mut operation: impl FnMut() -> sled::Result<T>) -> T {
+        let mut backoff = RETRY_BACKOFF;
+        let mut attempt = 0;
+        loop {
+            match operation() {
+                Ok(value) => return value,
+                Err(err) if is_transient(&err) && attempt < self.retry_attempts => {
+                    attempt += 1;
+                    debug!(
+                        "header map backend {} failed transiently: {}, retry {}/{} after {:?}",
+                        name, err, attempt, self.retry_attempts, backoff
+                    );
+                    thread::sleep(backoff);
+                    backoff *= 2;
+                }
+                Err(err) => panic!("header map backend {name} should be ok, but {err}"),
+            }
+        }
+    }
+}
+
+fn is_transient(err: &sled::Error) -> bool {
+    match err {
+        sled::Error::Io(err) => matches!(
+            err.kind(),
+            ErrorKind::Interrupted | ErrorKind::WouldBlock | ErrorKind::TimedOut
+        ),
+        _ => false,
+    }
 }
 
 impl KeyValueBackend for SledBackend {
-    fn new<P>(tmp_path: Option<P>) -> Self
+    fn new<P>(tmp_path: Option<P>, retry_attempts: usize) -> Result<Self, Error>
     where
         P: AsRef<path::Path>,
     {
-        let mut builder = tempfile::Builder::new();
-        builder.prefix("ckb-tmp-");
-        let tmpdir = if let Some(ref path) = tmp_path {
-            builder.tempdir_in(path)
-        } else {
-            builder.tempdir()
-        }
-        .expect("failed to create a tempdir to save header map into disk");
-
-        // use a smaller system page cache here since we are using sled as a temporary storage,
-        // most of the time we will only read header from memory.
-        let db: Db = Config::new()
-            .mode(Mode::HighThroughput)
-            .cache_capacity(64 * 1024 * 1024)
-            .path(tmpdir.path())
-            .open()
-            .expect("failed to open a key-value database to save header map into disk");
-
-        Self {
+        let tmpdir = create_tmpdir(tmp_path)?;
+        let db = open_db(tmpdir.path())?;
+
+        Ok(Self {
             db,
-            _tmpdir: tmpdir,
+            _tmpdir: Some(tmpdir),
             count: AtomicUsize::new(0),
+            retry_attempts,
+        })
+    }
+
+    fn open<P>(path: P, retry_attempts: usize) -> Result<Self, Error>
+    where
+        P: AsRef<path::Path>,
+    {
+        let path = path.as_ref();
+        let db = open_db(path)?;
+        let meta_error = |err: sled::Error| backend_error("check the version of", path, err);
+        let meta = db.open_tree(META_TREE).map_err(meta_error)?;
+        let version = meta.get(VERSION_KEY).map_err(meta_error)?;
+        if version.as_deref() != Some(VERSION) {
+            debug!(
+                "header map backend version {:?} mismatches {:?}, wipe it",
+                version, VERSION
+            );
+            db.clear().map_err(meta_error)?;
+            meta.remove(BEST_KEY).map_err(meta_error)?;
+            meta.insert(VERSION_KEY, VERSION).map_err(meta_error)?;
         }
+
+        Ok(Self {
+            count: AtomicUsize::new(db.len()),
+            db,
+            _tmpdir: None,
+            retry_attempts,
+        })
     }
 
     fn len(&self) -> usize {
@@ -48,24 +123,28 @@ impl KeyValueBackend for SledBackend {
     }
 
     fn contains_key(&self, key: &Byte32) -> bool {
-        self.db
-            .contains_key(key.as_slice())
-            .expect("sled contains_key")
+        #[cfg(feature = "stats")]
+        let _trace_timer = backend_timer("contains_key");
+        self.with_retry("contains_key", || self.db.contains_key(key.as_slice()))
     }
 
     fn get(&self, key: &Byte32) -> Option<HeaderIndexView> {
-        self.db
-            .get(key.as_slice())
-            .unwrap_or_else(|err| panic!("read header map from disk should be ok, but {err}"))
-            .map(|slice| HeaderIndexView::from_slice_should_be_ok(key.as_slice(), slice.as_ref()))
+        #[cfg(feature = "stats")]
+        let _trace_timer = backend_timer("get");
+        let slice = self.with_retry("get", || self.db.get(key.as_slice()))?;
+        let value = decode_value(key, &slice);
+        if value.is_none() {
+            self.remove_no_return(key);
+        }
+        value
     }
 
     fn insert(&self, value: &HeaderIndexView) -> Option<()> {
+        #[cfg(feature = "stats")]
+        let _trace_timer = backend_timer("insert");
         let key = value.hash();
-        let last_value = self
-            .db
-            .insert(key.as_slice(), value.to_vec())
-            .expect("failed to insert item to sled");
+        let last_value =
+            self.with_retry("insert", || self.db.insert(key.as_slice(), value.to_vec()));
         if last_value.is_none() {
             self.count.fetch_add(1, Ordering::SeqCst);
         }
@@ -73,13 +152,13 @@ impl KeyValueBackend for SledBackend {
     }
 
     fn insert_batch(&self, values: &[HeaderIndexView]) {
+        #[cfg(feature = "stats")]
+        let _trace_timer = backend_timer("insert_batch");
         let mut count = 0;
         for value in values {
             let key = value.hash();
-            let last_value = self
-                .db
-                .insert(key.as_slice(), value.to_vec())
-                .expect("failed to insert item to sled");
+            let last_value =
+                self.with_retry("insert", || self.db.insert(key.as_slice(), value.to_vec()));
             if last_value.is_none() {
                 count += 1;
             }
@@ -88,24 +167,63 @@ impl KeyValueBackend for SledBackend {
     }
 
     fn remove(&self, key: &Byte32) -> Option<HeaderIndexView> {
-        let old_value = self
-            .db
-            .remove(key.as_slice())
-            .expect("failed to remove item from sled");
+        #[cfg(feature = "stats")]
+        let _trace_timer = backend_timer("remove");
+        let old_value = self.with_retry("remove", || self.db.remove(key.as_slice()));
 
-        old_value.map(|slice| {
+        old_value.and_then(|slice| {
             self.count.fetch_sub(1, Ordering::SeqCst);
-            HeaderIndexView::from_slice_should_be_ok(key.as_slice(), &slice)
+            decode_value(key, &slice)
         })
     }
 
     fn remove_no_return(&self, key: &Byte32) {
-        let old_value = self
-            .db
-            .remove(key.as_slice())
-            .expect("failed to remove item from sled");
+        #[cfg(feature = "stats")]
+        let _trace_timer = backend_timer("remove_no_return");
+        let old_value = self.with_retry("remove", || self.db.remove(key.as_slice()));
         if old_value.is_some() {
             self.count.fetch_sub(1, Ordering::SeqCst);
         }
     }
+
+    fn remove_batch(&self, keys: &[Byte32]) -> usize {
+        #[cfg(feature = "stats")]
+        let _trace_timer = backend_timer("remove_batch");
+        let count = keys
+            .iter()
+            .filter(|key| {
+                self.with_retry("remove", || self.db.remove(key.as_slice()))
+                    .is_some()
+            })
+            .count();
+        self.count.fetch_sub(count, Ordering::SeqCst);
+        count
+    }
+
+    fn disk_bytes(&self) -> u64 {
+        self.db.size_on_disk().unwrap_or_default()
+    }
+
+    fn best(&self) -> Option<Byte32> {
+        self.with_retry("best", || self.db.open_tree(META_TREE)?.get(BEST_KEY))
+            .and_then(|slice| Byte32::from_slice(&slice).ok())
+    }
+
+    fn set_best(&self, hash: &Byte32) {
+        self.with_retry("set_best", || {
+            self.db
+                .open_tree(META_TREE)?
+                .insert(BEST_KEY, hash.as_slice())
+        });
+    }
+}
+
+#[cfg(feature = "stats")]
+fn backend_timer(operation: &str) -> Option<HistogramTimer> {
+    ckb_metrics::handle().map(|metrics| {
+        metrics
+            .ckb_header_map_backend_ops_duration
+            .with_label_values(&[operation])
+            .start_timer()
+    })
 }
@@ -1,18 +1,23 @@
-use std::path;
+use std::collections::HashSet;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 #[cfg(feature = "stats")]
 use ckb_logger::info;
 use ckb_metrics::HistogramTimer;
+use ckb_util::Mutex;
 #[cfg(feature = "stats")]
-use ckb_util::{Mutex, MutexGuard};
+use ckb_util::MutexGuard;
 
 use ckb_types::packed::Byte32;
+use tokio::sync::Notify;
 
 use super::{KeyValueBackend, MemoryMap};
 use crate::types::HeaderIndexView;
 
+// trim out of band once the memory map grows beyond this multiple of the limit
+const OUT_OF_BAND_TRIM_FACTOR: usize = 2;
+
 pub(crate) struct HeaderMapKernel<Backend>
 where
     Backend: KeyValueBackend,
@@ -23,6 +28,14 @@ where
     memory_limit: usize,
     // if ckb is in IBD mode, don't shrink memory map
     ibd_finished: Arc<AtomicBool>,
+    // serializes evictions, so the timer task and `evict_to` never move the same items twice
+    evict_lock: Mutex<()>,
+    // wakes the trim task when the memory map outgrows the limit between two ticks
+    trim_requested: Arc<Notify>,
+    // the headers inserted since the last `flush`, `None` if the backend isn't kept on restart
+    unflushed: Option<Mutex<HashSet<Byte32>>>,
+    // the best known header saved by the next `flush`
+    best_known: Mutex<Option<Byte32>>,
     // Statistics
     #[cfg(feature = "stats")]
     stats: Mutex<HeaderMapKernelStats>,
@@ -48,16 +61,14 @@ impl<Backend> HeaderMapKernel<Backend>
 where
     Backend: KeyValueBackend,
 {
-    pub(crate) fn new<P>(
-        tmpdir: Option<P>,
+    pub(crate) fn new(
+        backend: Backend,
         memory_limit: usize,
+        persistent: bool,
         ibd_finished: Arc<AtomicBool>,
-    ) -> Self
-    where
-        P: AsRef<path::Path>,
-    {
+    ) -> Self {
         let memory = Default::default();
-        let backend = Backend::new(tmpdir);
+        let unflushed = persistent.then(Default::default);
 
         #[cfg(not(feature = "stats"))]
         {
@@ -66,6 +77,10 @@ where
                 backend,
                 memory_limit,
                 ibd_finished,
+                evict_lock: Mutex::new(()),
+                trim_requested: Default::default(),
+                unflushed,
+                best_known: Default::default(),
             }
         }
 
@@ -76,6 +91,10 @@ where
                 backend,
                 memory_limit,
                 ibd_finished,
+                evict_lock: Mutex::new(()),
+                trim_requested: Default::default(),
+                unflushed,
+                best_known: Default::default(),
                 stats: Mutex::new(HeaderMapKernelStats::new(50_000)),
             }
         }
@@ -107,6 +126,11 @@ where
     }
 
     pub(crate) fn get(&self, hash: &Byte32) -> Option<HeaderIndexView> {
+        self.get_memory(hash).or_else(|| self.get_backend(hash))
+    }
+
+    /// Look up the memory map only, a hit is refreshed
+    pub(crate) fn get_memory(&self, hash: &Byte32) -> Option<HeaderIndexView> {
         #[cfg(feature = "stats")]
         {
             self.stats().tick_primary_select();
@@ -121,7 +145,11 @@ where
         if let Some(metrics) = ckb_metrics::handle() {
             metrics.ckb_header_map_memory_hit_miss_count.miss.inc();
         }
+        None
+    }
 
+    /// Move a header missed by `get_memory` from the backend into the memory map
+    pub(crate) fn get_backend(&self, hash: &Byte32) -> Option<HeaderIndexView> {
         if self.backend.is_empty() {
             return None;
         }
@@ -134,6 +162,7 @@ where
             {
                 self.stats().tick_primary_insert();
             }
+            self.mark_unflushed(view.hash());
             self.memory.insert(view.clone());
             Some(view)
         } else {
@@ -141,13 +170,81 @@ where
         }
     }
 
+    /// Get the headers of `hashes`, the results are in the same order as `hashes`
+    ///
+    /// Like `get`, a memory hit is refreshed and a backend hit is moved into memory, but the
+    /// misses of memory are looked up in the backend together.
+    pub(crate) fn get_batch(&self, hashes: &[Byte32]) -> Vec<Option<HeaderIndexView>> {
+        let mut views: Vec<Option<HeaderIndexView>> = hashes
+            .iter()
+            .map(|hash| self.memory.get_refresh(hash))
+            .collect();
+        if let Some(metrics) = ckb_metrics::handle() {
+            let hits = views.iter().filter(|view| view.is_some()).count();
+            metrics
+                .ckb_header_map_memory_hit_miss_count
+                .hit
+                .inc_by(hits as u64);
+            metrics
+                .ckb_header_map_memory_hit_miss_count
+                .miss
+                .inc_by((hashes.len() - hits) as u64);
+        }
+
+        if self.backend.is_empty() {
+            return views;
+        }
+        let (miss_indexes, misses): (Vec<usize>, Vec<Byte32>) = views
+            .iter()
+            .enumerate()
+            .filter(|(_, view)| view.is_none())
+            .map(|(index, _)| (index, hashes[index].clone()))
+            .unzip();
+        if misses.is_empty() {
+            return views;
+        }
+        let mut hits = Vec::new();
+        for (index, view) in miss_indexes
+            .into_iter()
+            .zip(self.backend.get_batch(&misses))
+        {
+            if let Some(view) = view {
+                hits.push(view.hash());
+                self.mark_unflushed(view.hash());
+                self.memory.insert(view.clone());
+                views[index] = Some(view);
+            }
+        }
+        if !hits.is_empty() {
+            self.backend.remove_batch(&hits);
+        }
+        views
+    }
+
+    /// Get a header without refreshing it in memory or moving it out of the backend
+    #[cfg(feature = "stats")]
+    pub(crate) fn peek(&self, hash: &Byte32) -> Option<HeaderIndexView> {
+        self.memory.peek(hash).or_else(|| {
+            if self.backend.is_empty() {
+                None
+            } else {
+                self.backend.get(hash)
+            }
+        })
+    }
+
     pub(crate) fn insert(&self, view: HeaderIndexView) -> Option<()> {
         #[cfg(feature = "stats")]
         {
             self.trace();
             self.stats().tick_primary_insert();
         }
-        self.memory.insert(view)
+        self.mark_unflushed(view.hash());
+        let ret = self.memory.insert(view);
+        if self.memory.len() > self.memory_limit * OUT_OF_BAND_TRIM_FACTOR {
+            self.trim_requested.notify_one();
+        }
+        ret
     }
 
     pub(crate) fn remove(&self, hash: &Byte32) {
@@ -169,15 +266,76 @@ where
         let _trace_timer: Option<HistogramTimer> = ckb_metrics::handle()
             .map(|handle| handle.ckb_header_map_limit_memory_duration.start_timer());
 
-        if let Some(values) = self.memory.front_n(self.memory_limit) {
-            tokio::task::block_in_place(|| {
-                self.backend.insert_batch(&values);
-            });
+        self.evict_to(self.memory_limit);
+    }
+
+    /// Notified when `limit_memory` should run before the next tick
+    pub(crate) fn trim_requested(&self) -> Arc<Notify> {
+        Arc::clone(&self.trim_requested)
+    }
+
+    /// The number of items in memory and in the backend, and the memory limit in items
+    pub(crate) fn occupancy(&self) -> (usize, usize, usize) {
+        (self.memory.len(), self.backend.len(), self.memory_limit)
+    }
+
+    /// Evict the oldest items from memory into the backend until at most `size_limit` items left.
+    ///
+    /// Returns the number of evicted items.
+    pub(crate) fn evict_to(&self, size_limit: usize) -> usize {
+        let _guard = self.evict_lock.lock();
+        let Some(values) = self.memory.front_n(size_limit) else {
+            return 0;
+        };
+        tokio::task::block_in_place(|| {
+            self.backend.insert_batch(&values);
+        });
+
+        // If IBD is not finished, don't shrink memory map
+        let allow_shrink_to_fit = self.ibd_finished.load(Ordering::Acquire);
+        let evicted = self
+            .memory
+            .remove_batch(values.iter().map(|value| value.hash()), allow_shrink_to_fit);
+        if let Some(metrics) = ckb_metrics::handle() {
+            metrics.ckb_header_map_evicted_count.inc_by(evicted as u64);
+        }
+        evicted
+    }
+
+    // track `hash` for the next `flush`, a header moved out of the backend is written back too
+    fn mark_unflushed(&self, hash: Byte32) {
+        if let Some(unflushed) = &self.unflushed {
+            unflushed.lock().insert(hash);
+        }
+    }
 
-            // If IBD is not finished, don't shrink memory map
-            let allow_shrink_to_fit = self.ibd_finished.load(Ordering::Acquire);
-            self.memory
-                .remove_batch(values.iter().map(|value| value.hash()), allow_shrink_to_fit);
+    /// Remember `hash` as the best known header, it's saved along with the next `flush`
+    pub(crate) fn set_best_known(&self, hash: &Byte32) {
+        if self.unflushed.is_some() {
+            *self.best_known.lock() = Some(hash.clone());
+        }
+    }
+
+    /// Copy the items inserted since the last flush and still in memory into the backend, then
+    /// save the best known header. The memory map is kept as is.
+    ///
+    /// Does nothing unless the map is persistent.
+    pub(crate) fn flush(&self) {
+        let Some(unflushed) = &self.unflushed else {
+            return;
+        };
+        let _guard = self.evict_lock.lock();
+        let hashes: Vec<Byte32> = unflushed.lock().drain().collect();
+        let values: Vec<HeaderIndexView> = hashes
+            .iter()
+            .filter_map(|hash| self.memory.peek(hash))
+            .collect();
+        if !values.is_empty() {
+            self.backend.insert_batch(&values);
+        }
+        // after the headers, so the saved best known header is never ahead of them
+        if let Some(hash) = self.best_known.lock().take() {
+            self.backend.set_best(&hash);
         }
     }
 
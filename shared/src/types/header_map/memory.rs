@@ -75,7 +75,6 @@ impl default::Default for MemoryMap {
 }
 
 impl MemoryMap {
-    #[cfg(feature = "stats")]
     pub(crate) fn len(&self) -> usize {
         self.0.read().len()
     }
@@ -84,6 +83,14 @@ impl MemoryMap {
         self.0.read().contains_key(key)
     }
 
+    pub(crate) fn peek(&self, key: &Byte32) -> Option<HeaderIndexView> {
+        self.0
+            .read()
+            .get(key)
+            .cloned()
+            .map(|inner| (key.clone(), inner).into())
+    }
+
     pub(crate) fn get_refresh(&self, key: &Byte32) -> Option<HeaderIndexView> {
         let mut guard = self.0.write();
         guard
@@ -137,7 +144,12 @@ impl MemoryMap {
         }
     }
 
-    pub(crate) fn remove_batch(&self, keys: impl Iterator<Item = Byte32>, shrink_to_fit: bool) {
+    /// Returns how many of `keys` were present
+    pub(crate) fn remove_batch(
+        &self,
+        keys: impl Iterator<Item = Byte32>,
+        shrink_to_fit: bool,
+    ) -> usize {
         let mut guard = self.0.write();
         let mut keys_count = 0;
         for key in keys {
@@ -147,11 +159,12 @@ impl MemoryMap {
         }
 
         if let Some(metrics) = ckb_metrics::handle() {
-            metrics.ckb_header_map_memory_count.sub(keys_count)
+            metrics.ckb_header_map_memory_count.sub(keys_count as i64)
         }
 
         if shrink_to_fit {
             shrink_to_fit!(guard, SHRINK_THRESHOLD);
         }
+        keys_count
     }
 }
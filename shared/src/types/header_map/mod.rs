@@ -1,6 +1,10 @@
+use ckb_app_config::HeaderMapBackend;
 use ckb_async_runtime::Handle;
+use ckb_error::Error;
 use ckb_logger::info;
 use ckb_stop_handler::{new_tokio_exit_rx, CancellationToken};
+#[cfg(feature = "stats")]
+use ckb_types::core::BlockNumber;
 use ckb_types::packed::Byte32;
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
@@ -11,24 +15,73 @@ use ckb_metrics::HistogramTimer;
 use tokio::time::MissedTickBehavior;
 
 mod backend;
+mod backend_memory;
+mod backend_rocksdb;
 mod backend_sled;
 mod kernel_lru;
 mod memory;
+#[cfg(test)]
+mod tests;
 
 pub(crate) use self::{
-    backend::KeyValueBackend, backend_sled::SledBackend, kernel_lru::HeaderMapKernel,
+    backend::{KeyValueBackend, SelectedBackend},
+    backend_memory::MemoryBackend,
+    backend_rocksdb::RocksdbBackend,
+    backend_sled::SledBackend,
+    kernel_lru::HeaderMapKernel,
     memory::MemoryMap,
 };
 
+#[cfg(feature = "stats")]
+use super::get_skip_height;
 use super::HeaderIndexView;
 
 pub struct HeaderMap {
-    inner: Arc<HeaderMapKernel<SledBackend>>,
+    inner: Arc<HeaderMapKernel<SelectedBackend>>,
+    // runs the backend reads of `get_async`
+    async_handle: Handle,
+}
+
+/// The contiguous run of headers below a header, see `HeaderMap::coverage_gap`.
+#[cfg(feature = "stats")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HeaderMapCoverage {
+    /// The lowest height reachable contiguously via `parent_hash`
+    pub lowest_contiguous: BlockNumber,
+    /// The first ancestor missing from the header map, `None` if the run reaches genesis
+    pub first_missing: Option<Byte32>,
+}
+
+/// A header whose `skip_hash` is not its skip-list ancestor, see `HeaderMap::find_bad_skip_hash`.
+#[cfg(feature = "stats")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BadSkipHash {
+    pub number: BlockNumber,
+    pub hash: Byte32,
+    /// The ancestor at the skip height
+    pub expected: Byte32,
+    pub actual: Option<Byte32>,
+}
+
+/// The occupancy of the header map, see `HeaderMap::stats`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HeaderMapStats {
+    /// The number of headers in memory
+    pub memory_entries: usize,
+    /// The number of headers spilled into the disk backend
+    pub backend_entries: usize,
+    /// The maximum number of headers kept in memory
+    pub size_limit: usize,
+    /// The estimated bytes of the headers in memory
+    pub memory_bytes: usize,
 }
 
-const INTERVAL: Duration = Duration::from_millis(5000);
 const ITEM_BYTES_SIZE: usize = size_of::<HeaderIndexView>();
 const WARN_THRESHOLD: usize = ITEM_BYTES_SIZE * 100_000;
+/// The default number of retries of a transient backend error
+pub const DEFAULT_BACKEND_RETRY_ATTEMPTS: usize = 3;
+/// The default interval of trimming the memory map down to the memory limit
+pub const DEFAULT_TRIM_INTERVAL: Duration = Duration::from_millis(5000);
 
 impl HeaderMap {
     pub fn new<P>(
@@ -40,6 +93,125 @@ impl HeaderMap {
     where
         P: AsRef<path::Path>,
     {
+        Self::with_backend_retry(
+            tmpdir,
+            memory_limit,
+            DEFAULT_BACKEND_RETRY_ATTEMPTS,
+            async_handle,
+            ibd_finished,
+        )
+    }
+
+    /// Same as `new`, a transient backend error is retried at most `backend_retry_attempts`
+    /// times before giving up.
+    ///
+    /// Panics if the backend can't be created, use `with_backend` to handle the error.
+    pub fn with_backend_retry<P>(
+        tmpdir: Option<P>,
+        memory_limit: usize,
+        backend_retry_attempts: usize,
+        async_handle: &Handle,
+        ibd_finished: Arc<AtomicBool>,
+    ) -> Self
+    where
+        P: AsRef<path::Path>,
+    {
+        Self::with_backend(
+            tmpdir,
+            memory_limit,
+            HeaderMapBackend::default(),
+            backend_retry_attempts,
+            DEFAULT_TRIM_INTERVAL,
+            async_handle,
+            ibd_finished,
+        )
+        .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Same as `with_backend_retry`, the spilled headers are stored by the `backend` engine.
+    ///
+    /// The memory map is trimmed to `memory_limit` every `trim_interval`, or as soon as it
+    /// grows beyond twice the limit.
+    pub fn with_backend<P>(
+        tmpdir: Option<P>,
+        memory_limit: usize,
+        backend: HeaderMapBackend,
+        backend_retry_attempts: usize,
+        trim_interval: Duration,
+        async_handle: &Handle,
+        ibd_finished: Arc<AtomicBool>,
+    ) -> Result<Self, Error>
+    where
+        P: AsRef<path::Path>,
+    {
+        let backend = SelectedBackend::with_kind(backend, tmpdir, backend_retry_attempts)?;
+        Ok(Self::with_selected_backend(
+            backend,
+            memory_limit,
+            false,
+            trim_interval,
+            async_handle,
+            ibd_finished,
+        ))
+    }
+
+    /// A header map without any disk backend, the headers trimmed from the memory map are
+    /// still kept in memory, so `memory_limit` only bounds the LRU part.
+    ///
+    /// It's the fallback when the disk backend can't be created.
+    pub fn memory_only(
+        memory_limit: usize,
+        trim_interval: Duration,
+        async_handle: &Handle,
+        ibd_finished: Arc<AtomicBool>,
+    ) -> Self {
+        Self::with_selected_backend(
+            SelectedBackend::Memory(MemoryBackend::default()),
+            memory_limit,
+            false,
+            trim_interval,
+            async_handle,
+            ibd_finished,
+        )
+    }
+
+    /// Same as `with_backend`, but the headers are stored in `path` instead of a temporary
+    /// directory, the headers stored there by the last run are reused.
+    ///
+    /// The headers inserted into memory and the best known header are flushed into the backend
+    /// on every trim and when the map is dropped, so a crash loses at most one `trim_interval`.
+    /// A flushed header stays in memory too, so it's also counted by `backend_entries`.
+    pub fn open_persistent<P>(
+        path: P,
+        memory_limit: usize,
+        backend: HeaderMapBackend,
+        backend_retry_attempts: usize,
+        trim_interval: Duration,
+        async_handle: &Handle,
+        ibd_finished: Arc<AtomicBool>,
+    ) -> Result<Self, Error>
+    where
+        P: AsRef<path::Path>,
+    {
+        let backend = SelectedBackend::open_with_kind(backend, path, backend_retry_attempts)?;
+        Ok(Self::with_selected_backend(
+            backend,
+            memory_limit,
+            true,
+            trim_interval,
+            async_handle,
+            ibd_finished,
+        ))
+    }
+
+    fn with_selected_backend(
+        backend: SelectedBackend,
+        memory_limit: usize,
+        persistent: bool,
+        trim_interval: Duration,
+        async_handle: &Handle,
+        ibd_finished: Arc<AtomicBool>,
+    ) -> Self {
         if memory_limit < ITEM_BYTES_SIZE {
             panic!("The limit setting is too low");
         }
@@ -50,27 +222,76 @@ impl HeaderMap {
             );
         }
         let size_limit = memory_limit / ITEM_BYTES_SIZE;
-        let inner = Arc::new(HeaderMapKernel::new(tmpdir, size_limit, ibd_finished));
-        let map = Arc::clone(&inner);
+        let inner = Arc::new(HeaderMapKernel::new(
+            backend,
+            size_limit,
+            persistent,
+            ibd_finished,
+        ));
+        // the task doesn't keep the map alive, so a persistent backend is closed on drop
+        let map = Arc::downgrade(&inner);
+        let trim_requested = inner.trim_requested();
         let stop_rx: CancellationToken = new_tokio_exit_rx();
 
         async_handle.spawn(async move {
-            let mut interval = tokio::time::interval(INTERVAL);
+            let mut interval = tokio::time::interval(trim_interval);
             interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
             loop {
                 tokio::select! {
-                    _ = interval.tick() => {
-                        map.limit_memory();
-                    }
+                    _ = interval.tick() => {}
+                    _ = trim_requested.notified() => {}
                     _ = stop_rx.cancelled() => {
                         info!("HeaderMap limit_memory received exit signal, exit now");
                         break
                     },
                 }
+                match map.upgrade() {
+                    Some(map) => {
+                        map.limit_memory();
+                        map.flush();
+                        update_metrics(&map);
+                    }
+                    None => break,
+                }
             }
         });
 
-        Self { inner }
+        Self {
+            inner,
+            async_handle: async_handle.clone(),
+        }
+    }
+
+    /// Synchronously evict the in-memory map down to `target_bytes`, flushing the evicted
+    /// headers into the backend.
+    ///
+    /// Evictions are serialized with the background `limit_memory` task, so calling this
+    /// while the timer fires never moves the same headers twice. The timer keeps running
+    /// afterwards and trims to the configured memory limit as usual.
+    ///
+    /// Returns the number of evicted headers.
+    pub fn evict_to(&self, target_bytes: usize) -> usize {
+        let _trace_timer: Option<HistogramTimer> = ckb_metrics::handle().map(|metric| {
+            metric
+                .ckb_header_map_ops_duration
+                .with_label_values(&["evict_to"])
+                .start_timer()
+        });
+
+        self.inner.evict_to(target_bytes / ITEM_BYTES_SIZE)
+    }
+
+    /// How full the memory map is and how many headers are spilled into the backend
+    ///
+    /// Both counts are kept along the way, this is cheap enough for production metrics.
+    pub fn stats(&self) -> HeaderMapStats {
+        let (memory_entries, backend_entries, size_limit) = self.inner.occupancy();
+        HeaderMapStats {
+            memory_entries,
+            backend_entries,
+            size_limit,
+            memory_bytes: memory_entries * ITEM_BYTES_SIZE,
+        }
     }
 
     pub fn contains_key(&self, hash: &Byte32) -> bool {
@@ -94,6 +315,42 @@ impl HeaderMap {
         self.inner.get(hash)
     }
 
+    /// Same as `get`, but a header missing from memory is read from the backend on the
+    /// blocking pool, so the calling async task never waits for disk I/O.
+    ///
+    /// A memory hit is returned right away without the hop.
+    pub async fn get_async(&self, hash: &Byte32) -> Option<HeaderIndexView> {
+        if let Some(view) = self.inner.get_memory(hash) {
+            return Some(view);
+        }
+
+        let inner = Arc::clone(&self.inner);
+        let hash = hash.clone();
+        self.async_handle
+            .spawn_blocking(move || {
+                let _trace_timer: Option<HistogramTimer> = ckb_metrics::handle().map(|metric| {
+                    metric
+                        .ckb_header_map_ops_duration
+                        .with_label_values(&["get_async_backend"])
+                        .start_timer()
+                });
+                inner.get_backend(&hash)
+            })
+            .await
+            .expect("header map backend read panicked")
+    }
+
+    /// Get the headers of `hashes` together, the results are in the same order as `hashes`
+    pub fn get_batch(&self, hashes: &[Byte32]) -> Vec<Option<HeaderIndexView>> {
+        let _trace_timer: Option<HistogramTimer> = ckb_metrics::handle().map(|metric| {
+            metric
+                .ckb_header_map_ops_duration
+                .with_label_values(&["get_batch"])
+                .start_timer()
+        });
+        self.inner.get_batch(hashes)
+    }
+
     pub fn insert(&self, view: HeaderIndexView) -> Option<()> {
         let _trace_timer: Option<HistogramTimer> = ckb_metrics::handle().map(|metric| {
             metric
@@ -115,4 +372,99 @@ impl HeaderMap {
 
         self.inner.remove(hash)
     }
+
+    /// Remember `hash` as the best known header, a persistent map saves it along with the
+    /// headers, see `saved_best_known`.
+    pub fn set_best_known(&self, hash: &Byte32) {
+        self.inner.set_best_known(hash)
+    }
+
+    /// The best known header saved by the last run of a persistent map, `None` if nothing was
+    /// saved or the header is no longer in the map.
+    pub fn saved_best_known(&self) -> Option<HeaderIndexView> {
+        let hash = self.inner.backend.best()?;
+        self.inner.get(&hash)
+    }
+
+    /// Walk down from `best_known` via `parent_hash` and report how far the headers are
+    /// contiguous, returns `None` if `best_known` itself is missing.
+    ///
+    /// The first missing ancestor is either a stored block or a hole which stalls the body
+    /// download. The walk neither refreshes the memory map nor moves headers out of the
+    /// backend, but it reads every header in the run, so it's slow on a long run.
+    #[cfg(feature = "stats")]
+    pub fn coverage_gap(&self, best_known: &Byte32) -> Option<HeaderMapCoverage> {
+        let mut lowest = self.inner.peek(best_known)?;
+        while lowest.number() > 0 {
+            let parent_hash = lowest.parent_hash();
+            match self.inner.peek(&parent_hash) {
+                Some(parent) => lowest = parent,
+                None => {
+                    return Some(HeaderMapCoverage {
+                        lowest_contiguous: lowest.number(),
+                        first_missing: Some(parent_hash),
+                    })
+                }
+            }
+        }
+        Some(HeaderMapCoverage {
+            lowest_contiguous: 0,
+            first_missing: None,
+        })
+    }
+
+    /// Check the `skip_hash` of every header in the contiguous run below `tip`, returns the
+    /// lowest header whose skip pointer is not the ancestor at `get_skip_height(number)`.
+    ///
+    /// A header whose skip height is below the run can't be checked and is ignored. Like
+    /// `coverage_gap`, the walk doesn't touch the LRU order but reads every header in the run.
+    #[cfg(feature = "stats")]
+    pub fn find_bad_skip_hash(&self, tip: &Byte32) -> Option<BadSkipHash> {
+        let mut lowest = self.inner.peek(tip)?;
+        let mut run = Vec::new();
+        while lowest.number() > 0 {
+            match self.inner.peek(&lowest.parent_hash()) {
+                Some(parent) => run.push(std::mem::replace(&mut lowest, parent)),
+                None => break,
+            }
+        }
+        run.push(lowest);
+        run.reverse();
+
+        let lowest_number = run[0].number();
+        run.iter()
+            .filter(|view| view.number() > 0)
+            .find_map(|view| {
+                let skip_height = get_skip_height(view.number());
+                let ancestor = run.get(skip_height.checked_sub(lowest_number)? as usize)?;
+                (view.skip_hash() != Some(&ancestor.hash())).then(|| BadSkipHash {
+                    number: view.number(),
+                    hash: view.hash(),
+                    expected: ancestor.hash(),
+                    actual: view.skip_hash().cloned(),
+                })
+            })
+    }
+}
+
+// report the occupancy along with the trim task, so no extra lock is taken
+fn update_metrics(map: &HeaderMapKernel<SelectedBackend>) {
+    if let Some(metrics) = ckb_metrics::handle() {
+        let (memory_entries, backend_entries, _) = map.occupancy();
+        metrics
+            .ckb_header_map_memory_bytes
+            .set((memory_entries * ITEM_BYTES_SIZE) as i64);
+        metrics
+            .ckb_header_map_backend_count
+            .set(backend_entries as i64);
+        metrics
+            .ckb_header_map_backend_disk_bytes
+            .set(map.backend.disk_bytes() as i64);
+    }
+}
+
+impl Drop for HeaderMap {
+    fn drop(&mut self) {
+        self.inner.flush();
+    }
 }
@@ -0,0 +1,393 @@
+use super::{hash, header_view};
+use crate::types::header_map::{
+    HeaderMapKernel, KeyValueBackend, RocksdbBackend, DEFAULT_TRIM_INTERVAL,
+};
+use crate::{HeaderIndexView, HeaderMap};
+use ckb_app_config::HeaderMapBackend;
+use ckb_async_runtime::new_background_runtime;
+use ckb_db::RocksDB;
+use ckb_types::packed::Byte32;
+use std::mem::size_of;
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Run the same operations against the header map, most headers are spilled into the backend,
+// and record everything observable.
+fn round_trip(backend: HeaderMapBackend) -> Vec<String> {
+    let memory_limit = size_of::<HeaderIndexView>() * 16;
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let header_map = HeaderMap::with_backend(
+        Some(tmp_dir.path()),
+        memory_limit,
+        backend,
+        3,
+        DEFAULT_TRIM_INTERVAL,
+        &new_background_runtime(),
+        Arc::new(AtomicBool::new(false)),
+    )
+    .unwrap();
+
+    let mut observed = Vec::new();
+    for number in 0..100 {
+        observed.push(format!("{:?}", header_map.insert(header_view(number))));
+    }
+    header_map.evict_to(memory_limit);
+    // insert an existing header again
+    observed.push(format!("{:?}", header_map.insert(header_view(10))));
+
+    for number in (0..100).step_by(3) {
+        header_map.remove(&hash(number));
+    }
+    for number in 0..110 {
+        observed.push(format!(
+            "{} {:?}",
+            header_map.contains_key(&hash(number)),
+            header_map.get(&hash(number))
+        ));
+    }
+
+    // the backend is empty again after everything is removed
+    for number in 0..100 {
+        header_map.remove(&hash(number));
+    }
+    for number in 0..100 {
+        observed.push(format!("{}", header_map.contains_key(&hash(number))));
+    }
+    observed
+}
+
+#[test]
+fn test_header_map_backends_round_trip() {
+    let sled = round_trip(HeaderMapBackend::Sled);
+    let rocksdb = round_trip(HeaderMapBackend::Rocksdb);
+    assert_eq!(sled, rocksdb);
+    assert!(sled
+        .iter()
+        .rev()
+        .take(100)
+        .all(|contains| contains == "false"));
+}
+
+#[test]
+fn test_header_map_get_batch() {
+    for backend in [HeaderMapBackend::Sled, HeaderMapBackend::Rocksdb] {
+        let memory_limit = size_of::<HeaderIndexView>() * 16;
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let header_map = HeaderMap::with_backend(
+            Some(tmp_dir.path()),
+            memory_limit,
+            backend,
+            3,
+            DEFAULT_TRIM_INTERVAL,
+            &new_background_runtime(),
+            Arc::new(AtomicBool::new(false)),
+        )
+        .unwrap();
+        for number in 0..100 {
+            header_map.insert(header_view(number));
+        }
+        header_map.evict_to(memory_limit);
+        let backend_entries = header_map.stats().backend_entries;
+
+        // 99 is in memory, 0 and 50 are in the backend, 200 is unknown
+        let numbers = [99, 0, 200, 50];
+        let hashes: Vec<Byte32> = numbers.iter().map(|number| hash(*number)).collect();
+        let expected: Vec<Option<HeaderIndexView>> = numbers
+            .iter()
+            .map(|number| (*number < 100).then(|| header_view(*number)))
+            .collect();
+        assert_eq!(header_map.get_batch(&hashes), expected);
+
+        // the backend hits are moved into memory, same as `get`
+        assert_eq!(header_map.stats().backend_entries, backend_entries - 2);
+        assert_eq!(header_map.get_batch(&hashes), expected);
+        assert_eq!(header_map.stats().backend_entries, backend_entries - 2);
+
+        // the 16 headers left in memory and the 2 moved back are evicted
+        assert_eq!(header_map.evict_to(0), 18);
+        assert_eq!(header_map.stats().backend_entries, 100);
+    }
+}
+
+#[test]
+fn test_header_map_persistent_reopen() {
+    for backend in [HeaderMapBackend::Sled, HeaderMapBackend::Rocksdb] {
+        let memory_limit = size_of::<HeaderIndexView>() * 16;
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let path = tmp_dir.path().join("header_map");
+        let open = || {
+            HeaderMap::open_persistent(
+                &path,
+                memory_limit,
+                backend,
+                3,
+                DEFAULT_TRIM_INTERVAL,
+                &new_background_runtime(),
+                Arc::new(AtomicBool::new(false)),
+            )
+            .unwrap()
+        };
+
+        let header_map = open();
+        for number in 0..100 {
+            header_map.insert(header_view(number));
+        }
+        header_map.evict_to(memory_limit);
+        header_map.remove(&hash(50));
+        // the headers left in memory are flushed on drop
+        drop(header_map);
+
+        let header_map = open();
+        assert_eq!(header_map.stats().memory_entries, 0);
+        assert_eq!(header_map.stats().backend_entries, 99);
+        for number in 0..100 {
+            let expected = (number != 50).then(|| header_view(number));
+            assert_eq!(header_map.get(&hash(number)), expected);
+        }
+    }
+}
+
+#[test]
+fn test_header_map_persistent_flush_on_trim() {
+    for backend in [HeaderMapBackend::Sled, HeaderMapBackend::Rocksdb] {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let header_map = HeaderMap::open_persistent(
+            tmp_dir.path().join("header_map"),
+            size_of::<HeaderIndexView>() * 16,
+            backend,
+            3,
+            Duration::from_millis(10),
+            &new_background_runtime(),
+            Arc::new(AtomicBool::new(false)),
+        )
+        .unwrap();
+        for number in 0..10 {
+            header_map.insert(header_view(number));
+        }
+
+        // nothing is over the limit, the trim task copies the headers without evicting them
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while header_map.stats().backend_entries < 10 {
+            assert!(Instant::now() < deadline, "the headers are never flushed");
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(header_map.stats().memory_entries, 10);
+    }
+}
+
+#[test]
+fn test_header_map_persistent_best_known() {
+    let memory_limit = size_of::<HeaderIndexView>() * 16;
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let path = tmp_dir.path().join("header_map");
+
+    // the kernel isn't flushed on drop, only what the explicit flush wrote survives, as if the
+    // node crashed right after a trim
+    let kernel = HeaderMapKernel::new(
+        RocksdbBackend::open(&path, 3).unwrap(),
+        16,
+        true,
+        Arc::new(AtomicBool::new(false)),
+    );
+    for number in 0..10 {
+        kernel.insert(header_view(number));
+    }
+    kernel.set_best_known(&hash(9));
+    kernel.flush();
+    kernel.insert(header_view(10));
+    kernel.set_best_known(&hash(10));
+    drop(kernel);
+
+    let header_map = HeaderMap::open_persistent(
+        &path,
+        memory_limit,
+        HeaderMapBackend::Rocksdb,
+        3,
+        DEFAULT_TRIM_INTERVAL,
+        &new_background_runtime(),
+        Arc::new(AtomicBool::new(false)),
+    )
+    .unwrap();
+    assert_eq!(header_map.saved_best_known(), Some(header_view(9)));
+    assert_eq!(header_map.stats().backend_entries, 9);
+    assert_eq!(header_map.get(&hash(10)), None);
+
+    // the saved best known header is ignored once it's gone
+    header_map.remove(&hash(9));
+    assert_eq!(header_map.saved_best_known(), None);
+}
+
+// Overwrite the values of the spilled headers in the backend at `path` with `values`
+fn corrupt(backend: HeaderMapBackend, path: &Path, values: &[(Byte32, Vec<u8>)]) {
+    match backend {
+        HeaderMapBackend::Sled => {
+            let db = sled::open(path).unwrap();
+            for (key, value) in values {
+                db.insert(key.as_slice(), value.as_slice()).unwrap();
+            }
+            db.flush().unwrap();
+        }
+        HeaderMapBackend::Rocksdb => {
+            let db = RocksDB::open_in(path, 2);
+            let mut batch = db.new_write_batch();
+            for (key, value) in values {
+                batch.put("0", key.as_slice(), value).unwrap();
+            }
+            db.write(&batch).unwrap();
+        }
+    }
+}
+
+#[test]
+fn test_header_map_corrupted_backend_value() {
+    for backend in [HeaderMapBackend::Sled, HeaderMapBackend::Rocksdb] {
+        let memory_limit = size_of::<HeaderIndexView>() * 16;
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let path = tmp_dir.path().join("header_map");
+        let open = || {
+            HeaderMap::open_persistent(
+                &path,
+                memory_limit,
+                backend,
+                3,
+                DEFAULT_TRIM_INTERVAL,
+                &new_background_runtime(),
+                Arc::new(AtomicBool::new(false)),
+            )
+            .unwrap()
+        };
+
+        let header_map = open();
+        for number in 0..100 {
+            header_map.insert(header_view(number));
+        }
+        header_map.evict_to(0);
+        drop(header_map);
+
+        let truncated = header_view(10).to_vec()[..20].to_vec();
+        let mut oversized = header_view(20).to_vec();
+        oversized.extend_from_slice(&[0; 64]);
+        corrupt(
+            backend,
+            &path,
+            &[(hash(10), truncated), (hash(20), oversized)],
+        );
+
+        // the corrupted headers are missing and removed, instead of panicking
+        let header_map = open();
+        assert_eq!(header_map.stats().backend_entries, 100);
+        assert_eq!(header_map.get(&hash(10)), None);
+        assert_eq!(
+            header_map.get_batch(&[hash(20), hash(30)]),
+            vec![None, Some(header_view(30))]
+        );
+        assert_eq!(header_map.stats().backend_entries, 97);
+        assert_eq!(header_map.get(&hash(10)), None);
+        assert_eq!(header_map.get(&hash(20)), None);
+    }
+}
+
+#[test]
+fn test_header_map_backend_creation_failure() {
+    let memory_limit = size_of::<HeaderIndexView>() * 16;
+    let tmp_dir = tempfile::tempdir().unwrap();
+    // a regular file can't hold a directory, whatever the permissions are
+    let file = tmp_dir.path().join("file");
+    std::fs::write(&file, b"").unwrap();
+
+    for backend in [HeaderMapBackend::Sled, HeaderMapBackend::Rocksdb] {
+        let err = HeaderMap::with_backend(
+            Some(&file),
+            memory_limit,
+            backend,
+            3,
+            DEFAULT_TRIM_INTERVAL,
+            &new_background_runtime(),
+            Arc::new(AtomicBool::new(false)),
+        )
+        .err()
+        .expect("a tempdir can't be created in a file");
+        assert!(err.to_string().contains(&file.display().to_string()));
+
+        assert!(HeaderMap::open_persistent(
+            file.join("header_map"),
+            memory_limit,
+            backend,
+            3,
+            DEFAULT_TRIM_INTERVAL,
+            &new_background_runtime(),
+            Arc::new(AtomicBool::new(false)),
+        )
+        .is_err());
+    }
+
+    // the memory only fallback keeps every header
+    let header_map = HeaderMap::memory_only(
+        memory_limit,
+        DEFAULT_TRIM_INTERVAL,
+        &new_background_runtime(),
+        Arc::new(AtomicBool::new(false)),
+    );
+    for number in 0..100 {
+        header_map.insert(header_view(number));
+    }
+    header_map.evict_to(memory_limit);
+    assert_eq!(header_map.stats().backend_entries, 84);
+    for number in 0..100 {
+        assert_eq!(header_map.get(&hash(number)), Some(header_view(number)));
+    }
+}
+
+#[test]
+fn test_rocksdb_backend_concurrent_count() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let path = tmp_dir.path().join("header_map");
+    let backend = Arc::new(RocksdbBackend::open(&path, 3).unwrap());
+    let views: Vec<_> = (0..100).map(header_view).collect();
+
+    // every thread inserts and then removes the same headers, each header counts once
+    let workers: Vec<_> = (0..4)
+        .map(|_| {
+            let backend = Arc::clone(&backend);
+            let views = views.clone();
+            thread::spawn(move || {
+                backend.insert_batch(&views[..50]);
+                for view in &views[50..] {
+                    backend.insert(view);
+                }
+                let hashes: Vec<_> = views[..50].iter().map(|view| view.hash()).collect();
+                backend.remove_batch(&hashes);
+            })
+        })
+        .collect();
+    for worker in workers {
+        worker.join().expect("worker should not panic");
+    }
+    assert_eq!(backend.len(), 50);
+
+    let workers: Vec<_> = (0..4)
+        .map(|_| {
+            let backend = Arc::clone(&backend);
+            thread::spawn(move || {
+                for number in 50..75 {
+                    backend.remove_no_return(&hash(number));
+                }
+            })
+        })
+        .collect();
+    for worker in workers {
+        worker.join().expect("worker should not panic");
+    }
+    assert_eq!(backend.len(), 25);
+    drop(backend);
+
+    // the count is persisted instead of counted on open
+    let backend = RocksdbBackend::open(&path, 3).unwrap();
+    assert_eq!(backend.len(), 25);
+    let hashes: Vec<_> = (0..100).map(hash).collect();
+    assert_eq!(backend.remove_batch(&hashes), 25);
+    assert!(backend.is_empty());
+}
@@ -0,0 +1,81 @@
+use crate::{HeaderIndexView, HeaderMap};
+use ckb_async_runtime::new_background_runtime;
+use ckb_types::{core::EpochNumberWithFraction, packed::Byte32, U256};
+use std::mem::size_of;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Barrier};
+use std::thread;
+
+const THREADS: u64 = 8;
+const HEADERS_PER_THREAD: u64 = 2_000;
+
+fn header_view(worker: u64, index: u64) -> HeaderIndexView {
+    let mut hash = [0u8; 32];
+    hash[..8].copy_from_slice(&worker.to_le_bytes());
+    hash[8..16].copy_from_slice(&index.to_le_bytes());
+    let number = worker * HEADERS_PER_THREAD + index;
+    HeaderIndexView::new(
+        Byte32::new(hash),
+        number,
+        EpochNumberWithFraction::new(0, index, HEADERS_PER_THREAD),
+        number,
+        Byte32::zero(),
+        U256::from(number),
+    )
+}
+
+#[test]
+fn test_header_map_concurrent_insert_and_evict() {
+    // Only 64 headers can be kept in memory, most of them are flushed into the backend
+    let memory_limit = size_of::<HeaderIndexView>() * 64;
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let header_map = Arc::new(HeaderMap::new(
+        Some(tmp_dir.path()),
+        memory_limit,
+        &new_background_runtime(),
+        Arc::new(AtomicBool::new(false)),
+    ));
+
+    let inserting = Arc::new(AtomicBool::new(true));
+    // Evict concurrently with the background limit_memory timer
+    let evictor = {
+        let header_map = Arc::clone(&header_map);
+        let inserting = Arc::clone(&inserting);
+        thread::spawn(move || {
+            while inserting.load(Ordering::Acquire) {
+                header_map.evict_to(memory_limit);
+                thread::yield_now();
+            }
+        })
+    };
+
+    let barrier = Arc::new(Barrier::new(THREADS as usize));
+    let inserters: Vec<_> = (0..THREADS)
+        .map(|worker| {
+            let header_map = Arc::clone(&header_map);
+            let barrier = Arc::clone(&barrier);
+            thread::spawn(move || {
+                barrier.wait();
+                for index in 0..HEADERS_PER_THREAD {
+                    let view = header_view(worker, index);
+                    header_map.insert(view.clone());
+                    // read your writes, wherever the header is now
+                    assert_eq!(header_map.get(&view.hash()), Some(view));
+                }
+            })
+        })
+        .collect();
+    for inserter in inserters {
+        inserter.join().expect("inserter should not panic");
+    }
+    inserting.store(false, Ordering::Release);
+    evictor.join().expect("evictor should not panic");
+
+    for worker in 0..THREADS {
+        for index in 0..HEADERS_PER_THREAD {
+            let view = header_view(worker, index);
+            assert!(header_map.contains_key(&view.hash()));
+            assert_eq!(header_map.get(&view.hash()), Some(view));
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use super::{hash, header_view};
+use crate::{HeaderIndexView, HeaderMap, HeaderMapCoverage};
+use ckb_async_runtime::new_background_runtime;
+use std::mem::size_of;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+#[test]
+fn test_header_map_coverage_gap() {
+    let memory_limit = size_of::<HeaderIndexView>() * 16;
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let header_map = HeaderMap::new(
+        Some(tmp_dir.path()),
+        memory_limit,
+        &new_background_runtime(),
+        Arc::new(AtomicBool::new(false)),
+    );
+
+    // headers 0..=99 except 40, most of them are flushed into the backend
+    for number in (0..100).filter(|number| *number != 40) {
+        header_map.insert(header_view(number));
+    }
+    header_map.evict_to(memory_limit);
+
+    assert_eq!(
+        header_map.coverage_gap(&hash(99)),
+        Some(HeaderMapCoverage {
+            lowest_contiguous: 41,
+            first_missing: Some(hash(40)),
+        })
+    );
+    assert_eq!(
+        header_map.coverage_gap(&hash(39)),
+        Some(HeaderMapCoverage {
+            lowest_contiguous: 0,
+            first_missing: None,
+        })
+    );
+    assert_eq!(header_map.coverage_gap(&hash(40)), None);
+
+    // the walk doesn't move any header out of the backend
+    for number in (0..100).filter(|number| *number != 40) {
+        assert!(header_map.contains_key(&hash(number)));
+    }
+}
@@ -0,0 +1,51 @@
+use super::{hash, header_view};
+use crate::{HeaderIndexView, HeaderMap};
+use ckb_async_runtime::new_background_runtime;
+use std::mem::size_of;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+#[test]
+fn test_header_map_get_async() {
+    let metrics = ckb_metrics::testing::enable();
+    let backend_reads = || {
+        metrics
+            .ckb_header_map_ops_duration
+            .with_label_values(&["get_async_backend"])
+            .get_sample_count()
+    };
+    let reads = backend_reads();
+
+    let memory_limit = size_of::<HeaderIndexView>() * 16;
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let handle = new_background_runtime();
+    let header_map = HeaderMap::new(
+        Some(tmp_dir.path()),
+        memory_limit,
+        &handle,
+        Arc::new(AtomicBool::new(false)),
+    );
+    for number in 0..100 {
+        header_map.insert(header_view(number));
+    }
+    header_map.evict_to(memory_limit);
+    assert_eq!(header_map.stats().backend_entries, 84);
+
+    // a memory hit doesn't hop onto the blocking pool
+    let view = handle.block_on(header_map.get_async(&hash(99)));
+    assert_eq!(view.map(|view| view.number()), Some(99));
+    assert_eq!(backend_reads() - reads, 0);
+
+    // a miss reads the backend on the blocking pool, and moves the header into memory
+    let view = handle.block_on(header_map.get_async(&hash(0)));
+    assert_eq!(view.map(|view| view.number()), Some(0));
+    assert_eq!(backend_reads() - reads, 1);
+    assert_eq!(header_map.stats().backend_entries, 83);
+
+    let view = handle.block_on(header_map.get_async(&hash(0)));
+    assert_eq!(view.map(|view| view.number()), Some(0));
+    assert_eq!(backend_reads() - reads, 1);
+
+    assert!(handle.block_on(header_map.get_async(&hash(100))).is_none());
+    assert_eq!(backend_reads() - reads, 2);
+}
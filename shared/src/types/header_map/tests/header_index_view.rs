@@ -0,0 +1,67 @@
+use super::{hash, header_view};
+use crate::{HeaderIndexView, HeaderMapDecodeError};
+use ckb_types::prelude::*;
+
+fn round_trip(view: &HeaderIndexView) -> HeaderIndexView {
+    HeaderIndexView::from_slice(view.hash().as_slice(), &view.to_vec()).unwrap()
+}
+
+#[test]
+fn test_header_index_view_round_trip() {
+    let view = header_view(7);
+    assert_eq!(view.skip_hash(), None);
+    assert_eq!(round_trip(&view), view);
+
+    let mut view = header_view(7);
+    view.build_skip(0, |_, _| Some(header_view(4)), |_, _| None);
+    assert_eq!(view.skip_hash(), Some(&hash(4)));
+    assert_eq!(round_trip(&view), view);
+}
+
+#[test]
+fn test_header_index_view_malformed_slice() {
+    let mut view = header_view(7);
+    view.build_skip(0, |_, _| Some(header_view(4)), |_, _| None);
+    let key = view.hash();
+    let bytes = view.to_vec();
+
+    // every truncation is rejected instead of panicking
+    for len in 0..bytes.len() {
+        assert!(HeaderIndexView::from_slice(key.as_slice(), &bytes[..len]).is_err());
+    }
+    assert_eq!(
+        HeaderIndexView::from_slice(key.as_slice(), &[]),
+        Err(HeaderMapDecodeError::Empty)
+    );
+    assert_eq!(
+        HeaderIndexView::from_slice(key.as_slice(), &bytes[..20]),
+        Err(HeaderMapDecodeError::Truncated(20))
+    );
+    assert_eq!(
+        HeaderIndexView::from_slice(&key.as_slice()[..31], &bytes),
+        Err(HeaderMapDecodeError::InvalidHash(31))
+    );
+
+    // an unknown format version
+    let mut unknown_version = bytes.clone();
+    unknown_version[0] += 1;
+    assert!(HeaderIndexView::from_slice(key.as_slice(), &unknown_version).is_err());
+
+    // the presence flag disagrees with the length
+    let mut bad_flag = header_view(7).to_vec();
+    *bad_flag.last_mut().unwrap() = 1;
+    assert!(HeaderIndexView::from_slice(key.as_slice(), &bad_flag).is_err());
+
+    // trailing bytes are not silently ignored
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert!(HeaderIndexView::from_slice(key.as_slice(), &trailing).is_err());
+
+    // an oversized slice
+    let mut oversized = bytes;
+    oversized.extend_from_slice(&[0; 64]);
+    assert_eq!(
+        HeaderIndexView::from_slice(key.as_slice(), &oversized),
+        Err(HeaderMapDecodeError::InvalidSkipHash(97))
+    );
+}
@@ -0,0 +1,81 @@
+use super::header_view;
+use crate::{HeaderIndexView, HeaderMap};
+use ckb_async_runtime::new_background_runtime;
+use ckb_logger::internal::{self as log, Log, Metadata, Record};
+use ckb_logger::Level;
+use std::mem::size_of;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+struct CaptureLogger {
+    warnings: Mutex<Vec<String>>,
+}
+
+impl Log for CaptureLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Warn
+    }
+
+    fn log(&self, record: &Record) {
+        if record.level() == Level::Warn {
+            self.warnings
+                .lock()
+                .unwrap()
+                .push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: CaptureLogger = CaptureLogger {
+    warnings: Mutex::new(Vec::new()),
+};
+
+#[test]
+fn test_header_map_low_memory_limit() {
+    // the only test of this crate installing a logger
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(log::LevelFilter::Warn);
+
+    // Far below the warning threshold, only 8 headers can be kept in memory
+    let memory_limit = size_of::<HeaderIndexView>() * 8;
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let header_map = HeaderMap::new(
+        Some(tmp_dir.path()),
+        memory_limit,
+        &new_background_runtime(),
+        Arc::new(AtomicBool::new(false)),
+    );
+
+    let expected = format!(
+        "The low memory limit setting {} will result in inefficient synchronization",
+        memory_limit
+    );
+    assert!(
+        LOGGER
+            .warnings
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|warning| warning == &expected),
+        "expect warning \"{expected}\""
+    );
+
+    let views: Vec<_> = (0..64).map(header_view).collect();
+    for view in views.iter() {
+        header_map.insert(view.clone());
+    }
+
+    // Spill everything beyond the limit into the backend, the map still serves all headers
+    header_map.evict_to(memory_limit);
+    for view in views.iter() {
+        assert!(header_map.contains_key(&view.hash()));
+        assert_eq!(header_map.get(&view.hash()).as_ref(), Some(view));
+    }
+
+    let removed = views.first().unwrap().hash();
+    header_map.remove(&removed);
+    assert!(!header_map.contains_key(&removed));
+    assert!(header_map.get(&removed).is_none());
+}
@@ -0,0 +1,53 @@
+use super::header_view;
+use crate::{HeaderIndexView, HeaderMap};
+use ckb_app_config::HeaderMapBackend;
+use ckb_async_runtime::new_background_runtime;
+use ckb_metrics::testing;
+use std::mem::size_of;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
+
+const SIZE_LIMIT: usize = 16;
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[test]
+fn test_header_map_metrics() {
+    let metrics = testing::enable();
+
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let header_map = HeaderMap::with_backend(
+        Some(tmp_dir.path()),
+        size_of::<HeaderIndexView>() * SIZE_LIMIT,
+        HeaderMapBackend::Sled,
+        3,
+        Duration::from_millis(10),
+        &new_background_runtime(),
+        Arc::new(AtomicBool::new(false)),
+    )
+    .unwrap();
+
+    // the gauges are reported by the trim task
+    for number in 0..100 {
+        header_map.insert(header_view(number));
+    }
+    assert!(testing::wait_for(TIMEOUT, |metrics| {
+        metrics.ckb_header_map_backend_count.get() == 100 - SIZE_LIMIT as i64
+    }));
+    assert_eq!(
+        metrics.ckb_header_map_memory_bytes.get(),
+        (size_of::<HeaderIndexView>() * SIZE_LIMIT) as i64
+    );
+    assert!(metrics.ckb_header_map_backend_disk_bytes.get() > 0);
+
+    for number in 100..200 {
+        header_map.insert(header_view(number));
+    }
+    assert!(testing::wait_for(TIMEOUT, |metrics| {
+        metrics.ckb_header_map_backend_count.get() == 200 - SIZE_LIMIT as i64
+    }));
+    assert_eq!(
+        metrics.ckb_header_map_memory_bytes.get(),
+        header_map.stats().memory_bytes as i64
+    );
+}
@@ -0,0 +1,38 @@
+use crate::HeaderIndexView;
+use ckb_types::{core::EpochNumberWithFraction, packed::Byte32, U256};
+
+mod backends;
+mod concurrency;
+#[cfg(feature = "stats")]
+mod coverage_gap;
+mod get_async;
+mod header_index_view;
+mod low_memory_limit;
+mod metrics;
+#[cfg(feature = "stats")]
+mod skip_hash;
+mod stats;
+mod trim;
+
+fn hash(number: u64) -> Byte32 {
+    let mut hash = [0u8; 32];
+    hash[..8].copy_from_slice(&(number + 1).to_le_bytes());
+    Byte32::new(hash)
+}
+
+// the header `number` of a chain starting from header 0
+fn header_view(number: u64) -> HeaderIndexView {
+    let parent_hash = if number == 0 {
+        Byte32::zero()
+    } else {
+        hash(number - 1)
+    };
+    HeaderIndexView::new(
+        hash(number),
+        number,
+        EpochNumberWithFraction::new(0, number, 1000),
+        number,
+        parent_hash,
+        U256::from(number),
+    )
+}
@@ -0,0 +1,46 @@
+use super::{hash, header_view};
+use crate::{BadSkipHash, HeaderIndexView, HeaderMap};
+use ckb_async_runtime::new_background_runtime;
+use std::mem::size_of;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+#[test]
+fn test_header_map_find_bad_skip_hash() {
+    let memory_limit = size_of::<HeaderIndexView>() * 16;
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let header_map = HeaderMap::new(
+        Some(tmp_dir.path()),
+        memory_limit,
+        &new_background_runtime(),
+        Arc::new(AtomicBool::new(false)),
+    );
+
+    for number in 0..100 {
+        let mut view = header_view(number);
+        if number == 70 {
+            // a broken lookup makes header 70 skip to header 1 instead of header 64
+            view.build_skip(0, |_, _| Some(header_view(1)), |_, _| None);
+        } else {
+            view.build_skip(0, |hash, _| header_map.get(hash), |_, _| None);
+        }
+        header_map.insert(view);
+    }
+    header_map.evict_to(memory_limit);
+
+    assert_eq!(header_map.find_bad_skip_hash(&hash(69)), None);
+    assert_eq!(
+        header_map.find_bad_skip_hash(&hash(99)),
+        Some(BadSkipHash {
+            number: 70,
+            hash: hash(70),
+            expected: hash(64),
+            actual: Some(hash(1)),
+        })
+    );
+    assert_eq!(header_map.find_bad_skip_hash(&hash(100)), None);
+
+    // the pointers below a missing header are not checked
+    header_map.remove(&hash(60));
+    assert_eq!(header_map.find_bad_skip_hash(&hash(69)), None);
+}
@@ -0,0 +1,59 @@
+use super::{hash, header_view};
+use crate::{HeaderIndexView, HeaderMap, HeaderMapStats};
+use ckb_async_runtime::new_background_runtime;
+use std::mem::size_of;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+#[test]
+fn test_header_map_stats() {
+    let item_bytes = size_of::<HeaderIndexView>();
+    let memory_limit = item_bytes * 16;
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let header_map = HeaderMap::new(
+        Some(tmp_dir.path()),
+        memory_limit,
+        &new_background_runtime(),
+        Arc::new(AtomicBool::new(false)),
+    );
+    assert_eq!(
+        header_map.stats(),
+        HeaderMapStats {
+            memory_entries: 0,
+            backend_entries: 0,
+            size_limit: 16,
+            memory_bytes: 0,
+        }
+    );
+
+    for number in 0..100 {
+        header_map.insert(header_view(number));
+    }
+    let stats = header_map.stats();
+    assert_eq!(stats.memory_entries + stats.backend_entries, 100);
+
+    assert_eq!(header_map.evict_to(memory_limit), 84);
+    assert_eq!(
+        header_map.stats(),
+        HeaderMapStats {
+            memory_entries: 16,
+            backend_entries: 84,
+            size_limit: 16,
+            memory_bytes: item_bytes * 16,
+        }
+    );
+
+    assert_eq!(header_map.evict_to(memory_limit), 0);
+
+    // a header read from the backend is moved back into memory
+    header_map.get(&hash(0));
+    let stats = header_map.stats();
+    assert_eq!(stats.memory_entries, 17);
+    assert_eq!(stats.backend_entries, 83);
+
+    header_map.remove(&hash(0));
+    header_map.remove(&hash(1));
+    let stats = header_map.stats();
+    assert_eq!(stats.memory_entries, 16);
+    assert_eq!(stats.backend_entries, 82);
+}
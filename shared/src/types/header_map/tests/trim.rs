@@ -0,0 +1,64 @@
+use super::header_view;
+use crate::{HeaderIndexView, HeaderMap};
+use ckb_app_config::HeaderMapBackend;
+use ckb_async_runtime::new_background_runtime;
+use std::mem::size_of;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const SIZE_LIMIT: usize = 64;
+
+fn new_header_map(tmp_dir: &tempfile::TempDir, trim_interval: Duration) -> HeaderMap {
+    HeaderMap::with_backend(
+        Some(tmp_dir.path()),
+        size_of::<HeaderIndexView>() * SIZE_LIMIT,
+        HeaderMapBackend::default(),
+        3,
+        trim_interval,
+        &new_background_runtime(),
+        Arc::new(AtomicBool::new(false)),
+    )
+    .unwrap()
+}
+
+// wait for the background task to trim the memory map down to `SIZE_LIMIT`
+fn wait_for_trim(header_map: &HeaderMap) -> bool {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while Instant::now() < deadline {
+        if header_map.stats().memory_entries <= SIZE_LIMIT {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    false
+}
+
+#[test]
+fn test_header_map_short_trim_interval() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let header_map = new_header_map(&tmp_dir, Duration::from_millis(10));
+
+    for round in 0..20 {
+        for number in round * 50..(round + 1) * 50 {
+            header_map.insert(header_view(number));
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+    assert!(wait_for_trim(&header_map));
+    assert_eq!(header_map.stats().backend_entries, 1000 - SIZE_LIMIT);
+}
+
+#[test]
+fn test_header_map_out_of_band_trim() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    // the timer never fires again after the first tick
+    let header_map = new_header_map(&tmp_dir, Duration::from_secs(3600));
+    thread::sleep(Duration::from_millis(50));
+
+    for number in 0..(SIZE_LIMIT as u64) * 3 {
+        header_map.insert(header_view(number));
+    }
+    assert!(wait_for_trim(&header_map));
+}
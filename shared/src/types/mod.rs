@@ -3,9 +3,38 @@ use ckb_types::core::{BlockNumber, EpochNumberWithFraction};
 use ckb_types::packed::Byte32;
 use ckb_types::prelude::{Entity, FromSliceShouldBeOk, Reader};
 use ckb_types::{packed, BlockNumberAndHash, U256};
+use thiserror::Error;
 
 pub mod header_map;
 
+/// The format version of the serialized `HeaderIndexView`, bump it on any layout change
+pub(crate) const HEADER_INDEX_VIEW_FORMAT: u8 = 1;
+// number, epoch, timestamp, parent_hash and total_difficulty
+const FIXED_FIELDS_SIZE: usize = 8 + 8 + 8 + 32 + 32;
+
+/// The error of decoding a `HeaderIndexView` from the bytes stored in a header map backend.
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+pub enum HeaderMapDecodeError {
+    /// The key isn't a 32 bytes hash
+    #[error("invalid hash of {0} bytes")]
+    InvalidHash(usize),
+    /// The value is empty
+    #[error("empty slice")]
+    Empty,
+    /// The value is written by another layout
+    #[error("unknown format version {0}")]
+    UnknownVersion(u8),
+    /// The value is shorter than the fixed fields
+    #[error("truncated slice of {0} bytes")]
+    Truncated(usize),
+    /// The total difficulty is out of range
+    #[error("invalid total difficulty")]
+    InvalidDifficulty,
+    /// The optional `skip_hash` is malformed, including trailing bytes
+    #[error("invalid skip_hash of {0} bytes")]
+    InvalidSkipHash(usize),
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct HeaderIndexView {
     hash: Byte32,
@@ -65,22 +94,36 @@ impl HeaderIndexView {
         self.skip_hash.as_ref()
     }
 
-    // deserialize from bytes
-    fn from_slice_should_be_ok(hash: &[u8], slice: &[u8]) -> Self {
-        let hash = packed::Byte32Reader::from_slice_should_be_ok(hash).to_entity();
-        let number = BlockNumber::from_le_bytes(slice[0..8].try_into().expect("stored slice"));
+    /// Deserialize from the bytes written by `to_vec`, a slice of another format version, a
+    /// truncated or an oversized slice is an error
+    pub fn from_slice(hash: &[u8], slice: &[u8]) -> Result<Self, HeaderMapDecodeError> {
+        let hash = packed::Byte32Reader::from_slice(hash)
+            .map_err(|_| HeaderMapDecodeError::InvalidHash(hash.len()))?
+            .to_entity();
+        let (version, slice) = slice.split_first().ok_or(HeaderMapDecodeError::Empty)?;
+        if *version != HEADER_INDEX_VIEW_FORMAT {
+            return Err(HeaderMapDecodeError::UnknownVersion(*version));
+        }
+        if slice.len() < FIXED_FIELDS_SIZE + 1 {
+            return Err(HeaderMapDecodeError::Truncated(slice.len() + 1));
+        }
+        let (fixed, optional) = slice.split_at(FIXED_FIELDS_SIZE);
+        let number = BlockNumber::from_le_bytes(fixed[0..8].try_into().expect("checked length"));
         let epoch = EpochNumberWithFraction::from_full_value(u64::from_le_bytes(
-            slice[8..16].try_into().expect("stored slice"),
+            fixed[8..16].try_into().expect("checked length"),
         ));
-        let timestamp = u64::from_le_bytes(slice[16..24].try_into().expect("stored slice"));
-        let parent_hash = packed::Byte32Reader::from_slice_should_be_ok(&slice[24..56]).to_entity();
-        let total_difficulty = U256::from_little_endian(&slice[56..88]).expect("stored slice");
-        let skip_hash = if slice.len() == 120 {
-            Some(packed::Byte32Reader::from_slice_should_be_ok(&slice[88..120]).to_entity())
-        } else {
-            None
+        let timestamp = u64::from_le_bytes(fixed[16..24].try_into().expect("checked length"));
+        let parent_hash = packed::Byte32Reader::from_slice_should_be_ok(&fixed[24..56]).to_entity();
+        let total_difficulty = U256::from_little_endian(&fixed[56..88])
+            .map_err(|_| HeaderMapDecodeError::InvalidDifficulty)?;
+        let skip_hash = match optional {
+            [0] => None,
+            [1, skip_hash @ ..] if skip_hash.len() == 32 => {
+                Some(packed::Byte32Reader::from_slice_should_be_ok(skip_hash).to_entity())
+            }
+            _ => return Err(HeaderMapDecodeError::InvalidSkipHash(optional.len())),
         };
-        Self {
+        Ok(Self {
             hash,
             number,
             epoch,
@@ -88,19 +131,25 @@ impl HeaderIndexView {
             parent_hash,
             total_difficulty,
             skip_hash,
-        }
+        })
     }
 
-    // serialize all fields except `hash` to bytes
+    // serialize all fields except `hash` to bytes, led by the format version, the `skip_hash`
+    // follows a presence flag, so fields appended later are never mistaken for it
     fn to_vec(&self) -> Vec<u8> {
-        let mut v = Vec::new();
+        let mut v = Vec::with_capacity(1 + FIXED_FIELDS_SIZE + 1 + 32);
+        v.push(HEADER_INDEX_VIEW_FORMAT);
         v.extend_from_slice(self.number.to_le_bytes().as_slice());
         v.extend_from_slice(self.epoch.full_value().to_le_bytes().as_slice());
         v.extend_from_slice(self.timestamp.to_le_bytes().as_slice());
         v.extend_from_slice(self.parent_hash.as_slice());
         v.extend_from_slice(self.total_difficulty.to_le_bytes().as_slice());
-        if let Some(ref skip_hash) = self.skip_hash {
-            v.extend_from_slice(skip_hash.as_slice());
+        match self.skip_hash {
+            Some(ref skip_hash) => {
+                v.push(1);
+                v.extend_from_slice(skip_hash.as_slice());
+            }
+            None => v.push(0),
         }
         v
     }
@@ -237,7 +286,7 @@ impl HeaderIndex {
 }
 
 // Compute what height to jump back to with the skip pointer.
-fn get_skip_height(height: BlockNumber) -> BlockNumber {
+pub(crate) fn get_skip_height(height: BlockNumber) -> BlockNumber {
     // Turn the lowest '1' bit in the binary representation of a number into a '0'.
     fn invert_lowest_one(n: i64) -> i64 {
         n & (n - 1)
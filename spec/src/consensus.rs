@@ -88,6 +88,8 @@ pub(crate) const MAX_BLOCK_CYCLES: u64 = TWO_IN_TWO_OUT_CYCLES * TWO_IN_TWO_OUT_
 ///
 /// Default value from 1.5 * TWO_IN_TWO_OUT_COUNT
 pub const MAX_BLOCK_PROPOSALS_LIMIT: u64 = 1_500;
+/// The default maximum allowed size in bytes of the block extension
+pub const MAX_BLOCK_EXTENSION_BYTES: u64 = 96;
 const PROPOSER_REWARD_RATIO: Ratio = Ratio::new(4, 10);
 
 // Satoshi's pubkey hash in Bitcoin genesis.
@@ -291,6 +293,7 @@ impl ConsensusBuilder {
                 type_id_code_hash: TYPE_ID_CODE_HASH,
                 proposer_reward_ratio: PROPOSER_REWARD_RATIO,
                 max_block_proposals_limit: MAX_BLOCK_PROPOSALS_LIMIT,
+                max_block_extension_bytes: MAX_BLOCK_EXTENSION_BYTES,
                 satoshi_pubkey_hash: SATOSHI_PUBKEY_HASH,
                 satoshi_cell_occupied_ratio: SATOSHI_CELL_OCCUPIED_RATIO,
                 primary_epoch_reward_halving_interval:
@@ -483,6 +486,13 @@ impl ConsensusBuilder {
         self
     }
 
+    /// Sets max_block_extension_bytes for the new Consensus.
+    #[must_use]
+    pub fn max_block_extension_bytes(mut self, max_block_extension_bytes: u64) -> Self {
+        self.inner.max_block_extension_bytes = max_block_extension_bytes;
+        self
+    }
+
     /// Sets a hard fork switch for the new Consensus.
     pub fn hardfork_switch(mut self, hardfork_switch: HardForks) -> Self {
         self.inner.hardfork_switch = hardfork_switch;
@@ -568,6 +578,8 @@ pub struct Consensus {
     pub type_id_code_hash: H256,
     /// The Limit to the number of proposals per block
     pub max_block_proposals_limit: u64,
+    /// The maximum size in bytes of the block extension
+    pub max_block_extension_bytes: u64,
     /// The genesis epoch information
     pub genesis_epoch_ext: EpochExt,
     /// Satoshi's pubkey hash in Bitcoin genesis.
@@ -746,6 +758,11 @@ impl Consensus {
         self.max_block_proposals_limit
     }
 
+    /// The maximum size in bytes of the block extension
+    pub fn max_block_extension_bytes(&self) -> u64 {
+        self.max_block_extension_bytes
+    }
+
     /// The current block version
     pub fn block_version(&self) -> Version {
         self.block_version
@@ -90,7 +90,8 @@ pub mod default_params {
         CELLBASE_MATURITY, DEFAULT_EPOCH_DURATION_TARGET, DEFAULT_ORPHAN_RATE_TARGET,
         DEFAULT_PRIMARY_EPOCH_REWARD_HALVING_INTERVAL, DEFAULT_SECONDARY_EPOCH_REWARD,
         GENESIS_EPOCH_LENGTH, INITIAL_PRIMARY_EPOCH_REWARD, MAX_BLOCK_BYTES, MAX_BLOCK_CYCLES,
-        MAX_BLOCK_PROPOSALS_LIMIT, STARTING_BLOCK_LIMITING_DAO_WITHDRAWING_LOCK,
+        MAX_BLOCK_EXTENSION_BYTES, MAX_BLOCK_PROPOSALS_LIMIT,
+        STARTING_BLOCK_LIMITING_DAO_WITHDRAWING_LOCK,
     };
     use ckb_types::core::{Capacity, Cycle, EpochNumber};
 
@@ -157,6 +158,13 @@ pub mod default_params {
         MAX_BLOCK_PROPOSALS_LIMIT
     }
 
+    /// The default max_block_extension_bytes
+    ///
+    /// Apply to [`max_block_extension_bytes`](../consensus/struct.Consensus.html#structfield.max_block_extension_bytes)
+    pub fn max_block_extension_bytes() -> u64 {
+        MAX_BLOCK_EXTENSION_BYTES
+    }
+
     /// The default permanent_difficulty_in_dummy
     ///
     /// Apply to [`permanent_difficulty_in_dummy`](../consensus/struct.Consensus.html#structfield.permanent_difficulty_in_dummy)
@@ -233,6 +241,11 @@ pub struct Params {
     /// See [`max_block_proposals_limit`](consensus/struct.Consensus.html#structfield.max_block_proposals_limit)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_block_proposals_limit: Option<u64>,
+    /// The max_block_extension_bytes
+    ///
+    /// See [`max_block_extension_bytes`](consensus/struct.Consensus.html#structfield.max_block_extension_bytes)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_block_extension_bytes: Option<u64>,
     /// The orphan_rate_target
     ///
     /// See [`orphan_rate_target`](consensus/struct.Consensus.html#structfield.orphan_rate_target)
@@ -311,6 +324,12 @@ impl Params {
             .unwrap_or_else(default_params::max_block_proposals_limit)
     }
 
+    /// Return the `max_block_extension_bytes`, otherwise if None, returns the default value
+    pub fn max_block_extension_bytes(&self) -> u64 {
+        self.max_block_extension_bytes
+            .unwrap_or_else(default_params::max_block_extension_bytes)
+    }
+
     /// Return the `orphan_rate_target`, otherwise if None, returns the default value
     pub fn orphan_rate_target(&self) -> (u32, u32) {
         self.orphan_rate_target
@@ -582,6 +601,7 @@ impl ChainSpec {
             .epoch_duration_target(self.params.epoch_duration_target())
             .permanent_difficulty_in_dummy(self.params.permanent_difficulty_in_dummy())
             .max_block_proposals_limit(self.params.max_block_proposals_limit())
+            .max_block_extension_bytes(self.params.max_block_extension_bytes())
             .orphan_rate_target(self.params.orphan_rate_target())
             .starting_block_limiting_dao_withdrawing_lock(
                 self.params.starting_block_limiting_dao_withdrawing_lock(),
@@ -202,6 +202,18 @@ fn test_default_params() {
 
     assert_eq!(params, expected);
 
+    let test_params: &str = r#"
+            max_block_extension_bytes = 128
+        "#;
+
+    let params: Params = toml::from_str(test_params).unwrap();
+    let expected = Params {
+        max_block_extension_bytes: Some(128),
+        ..Default::default()
+    };
+
+    assert_eq!(params, expected);
+
     let test_params: &str = r#"
             orphan_rate_target = [1, 40]
         "#;
@@ -0,0 +1,54 @@
+//! On-demand consistency checks of the stored main chain.
+use crate::ChainStore;
+use ckb_types::{
+    core::{BlockNumber, EpochNumberWithFraction},
+    packed::Byte32,
+};
+
+/// A main chain block whose epoch number is less than its parent's.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpochRegression {
+    /// The number of the offending block
+    pub number: BlockNumber,
+    /// The hash of the offending block
+    pub hash: Byte32,
+    /// The epoch of the offending block
+    pub epoch: EpochNumberWithFraction,
+    /// The epoch of its parent
+    pub parent_epoch: EpochNumberWithFraction,
+}
+
+/// Walk the main chain headers in `[from, to]` and return the first block whose epoch number
+/// decreases, or `None` if the epoch numbers are monotonically non-decreasing with height.
+///
+/// The per-block `EpochVerifier` can't detect a corrupted epoch state across the chain, this
+/// sweep is a diagnostic. It stops at the first height missing from the store.
+pub fn find_epoch_regression<S: ChainStore>(
+    store: &S,
+    from: BlockNumber,
+    to: BlockNumber,
+) -> Option<EpochRegression> {
+    let mut parent_epoch: Option<EpochNumberWithFraction> = None;
+    for number in from..=to {
+        let header = match store
+            .get_block_hash(number)
+            .and_then(|hash| store.get_block_header(&hash))
+        {
+            Some(header) => header,
+            None => break,
+        };
+        let epoch = header.epoch();
+        if let Some(parent_epoch) = parent_epoch {
+            if epoch.number() < parent_epoch.number() {
+                return Some(EpochRegression {
+                    number,
+                    hash: header.hash(),
+                    epoch,
+                    parent_epoch,
+                });
+            }
+        }
+        parent_epoch = Some(epoch);
+    }
+    None
+}
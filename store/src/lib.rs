@@ -5,6 +5,7 @@
 
 mod cache;
 mod cell;
+mod consistency;
 pub mod data_loader_wrapper;
 mod db;
 mod snapshot;
@@ -17,6 +18,7 @@ mod tests;
 
 pub use cache::StoreCache;
 pub use cell::{attach_block_cell, detach_block_cell};
+pub use consistency::{find_epoch_regression, EpochRegression};
 pub use db::ChainDB;
 pub use snapshot::StoreSnapshot;
 pub use store::ChainStore;
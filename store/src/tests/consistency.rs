@@ -0,0 +1,45 @@
+use ckb_chain_spec::consensus::ConsensusBuilder;
+use ckb_db::RocksDB;
+use ckb_db_schema::COLUMNS;
+use ckb_types::{
+    core::{BlockBuilder, EpochNumberWithFraction},
+    prelude::*,
+};
+use tempfile::TempDir;
+
+use crate::{db::ChainDB, find_epoch_regression, store::ChainStore};
+
+#[test]
+fn epoch_regression() {
+    let tmp_dir = TempDir::new().unwrap();
+    let db = RocksDB::open_in(&tmp_dir, COLUMNS);
+    let store = ChainDB::new(db, Default::default());
+    let consensus = ConsensusBuilder::default().build();
+    store.init(&consensus).unwrap();
+
+    let mut parent = consensus.genesis_block().header();
+    let txn = store.begin_transaction();
+    for epoch_number in [0, 1, 1, 0, 2] {
+        let number = parent.number() + 1;
+        let block = BlockBuilder::default()
+            .parent_hash(parent.hash())
+            .number(number.pack())
+            .epoch(EpochNumberWithFraction::new(epoch_number, number, 1000).pack())
+            .build();
+        txn.insert_block(&block).unwrap();
+        txn.attach_block(&block).unwrap();
+        parent = block.header();
+    }
+    txn.commit().unwrap();
+
+    let regression = find_epoch_regression(&store, 0, 5).expect("epoch regression");
+    assert_eq!(regression.number, 4);
+    assert_eq!(regression.hash, store.get_block_hash(4).unwrap());
+    assert_eq!(regression.epoch.number(), 0);
+    assert_eq!(regression.parent_epoch.number(), 1);
+
+    assert_eq!(find_epoch_regression(&store, 0, 3), None);
+    assert_eq!(find_epoch_regression(&store, 4, 5), None);
+    // the sweep stops at the first missing height
+    assert_eq!(find_epoch_regression(&store, 0, 100), Some(regression));
+}
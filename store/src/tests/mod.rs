@@ -1 +1,2 @@
+mod consistency;
 mod db;
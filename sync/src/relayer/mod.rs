@@ -349,6 +349,7 @@ impl Relayer {
 
         let remote_block = RemoteBlock {
             block,
+            peer_id,
             verify_callback,
         };
 
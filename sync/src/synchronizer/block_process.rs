@@ -70,6 +70,7 @@ impl<'a> BlockProcess<'a> {
             };
             let remote_block = RemoteBlock {
                 block,
+                peer_id: self.peer,
                 verify_callback,
             };
             self.synchronizer
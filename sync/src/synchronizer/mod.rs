@@ -499,6 +499,9 @@ impl Synchronizer {
 
         self.peers()
             .sync_connected(peer, is_outbound, is_whitelist, is_2023edition);
+        self.shared
+            .shared()
+            .set_sync_peers(self.peers().state.len());
     }
 
     /// Regularly check and eject some nodes that do not respond in time
@@ -708,6 +711,11 @@ impl Synchronizer {
             return;
         }
 
+        if self.chain.is_verification_saturated() {
+            trace!("skip find_blocks_to_fetch, ckb_chain can't verify the received blocks in time");
+            return;
+        }
+
         if ckb_stop_handler::has_received_stop_signal() {
             info!("received stop signal, stop find_blocks_to_fetch");
             return;
@@ -947,6 +955,10 @@ impl CKBProtocolHandler for Synchronizer {
     ) {
         let sync_state = self.shared().state();
         sync_state.disconnected(peer_index);
+        self.chain.remove_peer_block_counts(peer_index);
+        self.shared
+            .shared()
+            .set_sync_peers(sync_state.peers().state.len());
         info!("SyncProtocol.disconnected peer={}", peer_index);
     }
 
@@ -8,6 +8,7 @@ use crate::tests::util::{build_chain, inherit_block};
 use crate::{Relayer, Status, SyncShared, Synchronizer};
 use ckb_chain::{start_chain_services, RemoteBlock, VerifyResult};
 use ckb_logger::info;
+use ckb_network::PeerIndex;
 use ckb_shared::block_status::BlockStatus;
 use ckb_shared::{Shared, SharedBuilder};
 use ckb_store::{self, ChainStore};
@@ -115,7 +116,7 @@ fn test_insert_parent_unknown_block() {
         &chain,
         RemoteBlock {
             block: Arc::clone(&valid_orphan),
-
+            peer_id: PeerIndex::new(0),
             verify_callback: Box::new(|_: VerifyResult| {}),
         },
     );
@@ -123,6 +124,7 @@ fn test_insert_parent_unknown_block() {
         &chain,
         RemoteBlock {
             block: Arc::clone(&invalid_orphan),
+            peer_id: PeerIndex::new(0),
             verify_callback: Box::new(|_: VerifyResult| {}),
         },
     );
@@ -14,7 +14,7 @@ use std::{
     },
 };
 
-use crate::types::{TtlFilter, FILTER_TTL};
+use crate::types::{locator_heights, TtlFilter, FILTER_TTL};
 
 const SKIPLIST_LENGTH: u64 = 10_000;
 
@@ -110,3 +110,20 @@ fn ttl_filter() {
     assert!(!filter.contains(&1));
     assert!(filter.contains(&2));
 }
+
+#[test]
+fn test_locator_heights() {
+    assert_eq!(locator_heights(0), vec![0]);
+    assert_eq!(locator_heights(3), vec![3, 2, 1, 0]);
+    assert_eq!(
+        locator_heights(199),
+        vec![199, 198, 197, 196, 195, 194, 193, 192, 191, 190, 188, 184, 176, 160, 128, 64, 0]
+    );
+
+    // low-height blocks are inserted after one day
+    let heights = locator_heights(100_000);
+    assert!(heights.len() <= 52 + 1);
+    assert_eq!(heights.last(), Some(&0));
+    assert!(heights.windows(2).all(|pair| pair[0] > pair[1]));
+    assert!(heights.iter().rev().nth(1).unwrap() <= &8192);
+}
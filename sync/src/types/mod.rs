@@ -9,8 +9,9 @@ use ckb_constant::sync::{
     BLOCK_DOWNLOAD_TIMEOUT, HEADERS_DOWNLOAD_HEADERS_PER_SECOND, HEADERS_DOWNLOAD_INSPECT_WINDOW,
     HEADERS_DOWNLOAD_TOLERABLE_BIAS_FOR_SINGLE_SAMPLE, INIT_BLOCKS_IN_TRANSIT_PER_PEER,
     MAX_BLOCKS_IN_TRANSIT_PER_PEER, MAX_HEADERS_LEN, MAX_OUTBOUND_PEERS_TO_PROTECT_FROM_DISCONNECT,
-    MAX_UNKNOWN_TX_HASHES_SIZE, MAX_UNKNOWN_TX_HASHES_SIZE_PER_PEER, POW_INTERVAL,
-    RETRY_ASK_TX_TIMEOUT_INCREASE, SUSPEND_SYNC_TIME,
+    MAX_OUTSTANDING_RELAY_TXS_PER_PEER, MAX_UNKNOWN_TX_HASHES_SIZE,
+    MAX_UNKNOWN_TX_HASHES_SIZE_PER_PEER, POW_INTERVAL, RETRY_ASK_TX_TIMEOUT_INCREASE,
+    SUSPEND_SYNC_TIME,
 };
 use ckb_logger::{debug, error, info, trace, warn};
 use ckb_network::{CKBProtocolContext, PeerIndex, SupportProtocols};
@@ -1006,7 +1007,20 @@ impl SyncShared {
                 snapshot.tip_header().to_owned(),
             )
         };
-        let shared_best_header = RwLock::new((header, total_difficulty).into());
+        // a persistent header map restores the best known header of the last run, unless the
+        // tip has caught up with it
+        let shared_best_header = match shared.header_map().saved_best_known() {
+            Some(saved) if saved.is_better_than(&total_difficulty) => {
+                info!(
+                    "restore the best known header {}-{} from the header map",
+                    saved.number(),
+                    saved.hash()
+                );
+                saved
+            }
+            _ => (header, total_difficulty).into(),
+        };
+        let shared_best_header = RwLock::new(shared_best_header);
         info!(
             "header_map.memory_limit {}",
             sync_config.header_map.memory_limit
@@ -1129,7 +1143,10 @@ impl SyncShared {
         self.state
             .peers()
             .may_set_best_known_header(peer, header_view.as_header_index());
-        self.state.may_set_shared_best_header(header_view);
+        let hash = header_view.hash();
+        if self.state.may_set_shared_best_header(header_view) {
+            self.shared.header_map().set_best_known(&hash);
+        }
     }
 
     pub(crate) fn get_header_index_view(
@@ -1385,16 +1402,18 @@ impl SyncState {
         self.shared_best_header.read()
     }
 
-    pub fn may_set_shared_best_header(&self, header: HeaderIndexView) {
+    /// Returns true if `header` becomes the shared best header
+    pub fn may_set_shared_best_header(&self, header: HeaderIndexView) -> bool {
         let mut shared_best_header = self.shared_best_header.write();
         if !header.is_better_than(shared_best_header.total_difficulty()) {
-            return;
+            return false;
         }
 
         if let Some(metrics) = ckb_metrics::handle() {
             metrics.ckb_shared_best_number.set(header.number() as i64);
         }
         *shared_best_header = header;
+        true
     }
 
     pub(crate) fn suspend_sync(&self, peer_state: &mut PeerState) {
@@ -1453,9 +1472,29 @@ impl SyncState {
             return result;
         }
 
+        // Count the requests which are still waiting for the response of each peer, so that
+        // a peer announcing a flood of hashes can't induce unbounded requests.
+        let mut outstanding: HashMap<PeerIndex, usize> = HashMap::new();
+        for (_tx_hash, priority) in unknown_tx_hashes.iter() {
+            if !priority.should_request(now) {
+                if let Some(peer_index) = priority.requesting_peer() {
+                    *outstanding.entry(peer_index).or_default() += 1;
+                }
+            }
+        }
+
+        let mut throttled = Vec::new();
         while let Some((tx_hash, mut priority)) = unknown_tx_hashes.pop() {
             if priority.should_request(now) {
+                let origin_priority = priority.clone();
                 if let Some(peer_index) = priority.next_request_peer() {
+                    let requests = outstanding.entry(peer_index).or_default();
+                    if *requests >= MAX_OUTSTANDING_RELAY_TXS_PER_PEER {
+                        // Ask for it later, after the peer responds or the requests time out
+                        throttled.push((tx_hash, origin_priority));
+                        continue;
+                    }
+                    *requests += 1;
                     result
                         .entry(peer_index)
                         .and_modify(|hashes| hashes.push(tx_hash.clone()))
@@ -1467,6 +1506,9 @@ impl SyncState {
                 break;
             }
         }
+        for (tx_hash, priority) in throttled {
+            unknown_tx_hashes.push(tx_hash, priority);
+        }
         result
     }
 
@@ -1762,53 +1804,35 @@ impl ActiveChain {
     }
 
     pub fn get_locator(&self, start: BlockNumberAndHash) -> Vec<Byte32> {
-        let mut step = 1;
-        let mut locator = Vec::with_capacity(32);
-        let mut index = start.number();
+        let heights = locator_heights(start.number());
+        let mut locator = Vec::with_capacity(heights.len());
         let mut base = start.hash();
-
-        loop {
-            let header_hash = self
-                .get_ancestor(&base, index)
-                .unwrap_or_else(|| {
-                    panic!(
-                        "index calculated in get_locator: \
-                         start: {:?}, base: {}, step: {}, locators({}): {:?}.",
-                        start,
-                        base,
-                        step,
-                        locator.len(),
-                        locator,
-                    )
-                })
-                .hash();
-            locator.push(header_hash.clone());
-
-            if locator.len() >= 10 {
-                step <<= 1;
-            }
-
-            if index < step * 2 {
-                // Insert some low-height blocks in the locator
-                // to quickly start parallel ibd block downloads
-                // and it should not be too much
-                //
-                // 100 * 365 * 86400 / 8 = 394200000  100 years block number
-                // 2 ** 29 = 536870912
-                // 2 ** 13 = 8192
-                // 52 = 10 + 29 + 13
-                if locator.len() < 52 && index > ONE_DAY_BLOCK_NUMBER {
-                    index >>= 1;
-                    base = header_hash;
-                    continue;
-                }
-                // always include genesis hash
-                if index != 0 {
-                    locator.push(self.sync_shared.consensus().genesis_hash());
-                }
-                break;
+        let mut on_main_chain = false;
+
+        for number in heights {
+            // Once the walk reaches the main chain, the rest of the heights are resolved by the
+            // block number index directly, instead of walking ancestors in the header map
+            on_main_chain = on_main_chain || self.is_main_chain(&base);
+            let header_hash = if number == 0 {
+                Some(self.sync_shared.consensus().genesis_hash())
+            } else if on_main_chain {
+                self.get_block_hash(number)
+            } else {
+                self.get_ancestor(&base, number)
+                    .map(|header_index| header_index.hash())
             }
-            index -= step;
+            .unwrap_or_else(|| {
+                panic!(
+                    "index calculated in get_locator: \
+                     start: {:?}, base: {}, number: {}, locators({}): {:?}.",
+                    start,
+                    base,
+                    number,
+                    locator.len(),
+                    locator,
+                )
+            });
+            locator.push(header_hash.clone());
             base = header_hash;
         }
         locator
@@ -1962,6 +1986,46 @@ impl ActiveChain {
     }
 }
 
+/// The heights of the block locator starting from `start`, in descending order.
+///
+/// The first 10 heights are consecutive, then the step doubles every time, genesis is always
+/// the last one.
+pub(crate) fn locator_heights(start: BlockNumber) -> Vec<BlockNumber> {
+    let mut step = 1;
+    let mut heights = Vec::with_capacity(32);
+    let mut index = start;
+
+    loop {
+        heights.push(index);
+
+        if heights.len() >= 10 {
+            step <<= 1;
+        }
+
+        if index < step * 2 {
+            // Insert some low-height blocks in the locator
+            // to quickly start parallel ibd block downloads
+            // and it should not be too much
+            //
+            // 100 * 365 * 86400 / 8 = 394200000  100 years block number
+            // 2 ** 29 = 536870912
+            // 2 ** 13 = 8192
+            // 52 = 10 + 29 + 13
+            if heights.len() < 52 && index > ONE_DAY_BLOCK_NUMBER {
+                index >>= 1;
+                continue;
+            }
+            // always include genesis hash
+            if index != 0 {
+                heights.push(0);
+            }
+            break;
+        }
+        index -= step;
+    }
+    heights
+}
+
 /// The `IBDState` enum represents whether the node is currently in the IBD process (`In`) or has
 /// completed it (`Out`).
 #[derive(Clone, Copy, Debug)]
@@ -408,11 +408,13 @@ fn all_specs() -> Vec<Box<dyn Spec>> {
         Box::new(BlockSyncForks),
         Box::new(BlockSyncDuplicatedAndReconnect),
         Box::new(BlockSyncOrphanBlocks),
+        Box::new(BlockSyncOrphanBlocksExpired),
         Box::new(BlockSyncWithUncle),
         Box::new(BlockSyncNonAncestorBestBlocks),
         Box::new(RequestUnverifiedBlocks),
         Box::new(SyncTimeout),
         Box::new(SyncInvalid),
+        Box::new(HeaderMapSpillover),
         Box::new(GetBlockFilterCheckPoints),
         Box::new(GetBlockFilterHashes),
         Box::new(GetBlockFilters),
@@ -462,9 +464,12 @@ fn all_specs() -> Vec<Box<dyn Spec>> {
         Box::new(TooManyUnknownTransactions),
         // TODO failed on poor CI server
         // Box::new(TransactionRelayMultiple),
+        Box::new(TransactionRelayFairness),
         Box::new(RelayInvalidTransaction),
         Box::new(RelayInvalidTransactionResumable),
         Box::new(TransactionRelayTimeout),
+        Box::new(TransactionRelayRetryFromOtherPeer),
+        Box::new(TransactionRelayOutstandingLimit),
         Box::new(TransactionRelayEmptyPeers),
         Box::new(TransactionRelayConflict),
         Box::new(Discovery),
@@ -509,6 +514,7 @@ fn all_specs() -> Vec<Box<dyn Spec>> {
         Box::new(CompactBlockLoseGetBlockTransactions),
         Box::new(CompactBlockRelayParentOfOrphanBlock),
         Box::new(CompactBlockRelayLessThenSharedBestKnown),
+        Box::new(CompactBlockRelayBasic),
         Box::new(InvalidLocatorSize),
         Box::new(SizeLimit),
         Box::new(TxPoolLimitAncestorCount),
@@ -541,12 +547,20 @@ fn all_specs() -> Vec<Box<dyn Spec>> {
         // TODO These cases will fail occasionally because of some unknown
         // asynchronous issues.
         Box::new(IBDProcess),
+        Box::new(IBDMinPeers),
         Box::new(WhitelistOnSessionLimit),
         // Box::new(IBDProcessWithWhiteList),
         Box::new(MalformedMessageWithWhitelist),
         // Box::new(InsufficientReward),
+        Box::new(BlockExceededMaximumCycles),
+        Box::new(BlockWithInvalidDAO),
+        Box::new(BlockRejectionRollback),
+        Box::new(BlockTimestampMedianTimePast),
+        Box::new(CommitTxProposedInUncle),
+        Box::new(CommitTxProposedInUncleOutOfWindow),
         Box::new(UncleInheritFromForkBlock),
         Box::new(UncleInheritFromForkUncle),
+        Box::new(UncleDescendantRule),
         Box::new(PackUnclesIntoEpochStarting),
         Box::new(FeeOfTransaction),
         Box::new(FeeOfMaxBlockProposalsLimit),
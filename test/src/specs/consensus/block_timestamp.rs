@@ -0,0 +1,62 @@
+use crate::util::check::assert_submit_block_fail;
+use crate::utils::now_ms;
+use crate::{Node, Spec};
+use ckb_logger::info;
+use ckb_types::prelude::*;
+
+// The allowed future block time is 15s in `ckb-verification`,
+// plus another 30s to make sure the block is still out of limit when it's submitted
+const FUTURE_BLOCK_TIME: u64 = 15_000 + 30_000;
+
+/// A block's timestamp must be greater than the median time of its ancestors,
+/// and must not be too far in the future.
+pub struct BlockTimestampMedianTimePast;
+
+impl Spec for BlockTimestampMedianTimePast {
+    fn run(&self, nodes: &mut Vec<Node>) {
+        let node0 = &nodes[0];
+        node0.mine(node0.consensus().median_time_block_count() as u64 * 2);
+
+        let tip_before = node0.get_tip_block();
+        let median_time = node0
+            .rpc_client()
+            .get_block_median_time(tip_before.hash())
+            .expect("tip median time")
+            .value();
+
+        info!("Submit a block with timestamp below the median time past");
+        let block = node0
+            .new_block_builder(None, None, None)
+            .timestamp((median_time - 1).pack())
+            .build();
+        assert_submit_block_fail(node0, &block, "BlockTimeTooOld");
+
+        info!("Submit a block with timestamp equal to the median time past");
+        let block = node0
+            .new_block_builder(None, None, None)
+            .timestamp(median_time.pack())
+            .build();
+        assert_submit_block_fail(node0, &block, "BlockTimeTooOld");
+
+        info!("Submit a block with timestamp too far in the future");
+        let block = node0
+            .new_block_builder(None, None, None)
+            .timestamp((now_ms() + FUTURE_BLOCK_TIME).pack())
+            .build();
+        assert_submit_block_fail(node0, &block, "BlockTimeTooNew");
+
+        assert_eq!(
+            tip_before.hash(),
+            node0.get_tip_block().hash(),
+            "the tip should not advance after rejecting blocks with invalid timestamp"
+        );
+
+        info!("Submit a block with timestamp just after the median time past");
+        let block = node0
+            .new_block_builder(None, None, None)
+            .timestamp((median_time + 1).pack())
+            .build();
+        node0.submit_block(&block);
+        assert_eq!(block.hash(), node0.get_tip_block().hash());
+    }
+}
@@ -0,0 +1,50 @@
+use crate::util::cell::gen_spendable;
+use crate::util::check::assert_submit_block_fail;
+use crate::util::transaction::always_success_transactions;
+use crate::{Node, Spec};
+use ckb_chain_spec::ChainSpec;
+use ckb_logger::info;
+
+const ALWAYS_SUCCESS_SCRIPT_CYCLE: u64 = 537;
+const TXS_COUNT: usize = 3;
+
+/// A block whose transactions collectively consume more cycles than `max_block_cycles` should
+/// be rejected with `ExceededMaximumCycles`, and the tip should stay unchanged.
+pub struct BlockExceededMaximumCycles;
+
+impl Spec for BlockExceededMaximumCycles {
+    fn run(&self, nodes: &mut Vec<Node>) {
+        let node0 = &nodes[0];
+
+        let cells = gen_spendable(node0, TXS_COUNT);
+        let txs = always_success_transactions(node0, &cells[..TXS_COUNT]);
+
+        // Propose the transactions, bypassing the tx-pool
+        let block = node0
+            .new_block_builder(None, None, None)
+            .proposals(txs.iter().map(|tx| tx.proposal_short_id()))
+            .build();
+        node0.submit_block(&block);
+        node0.mine(node0.consensus().tx_proposal_window().closest());
+
+        let tip_before = node0.get_tip_block();
+        let block = node0
+            .new_block_builder(None, None, None)
+            .transactions(txs)
+            .build();
+        info!("Submit a block whose transactions exceed max_block_cycles");
+        assert_submit_block_fail(node0, &block, "ExceededMaximumCycles");
+
+        let tip_after = node0.get_tip_block();
+        assert_eq!(
+            tip_before.hash(),
+            tip_after.hash(),
+            "the tip should not advance after rejecting the oversized block"
+        );
+    }
+
+    fn modify_chain_spec(&self, spec: &mut ChainSpec) {
+        // Enough cycles for a single always-success transaction, but not for all of them
+        spec.params.max_block_cycles = Some(ALWAYS_SUCCESS_SCRIPT_CYCLE * (TXS_COUNT as u64 - 1));
+    }
+}
@@ -0,0 +1,63 @@
+use crate::util::check::assert_submit_block_fail;
+use crate::util::mining::out_ibd_mode;
+use crate::utils::{build_compact_block, wait_until};
+use crate::{Net, Node, Spec};
+use ckb_logger::info;
+use ckb_network::SupportProtocols;
+use ckb_types::{core::BlockView, packed::Byte32, prelude::*};
+
+/// A block whose header `dao` field doesn't match the one calculated from its parent should be
+/// rejected with `InvalidDAO`, and the peer relaying it should be banned.
+pub struct BlockWithInvalidDAO;
+
+impl Spec for BlockWithInvalidDAO {
+    fn run(&self, nodes: &mut Vec<Node>) {
+        let node0 = &nodes[0];
+        out_ibd_mode(nodes);
+
+        let tip_before = node0.get_tip_block();
+        let block = node0.new_block(None, None, None);
+
+        info!("Submit a block with a zeroed dao field via RPC");
+        let zeroed = tamper_dao(&block, Byte32::zero());
+        assert_submit_block_fail(node0, &zeroed, "InvalidDAO");
+        assert_eq!(tip_before.hash(), node0.get_tip_block().hash());
+
+        info!("Relay a block with a corrupted dao field from a test node");
+        let mut net = Net::new(
+            self.name(),
+            node0.consensus(),
+            vec![SupportProtocols::Sync, SupportProtocols::RelayV3],
+        );
+        net.connect(node0);
+        let mut dao = block.header().dao().as_bytes().to_vec();
+        dao[0] ^= 0xff;
+        let corrupted = tamper_dao(&block, Byte32::from_slice(&dao).unwrap());
+        net.send(
+            node0,
+            SupportProtocols::RelayV3,
+            build_compact_block(&corrupted),
+        );
+
+        let rpc_client = node0.rpc_client();
+        let ret = wait_until(10, || {
+            rpc_client
+                .get_banned_addresses()
+                .iter()
+                .any(|ban| ban.address == "127.0.0.1/32")
+        });
+        assert!(
+            ret,
+            "Node0 should ban the peer relaying a block with invalid dao"
+        );
+        assert_eq!(tip_before.hash(), node0.get_tip_block().hash());
+
+        info!("The untampered block is still accepted");
+        node0.submit_block(&block);
+        assert_eq!(block.hash(), node0.get_tip_block().hash());
+    }
+}
+
+fn tamper_dao(block: &BlockView, dao: Byte32) -> BlockView {
+    block.as_advanced_builder().dao(dao).build()
+}
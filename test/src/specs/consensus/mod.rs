@@ -1,3 +1,13 @@
+mod block_timestamp;
+mod exceeded_maximum_cycles;
 mod insufficient_reward;
+mod invalid_dao;
+mod rejection_rollback;
+mod uncle_proposal;
 
+pub use block_timestamp::BlockTimestampMedianTimePast;
+pub use exceeded_maximum_cycles::BlockExceededMaximumCycles;
 pub use insufficient_reward::InsufficientReward;
+pub use invalid_dao::BlockWithInvalidDAO;
+pub use rejection_rollback::BlockRejectionRollback;
+pub use uncle_proposal::{CommitTxProposedInUncle, CommitTxProposedInUncleOutOfWindow};
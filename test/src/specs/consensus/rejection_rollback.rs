@@ -0,0 +1,65 @@
+use crate::util::cell::gen_spendable;
+use crate::util::check::{assert_submit_block_fail, is_transaction_proposed};
+use crate::util::transaction::always_success_transaction;
+use crate::{Node, Spec};
+use ckb_logger::info;
+use ckb_types::{
+    packed::{Byte32, OutPoint},
+    prelude::*,
+};
+
+/// A block is attached to the store transaction only after it passes the contextual
+/// verification. A block rejected late, after its transactions are resolved and its
+/// scripts are run, must leave the live cells exactly as if it never existed.
+pub struct BlockRejectionRollback;
+
+impl Spec for BlockRejectionRollback {
+    fn run(&self, nodes: &mut Vec<Node>) {
+        let node0 = &nodes[0];
+
+        let cells = gen_spendable(node0, 1);
+        let tx = always_success_transaction(node0, &cells[0]);
+        let input = cells[0].out_point.clone();
+        let output = OutPoint::new(tx.hash(), 0);
+        node0.submit_transaction(&tx);
+        node0.mine_until_bool(|| is_transaction_proposed(node0, &tx));
+
+        let block = node0.new_block(None, None, None);
+        assert!(
+            block.transactions().iter().any(|t| t.hash() == tx.hash()),
+            "the block should commit the proposed transaction"
+        );
+
+        let tip_before = node0.get_tip_block();
+        info!("Submit a block spending a live cell with an invalid dao field");
+        let mut dao = block.header().dao().as_bytes().to_vec();
+        dao[0] ^= 0xff;
+        let invalid = block
+            .as_advanced_builder()
+            .dao(Byte32::from_slice(&dao).unwrap())
+            .build();
+        assert_submit_block_fail(node0, &invalid, "InvalidDAO");
+        assert_eq!(tip_before.hash(), node0.get_tip_block().hash());
+        assert!(
+            is_live(node0, &input),
+            "the input spent by the rejected block should stay live"
+        );
+        assert!(
+            !is_live(node0, &output),
+            "the output created by the rejected block should not exist"
+        );
+
+        info!("Submit the valid block spending the same cell");
+        node0.submit_block(&block);
+        assert_eq!(block.hash(), node0.get_tip_block().hash());
+        assert!(!is_live(node0, &input));
+        assert!(is_live(node0, &output));
+    }
+}
+
+fn is_live(node: &Node, out_point: &OutPoint) -> bool {
+    node.rpc_client()
+        .get_live_cell(out_point.clone().into(), false)
+        .cell
+        .is_some()
+}
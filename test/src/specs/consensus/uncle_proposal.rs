@@ -0,0 +1,84 @@
+use crate::util::cell::gen_spendable;
+use crate::util::check::assert_submit_block_fail;
+use crate::util::transaction::always_success_transaction;
+use crate::{Node, Spec};
+use ckb_logger::info;
+use ckb_types::core::{BlockNumber, BlockView, TransactionView};
+use ckb_types::prelude::*;
+
+/// A transaction proposed only within an uncle can be committed once the block embedding
+/// the uncle falls into the proposal window of the committing block.
+pub struct CommitTxProposedInUncle;
+
+impl Spec for CommitTxProposedInUncle {
+    fn run(&self, nodes: &mut Vec<Node>) {
+        let node0 = &nodes[0];
+        let (tx, embedded_at) = propose_in_uncle(node0);
+
+        // Commit `tx` at the closest end of the window
+        let closest = node0.consensus().tx_proposal_window().closest();
+        node0.mine(embedded_at + closest - 1 - node0.get_tip_block_number());
+        let block = commit_block(node0, &tx);
+        info!(
+            "Submit block {} committing tx proposed in the uncle embedded at {}",
+            block.number(),
+            embedded_at
+        );
+        node0.submit_block(&block);
+        assert_eq!(node0.get_tip_block().hash(), block.hash());
+    }
+}
+
+/// A transaction proposed only within an uncle can't be committed after the block
+/// embedding the uncle falls out of the proposal window of the committing block.
+pub struct CommitTxProposedInUncleOutOfWindow;
+
+impl Spec for CommitTxProposedInUncleOutOfWindow {
+    fn run(&self, nodes: &mut Vec<Node>) {
+        let node0 = &nodes[0];
+        let (tx, embedded_at) = propose_in_uncle(node0);
+
+        // Commit `tx` one block beyond the farthest end of the window
+        let farthest = node0.consensus().tx_proposal_window().farthest();
+        node0.mine(embedded_at + farthest - node0.get_tip_block_number());
+        let tip_before = node0.get_tip_block();
+        let block = commit_block(node0, &tx);
+        info!(
+            "Submit block {} committing tx proposed in the uncle embedded at {}",
+            block.number(),
+            embedded_at
+        );
+        assert_submit_block_fail(node0, &block, "Commit(Invalid)");
+        assert_eq!(node0.get_tip_block().hash(), tip_before.hash());
+    }
+}
+
+// Propose `tx` only within an uncle, returns `tx` and the number of the block embedding
+// the uncle. `tx` is never submitted to the tx-pool, so the main-chain blocks never
+// propose it.
+fn propose_in_uncle(node: &Node) -> (TransactionView, BlockNumber) {
+    let cells = gen_spendable(node, 1);
+    let tx = always_success_transaction(node, &cells[0]);
+
+    let (block, uncle) = node.construct_uncle();
+    let uncle = uncle
+        .as_advanced_builder()
+        .set_proposals(vec![tx.proposal_short_id()])
+        .build();
+    node.submit_block(&block);
+
+    let block = node
+        .new_block_builder(None, None, None)
+        .set_uncles(vec![uncle.as_uncle()])
+        .set_proposals(vec![])
+        .build();
+    node.submit_block(&block);
+    assert_eq!(node.get_tip_block().hash(), block.hash());
+    (tx, block.number())
+}
+
+fn commit_block(node: &Node, tx: &TransactionView) -> BlockView {
+    node.new_block_builder(None, None, None)
+        .transaction(tx.clone())
+        .build()
+}
@@ -63,9 +63,14 @@ impl Spec for BootstrapCellbase {
             use_binary_version_as_message_prefix: false,
             binary_version: "TEST".to_string(),
             update_interval_millis: 0,
+            new_transactions_threshold: 0,
             notify: vec![],
             notify_scripts: vec![],
             notify_timeout_millis: 800,
+            assembly_deadline_millis: None,
+            max_block_transactions: None,
+            reward_locks: vec![],
+            reward_lock_policy: Default::default(),
         });
     }
 }
@@ -136,6 +136,65 @@ impl Spec for UncleInheritFromForkUncle {
     }
 }
 
+pub struct UncleDescendantRule;
+
+impl Spec for UncleDescendantRule {
+    // Case: An uncle must be a direct child of a main-block (or an embedded uncle), that is
+    //       its parent is a known block exactly one height below it
+    //    1. Construct `uncle` whose parent is the grandparent of the next block;
+    //    2. Submit block with a tampered `uncle` whose number mismatches the height of its
+    //       parent, should be failed with DescendantLimit;
+    //    3. Submit block with `uncle`, should be success
+
+    fn run(&self, nodes: &mut Vec<Node>) {
+        let node = &nodes[0];
+        let uncle = construct_uncle(node);
+        let uncle_parent = node
+            .rpc_client()
+            .get_header(uncle.parent_hash())
+            .expect("uncle parent is a main-block");
+        assert_eq!(uncle_parent.inner.number.value() + 1, uncle.number());
+
+        let invalid_uncle = uncle
+            .as_advanced_builder()
+            .number((uncle.number() - 1).pack())
+            .build();
+        let tip_before = node.get_tip_block();
+        let block = node
+            .new_block_builder(None, None, None)
+            .set_uncles(vec![invalid_uncle.as_uncle()])
+            .build();
+        let ret = node
+            .rpc_client()
+            .submit_block("0".to_owned(), block.data().into());
+        assert!(
+            ret.is_err(),
+            "Submit block with uncle at the wrong height should be failed, but got {ret:?}"
+        );
+        let err = ret.unwrap_err();
+        assert!(
+            err.to_string().contains("DescendantLimit"),
+            "The result should contain 'DescendantLimit', but got {err:?}"
+        );
+        assert_eq!(tip_before.hash(), node.get_tip_block().hash());
+
+        let block = node
+            .new_block_builder(None, None, None)
+            .set_uncles(vec![uncle.as_uncle()])
+            .build();
+        node.submit_block(&block);
+        assert_eq!(block.hash(), node.get_tip_block().hash());
+        assert_eq!(
+            vec![uncle.hash()],
+            node.get_tip_block()
+                .uncles()
+                .into_iter()
+                .map(|uncle| uncle.hash())
+                .collect::<Vec<_>>()
+        );
+    }
+}
+
 pub struct PackUnclesIntoEpochStarting;
 
 impl Spec for PackUnclesIntoEpochStarting {
@@ -1,4 +1,4 @@
-use crate::node::waiting_for_sync;
+use crate::node::{connect_all, waiting_for_sync};
 use crate::util::cell::gen_spendable;
 use crate::util::check::is_transaction_committed;
 use crate::util::mining::out_ibd_mode;
@@ -673,3 +673,84 @@ impl Spec for CompactBlockRelayLessThenSharedBestKnown {
         );
     }
 }
+
+pub struct CompactBlockRelayBasic;
+
+impl Spec for CompactBlockRelayBasic {
+    crate::setup!(num_nodes: 3);
+
+    // Case: A block mined by node0 is relayed to its peers as a compact block, and a compact
+    //       block with a transaction unknown to node0 is reconstructed by requesting the
+    //       missing transaction instead of banning the sender.
+    fn run(&self, nodes: &mut Vec<Node>) {
+        out_ibd_mode(nodes);
+        connect_all(nodes);
+        waiting_for_sync(nodes);
+
+        let node0 = &nodes[0];
+        let mut net = Net::new(
+            self.name(),
+            node0.consensus(),
+            vec![SupportProtocols::Sync, SupportProtocols::RelayV3],
+        );
+        net.connect(node0);
+
+        // 1. A newly mined block is announced as a compact block
+        node0.mine(1);
+        let ret = net.should_receive(node0, |data: &Bytes| {
+            RelayMessage::from_slice(data)
+                .map(|message| message.to_enum().item_name() == packed::CompactBlock::NAME)
+                .unwrap_or(false)
+        });
+        assert!(ret, "Node0 should relay the new block as a compact block");
+        waiting_for_sync(nodes);
+
+        // 2. Relay a block whose committed transaction is unknown to every node
+        let cells = gen_spendable(node0, 1);
+        let new_tx = always_success_transaction(node0, &cells[0]);
+        node0.submit_block(
+            &node0
+                .new_block_builder(None, None, None)
+                .proposal(new_tx.proposal_short_id())
+                .build(),
+        );
+        node0.mine(3);
+        waiting_for_sync(nodes);
+
+        let new_block = node0
+            .new_block_builder(None, None, None)
+            .transaction(new_tx)
+            .build();
+        net.send(
+            node0,
+            SupportProtocols::RelayV3,
+            build_compact_block(&new_block),
+        );
+        let ret = net.should_receive(node0, |data: &Bytes| {
+            RelayMessage::from_slice(data)
+                .map(|message| message.to_enum().item_name() == packed::GetBlockTransactions::NAME)
+                .unwrap_or(false)
+        });
+        assert!(
+            ret,
+            "Node0 should send GetBlockTransactions message for the unknown short id"
+        );
+
+        net.send(
+            node0,
+            SupportProtocols::RelayV3,
+            build_block_transactions(&new_block),
+        );
+        let ret = wait_until(10, || node0.get_tip_block() == new_block);
+        assert!(ret, "Node0 should reconstruct the block");
+        assert!(
+            node0.rpc_client().get_banned_addresses().is_empty(),
+            "Node0 should not ban the peer relaying the unknown short id"
+        );
+
+        // node1 and node2 get the block as a compact block from node0, and request the
+        // transaction from node0 in turn
+        waiting_for_sync(nodes);
+        assert!(nodes.iter().all(|node| node.get_tip_block() == new_block));
+    }
+}
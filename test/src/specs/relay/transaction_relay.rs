@@ -4,15 +4,16 @@ use crate::util::mining::out_ibd_mode;
 use crate::util::transaction::{always_success_transaction, always_success_transactions};
 use crate::utils::{build_relay_tx_hashes, build_relay_txs, sleep, wait_until};
 use crate::{Net, Node, Spec};
-use ckb_constant::sync::RETRY_ASK_TX_TIMEOUT_INCREASE;
+use ckb_constant::sync::{MAX_OUTSTANDING_RELAY_TXS_PER_PEER, RETRY_ASK_TX_TIMEOUT_INCREASE};
 use ckb_jsonrpc_types::Status;
 use ckb_logger::info;
 use ckb_network::SupportProtocols;
 use ckb_types::{
-    core::{capacity_bytes, Capacity, TransactionBuilder},
-    packed::{CellOutputBuilder, GetRelayTransactions, RelayMessage},
+    core::{capacity_bytes, Capacity, TransactionBuilder, TransactionView},
+    packed::{Byte32, CellOutputBuilder, GetRelayTransactions, RelayMessage, RelayMessageUnion},
     prelude::*,
 };
+use std::time::{Duration, Instant};
 
 pub struct TransactionRelayBasic;
 
@@ -79,6 +80,103 @@ impl Spec for TransactionRelayMultiple {
     }
 }
 
+const FAIRNESS_TXS_COUNT: usize = 20;
+
+pub struct TransactionRelayFairness;
+
+impl Spec for TransactionRelayFairness {
+    crate::setup!(num_nodes: 5);
+
+    // Transactions submitted to node0 should reach every peer, no peer is starved while the
+    // others are served, and relay doesn't serialize behind a slow peer which announces
+    // hashes but never delivers the transactions.
+    fn run(&self, nodes: &mut Vec<Node>) {
+        out_ibd_mode(nodes);
+        let node0 = &nodes[0];
+        let peers = &nodes[1..];
+        peers.iter().for_each(|peer| node0.connect(peer));
+
+        let cells = gen_spendable(node0, FAIRNESS_TXS_COUNT);
+        let transactions = always_success_transactions(node0, &cells);
+        waiting_for_sync(nodes);
+
+        info!("Connecting a slow peer which keeps node0 asking for transactions");
+        let mut slow_peer = Net::new(
+            self.name(),
+            node0.consensus(),
+            vec![SupportProtocols::Sync, SupportProtocols::RelayV3],
+        );
+        slow_peer.connect(node0);
+        let unknown_hashes: Vec<_> = (0..MAX_OUTSTANDING_RELAY_TXS_PER_PEER as u32)
+            .map(|version| {
+                TransactionBuilder::default()
+                    .version(version)
+                    .build()
+                    .hash()
+            })
+            .collect();
+        slow_peer.send(
+            node0,
+            SupportProtocols::RelayV3,
+            build_relay_tx_hashes(&unknown_hashes),
+        );
+
+        transactions.iter().for_each(|tx| {
+            node0.submit_transaction(tx);
+        });
+
+        let start = Instant::now();
+        let mut completed_at: Vec<Option<Duration>> = vec![None; peers.len()];
+        let mut progress_at_first_completion: Option<Vec<usize>> = None;
+        let relayed = wait_until(30, || {
+            let progress: Vec<usize> = peers
+                .iter()
+                .map(|peer| relay_progress(peer, &transactions))
+                .collect();
+            for (completed, received) in completed_at.iter_mut().zip(progress.iter()) {
+                if completed.is_none() && *received == transactions.len() {
+                    *completed = Some(start.elapsed());
+                }
+            }
+            if progress_at_first_completion.is_none() && completed_at.iter().any(Option::is_some) {
+                progress_at_first_completion = Some(progress);
+            }
+            completed_at.iter().all(Option::is_some)
+        });
+        info!("Relay completion time of every peer: {:?}", completed_at);
+        assert!(relayed, "every peer should receive all transactions");
+
+        let progress = progress_at_first_completion.expect("checked above");
+        assert!(
+            progress.iter().all(|received| *received > 0),
+            "no peer should be starved when the first peer completes, progress: {:?}",
+            progress
+        );
+
+        let completed_at: Vec<Duration> = completed_at.into_iter().flatten().collect();
+        let fastest = completed_at.iter().min().expect("peers are not empty");
+        let slowest = completed_at.iter().max().expect("peers are not empty");
+        assert!(
+            *slowest - *fastest < Duration::from_secs(10),
+            "relay should not serialize behind a single peer, completion time: {:?}",
+            completed_at
+        );
+    }
+}
+
+// The number of `transactions` which `node` has received
+fn relay_progress(node: &Node, transactions: &[TransactionView]) -> usize {
+    transactions
+        .iter()
+        .filter(|tx| {
+            node.rpc_client()
+                .get_transaction(tx.hash())
+                .transaction
+                .is_some()
+        })
+        .count()
+}
+
 pub struct TransactionRelayTimeout;
 
 impl Spec for TransactionRelayTimeout {
@@ -116,6 +214,108 @@ impl Spec for TransactionRelayTimeout {
     }
 }
 
+/// The relay protocol retries `RETRY_ASK_TX_TIMEOUT_INCREASE` later from another peer which
+/// announced the same hash, if the requested peer doesn't respond.
+pub struct TransactionRelayRetryFromOtherPeer;
+
+impl Spec for TransactionRelayRetryFromOtherPeer {
+    fn run(&self, nodes: &mut Vec<Node>) {
+        let node = nodes.pop().unwrap();
+        node.mine(4);
+        let protocols = vec![SupportProtocols::Sync, SupportProtocols::RelayV3];
+        let mut net1 = Net::new(self.name(), node.consensus(), protocols.clone());
+        net1.connect(&node);
+        let mut net2 = Net::new(self.name(), node.consensus(), protocols);
+        net2.connect(&node);
+
+        let tx_hash = TransactionBuilder::default().build().hash();
+        info!("Sending RelayTransactionHashes to node from the first peer");
+        net1.send(
+            &node,
+            SupportProtocols::RelayV3,
+            build_relay_tx_hashes(&[tx_hash.clone()]),
+        );
+        assert!(
+            receive_get_relay_tx(&net1, &node, &tx_hash, Duration::from_secs(10)),
+            "the first peer should be asked for the transaction"
+        );
+
+        info!("Sending RelayTransactionHashes to node from the second peer");
+        net2.send(
+            &node,
+            SupportProtocols::RelayV3,
+            build_relay_tx_hashes(&[tx_hash.clone()]),
+        );
+        assert!(
+            !receive_get_relay_tx(&net2, &node, &tx_hash, RETRY_ASK_TX_TIMEOUT_INCREASE / 2),
+            "the second peer should not be asked before the request to the first peer times out"
+        );
+        assert!(
+            receive_get_relay_tx(&net2, &node, &tx_hash, RETRY_ASK_TX_TIMEOUT_INCREASE),
+            "the second peer should be asked after the request to the first peer times out"
+        );
+        assert!(
+            !receive_get_relay_tx(&net1, &node, &tx_hash, Duration::from_secs(5)),
+            "the first peer should not be asked again"
+        );
+    }
+}
+
+pub struct TransactionRelayOutstandingLimit;
+
+impl Spec for TransactionRelayOutstandingLimit {
+    // A peer announcing a flood of unknown hashes should not induce unbounded
+    // `GetRelayTransactions` requests, the requested hashes are capped by
+    // `MAX_OUTSTANDING_RELAY_TXS_PER_PEER` until the peer responds or the requests time out.
+    fn run(&self, nodes: &mut Vec<Node>) {
+        let node = nodes.pop().unwrap();
+        node.mine(4);
+        let mut net = Net::new(
+            self.name(),
+            node.consensus(),
+            vec![SupportProtocols::Sync, SupportProtocols::RelayV3],
+        );
+        net.connect(&node);
+
+        let announced = MAX_OUTSTANDING_RELAY_TXS_PER_PEER + 1000;
+        let tx_hashes: Vec<_> = (0..announced as u32)
+            .map(|version| {
+                TransactionBuilder::default()
+                    .version(version)
+                    .build()
+                    .hash()
+            })
+            .collect();
+        info!(
+            "Sending {} hashes by RelayTransactionHashes to node",
+            announced
+        );
+        net.send(
+            &node,
+            SupportProtocols::RelayV3,
+            build_relay_tx_hashes(&tx_hashes),
+        );
+
+        let mut requested = 0;
+        while let Ok((_, _, data)) = net.receive_timeout(&node, Duration::from_secs(10)) {
+            if let Ok(message) = RelayMessage::from_slice(&data) {
+                if let RelayMessageUnion::GetRelayTransactions(get_relay_txs) = message.to_enum() {
+                    requested += get_relay_txs.tx_hashes().len();
+                }
+            }
+        }
+        info!("Node requested {} hashes", requested);
+        assert_eq!(
+            requested, MAX_OUTSTANDING_RELAY_TXS_PER_PEER,
+            "outstanding GetRelayTransactions requests should be bounded"
+        );
+        assert!(
+            node.rpc_client().get_banned_addresses().is_empty(),
+            "the peer stays below the unknown transactions limit and should not be banned"
+        );
+    }
+}
+
 fn run_relay_malformed_tx_with_cycle(spec: &dyn Spec, nodes: &mut Vec<Node>, cycle: u64) {
     let node = &nodes.pop().unwrap();
     node.mine(4);
@@ -186,6 +386,28 @@ fn wait_get_relay_txs(net: &Net, node: &Node) -> bool {
     })
 }
 
+// Wait for a `GetRelayTransactions` containing `tx_hash` within `timeout`
+fn receive_get_relay_tx(net: &Net, node: &Node, tx_hash: &Byte32, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+        let Ok((_, _, data)) = net.receive_timeout(node, remaining) else {
+            return false;
+        };
+        if let Ok(message) = RelayMessage::from_slice(&data) {
+            if let RelayMessageUnion::GetRelayTransactions(get_relay_txs) = message.to_enum() {
+                if get_relay_txs
+                    .tx_hashes()
+                    .into_iter()
+                    .any(|hash| &hash == tx_hash)
+                {
+                    return true;
+                }
+            }
+        }
+    }
+    false
+}
+
 pub struct TransactionRelayEmptyPeers;
 
 impl Spec for TransactionRelayEmptyPeers {
@@ -242,6 +242,67 @@ impl Spec for BlockSyncOrphanBlocks {
     }
 }
 
+const ORPHAN_BLOCK_MAX_AGE_SECS: u64 = 5;
+
+pub struct BlockSyncOrphanBlocksExpired;
+
+impl Spec for BlockSyncOrphanBlocksExpired {
+    crate::setup!(num_nodes: 2);
+
+    // Case: A peer sends the headers of a chain but never the first block, the rest blocks
+    //       stay orphaned until they expire, then node0 evicts them. The header of the
+    //       missing parent is known, so the peer is not banned for it.
+    fn run(&self, nodes: &mut Vec<Node>) {
+        let node0 = &nodes[0];
+        let node1 = &nodes[1];
+        out_ibd_mode(nodes);
+
+        // Generate some blocks from node1
+        let mut blocks: Vec<BlockView> = (1..=5)
+            .map(|_| {
+                let block = node1.new_block(None, None, None);
+                node1.submit_block(&block);
+                block
+            })
+            .collect();
+
+        let mut net = Net::new(self.name(), node0.consensus(), vec![SupportProtocols::Sync]);
+        net.connect(node0);
+        let rpc_client = node0.rpc_client();
+        let tip_number = rpc_client.get_tip_block_number();
+
+        // Send headers to node0, keep blocks body
+        blocks.iter().for_each(|block| {
+            sync_header(&net, node0, block);
+        });
+        should_receive_get_blocks_message(&net, node0, blocks.last().unwrap().hash());
+
+        // Withhold the first block, the rest blocks become orphans
+        blocks.remove(0);
+        blocks.iter().for_each(|block| {
+            sync_block(&net, node0, block);
+        });
+        let orphan_blocks_count = || rpc_client.sync_state().orphan_blocks_count.value();
+        let ret = wait_until(10, || orphan_blocks_count() == blocks.len() as u64);
+        assert!(ret, "node0 should keep the blocks as orphans");
+
+        // The cleanup runs at least every max age
+        let ret = wait_until(ORPHAN_BLOCK_MAX_AGE_SECS * 4, || orphan_blocks_count() == 0);
+        assert!(ret, "node0 should evict the expired orphans");
+        assert_eq!(rpc_client.get_tip_block_number(), tip_number);
+
+        let ret = wait_until(5, || !rpc_client.get_banned_addresses().is_empty());
+        assert!(
+            !ret,
+            "node0 should not ban the peer while the parent header is known"
+        );
+    }
+
+    fn modify_app_config(&self, config: &mut ckb_app_config::CKBAppConfig) {
+        config.network.sync.orphan_block_max_age_secs = Some(ORPHAN_BLOCK_MAX_AGE_SECS);
+    }
+}
+
 /// test case:
 /// 1. generate 1-17 block
 /// 2. sync 1-16 header to node
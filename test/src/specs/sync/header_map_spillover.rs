@@ -0,0 +1,75 @@
+use crate::node::waiting_for_sync;
+use crate::utils::{build_headers, sleep, wait_until};
+use crate::{Net, Node, Spec};
+use ckb_app_config::CKBAppConfig;
+use ckb_logger::info;
+use ckb_network::SupportProtocols;
+use ckb_types::core::HeaderView;
+
+const BLOCKS_NUM: u64 = 600;
+
+pub struct HeaderMapSpillover;
+
+impl Spec for HeaderMapSpillover {
+    crate::setup!(num_nodes: 2);
+
+    // Case: node0 receives all headers of node1 from a peer which never serves the blocks, with
+    //       a header map far smaller than the headers, so they spill into the disk backend.
+    //       After a restart, node0 still knows the best header before connecting to any peer,
+    //       then downloads the blocks from node1 and catches up with it.
+    fn run(&self, nodes: &mut Vec<Node>) {
+        nodes[1].mine(BLOCKS_NUM);
+        let headers: Vec<HeaderView> = (1..=BLOCKS_NUM)
+            .map(|number| nodes[1].get_header_by_number(number))
+            .collect();
+
+        info!("Send the headers of node1 to node0 with a tiny header map");
+        let mut net = Net::new(
+            self.name(),
+            nodes[0].consensus(),
+            vec![SupportProtocols::Sync],
+        );
+        net.connect(&nodes[0]);
+        net.send(&nodes[0], SupportProtocols::Sync, build_headers(&headers));
+        let rpc_client = nodes[0].rpc_client();
+        let received = wait_until(60, || {
+            rpc_client.sync_state().best_known_block_number.value() == BLOCKS_NUM
+        });
+        assert!(received, "node0 should accept all headers of node1");
+        assert_eq!(nodes[0].get_tip_block_number(), 0);
+
+        // wait for a few trims, each flushes the headers into the backend
+        sleep(1);
+        nodes[0].stop();
+        let header_map_dir = nodes[0].working_dir().join("data/tmp/header_map");
+        assert!(
+            header_map_dir
+                .read_dir()
+                .map(|mut entries| entries.next().is_some())
+                .unwrap_or(false),
+            "node0 should keep the spilled headers in {}",
+            header_map_dir.display()
+        );
+
+        info!("Restart node0, the headers are served by the persisted header map");
+        nodes[0].start();
+        let sync_state = nodes[0].rpc_client().sync_state();
+        assert_eq!(
+            sync_state.best_known_block_number.value(),
+            BLOCKS_NUM,
+            "node0 should restore the best known header without requesting the headers again"
+        );
+        assert_eq!(sync_state.tip_number.value(), 0);
+
+        nodes[0].connect(&nodes[1]);
+        waiting_for_sync(nodes);
+        assert_eq!(nodes[0].get_tip_block(), nodes[1].get_tip_block());
+    }
+
+    fn modify_app_config(&self, config: &mut CKBAppConfig) {
+        let header_map = &mut config.network.sync.header_map;
+        header_map.memory_limit = "16KB".parse().expect("valid memory limit");
+        header_map.trim_interval_ms = Some(100);
+        header_map.persistent = true;
+    }
+}
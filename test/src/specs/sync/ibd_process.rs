@@ -1,3 +1,4 @@
+use crate::node::waiting_for_sync;
 use crate::utils::{sleep, wait_until};
 use crate::{Node, Spec};
 use ckb_logger::info;
@@ -137,3 +138,45 @@ impl Spec for IBDProcessWithWhiteList {
         );
     }
 }
+
+/// A node requiring two sync peers stays in IBD while synced from a lone peer, and exits IBD
+/// once a second peer connects.
+pub struct IBDMinPeers;
+
+impl Spec for IBDMinPeers {
+    crate::setup!(num_nodes: 3);
+
+    fn run(&self, nodes: &mut Vec<Node>) {
+        info!("Running IBD process with a minimum of sync peers");
+
+        {
+            nodes[0].stop();
+            nodes[0].modify_app_config(|config| {
+                config.network.sync.min_peers_to_exit_ibd = 2;
+            });
+            nodes[0].start();
+        }
+
+        let node0 = &nodes[0];
+        let node1 = &nodes[1];
+        let node2 = &nodes[2];
+
+        node1.mine(2);
+        node2.connect(node1);
+        waiting_for_sync(&nodes[1..]);
+
+        node0.connect(node1);
+        let is_node0_sync = wait_until(20, || {
+            node0.get_tip_block_number() == node1.get_tip_block_number()
+        });
+        assert!(is_node0_sync, "node0 must sync with node1");
+        assert!(
+            node0.rpc_client().sync_state().ibd,
+            "node0 must stay in IBD with a lone peer"
+        );
+
+        node0.connect(node2);
+        let is_node0_out_ibd = wait_until(10, || !node0.rpc_client().sync_state().ibd);
+        assert!(is_node0_out_ibd, "node0 must exit IBD with two peers");
+    }
+}
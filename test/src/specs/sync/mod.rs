@@ -2,6 +2,7 @@ mod block_filter;
 mod block_sync;
 mod chain_forks;
 mod get_blocks;
+mod header_map_spillover;
 mod ibd_process;
 mod invalid_block;
 mod invalid_locator_size;
@@ -15,6 +16,7 @@ pub use block_filter::*;
 pub use block_sync::*;
 pub use chain_forks::*;
 pub use get_blocks::*;
+pub use header_map_spillover::*;
 pub use ibd_process::*;
 pub use invalid_block::*;
 pub use invalid_locator_size::*;
@@ -9,7 +9,7 @@ mod tests;
 use crate::component::entry::TxEntry;
 use crate::error::BlockAssemblerError;
 pub use candidate_uncles::CandidateUncles;
-use ckb_app_config::BlockAssemblerConfig;
+use ckb_app_config::{BlockAssemblerConfig, RewardLockPolicy};
 use ckb_dao::DaoCalculator;
 use ckb_error::{AnyError, InternalErrorKind};
 use ckb_jsonrpc_types::{
@@ -33,15 +33,18 @@ use ckb_types::{
     },
     prelude::*,
 };
+use ckb_verification::cache::TxVerificationCache;
 use http_body_util::Full;
 use hyper::{Method, Request};
 use hyper_util::client::legacy::{connect::HttpConnector, Client};
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::{rngs::StdRng, SeedableRng};
 use std::collections::HashSet;
 use std::sync::{
     atomic::{AtomicU64, Ordering},
     Arc,
 };
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::{cmp, iter};
 use tokio::process::Command;
 use tokio::sync::{Mutex, RwLock};
@@ -49,7 +52,7 @@ use tokio::task::block_in_place;
 use tokio::time::timeout;
 
 use crate::TxPool;
-pub(crate) use process::process;
+pub(crate) use process::{process, QueuedMessages};
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub(crate) struct TemplateSize {
@@ -168,7 +171,19 @@ impl BlockAssembler {
         }
     }
 
-    pub(crate) async fn update_full(&self, tx_pool: &RwLock<TxPool>) -> Result<(), AnyError> {
+    // The deadline of transactions selection, counting from the start of the template update
+    fn assembly_deadline(&self) -> Option<Instant> {
+        self.config
+            .assembly_deadline_millis
+            .map(|millis| Instant::now() + Duration::from_millis(millis))
+    }
+
+    pub(crate) async fn update_full(
+        &self,
+        tx_pool: &RwLock<TxPool>,
+        txs_verify_cache: &RwLock<TxVerificationCache>,
+    ) -> Result<(), AnyError> {
+        let deadline = self.assembly_deadline();
         let mut current = self.current.lock().await;
         let consensus = current.snapshot.consensus();
         let max_block_bytes = consensus.max_block_bytes() as usize;
@@ -197,10 +212,17 @@ impl BlockAssembler {
 
             let max_block_cycles = consensus.max_block_cycles();
             let (txs, _txs_size, _cycles) =
-                tx_pool_reader.package_txs(max_block_cycles, txs_size_limit);
+                tx_pool_reader.package_txs(max_block_cycles, txs_size_limit, deadline);
             (proposals, txs, basic_size)
         };
 
+        let (txs, _cycles) = Self::apply_cycles_budget(
+            txs,
+            &*txs_verify_cache.read().await,
+            consensus.max_block_cycles(),
+            self.config.max_block_transactions,
+        );
+
         let proposals_size = proposals.len() * ProposalShortId::serialized_size();
         let (dao, checked_txs, failed_txs) = Self::calc_dao(
             &current.snapshot,
@@ -215,6 +237,7 @@ impl BlockAssembler {
             }
         }
 
+        let cycles = Self::total_cycles(&checked_txs, &*txs_verify_cache.read().await);
         let txs_size = checked_txs.iter().map(|tx| tx.size).sum();
         let total_size = basic_size + txs_size;
 
@@ -222,6 +245,7 @@ impl BlockAssembler {
         builder
             .set_proposals(Vec::from_iter(proposals))
             .set_transactions(checked_txs)
+            .cycles(cycles)
             .work_id(self.work_id.fetch_add(1, Ordering::SeqCst))
             .current_time(cmp::max(
                 unix_time_as_millis(),
@@ -235,11 +259,12 @@ impl BlockAssembler {
         current.size.proposals = proposals_size;
 
         trace!(
-            "[BlockAssembler] update_full {} uncles-{} proposals-{} txs-{}",
+            "[BlockAssembler] update_full {} uncles-{} proposals-{} txs-{} cycles-{}",
             current.template.number,
             current.template.uncles.len(),
             current.template.proposals.len(),
             current.template.transactions.len(),
+            current.template.cycles,
         );
 
         Ok(())
@@ -384,10 +409,20 @@ impl BlockAssembler {
         }
     }
 
+    /// The size limit of transactions in the current template
+    pub(crate) async fn txs_size_limit(&self) -> usize {
+        let current = self.current.lock().await;
+        let max_block_bytes = current.snapshot.consensus().max_block_bytes() as usize;
+        let basic_block_size = current.size.total.saturating_sub(current.size.txs);
+        max_block_bytes.saturating_sub(basic_block_size)
+    }
+
     pub(crate) async fn update_transactions(
         &self,
         tx_pool: &RwLock<TxPool>,
+        txs_verify_cache: &RwLock<TxVerificationCache>,
     ) -> Result<(), AnyError> {
+        let deadline = self.assembly_deadline();
         let mut current = self.current.lock().await;
         let consensus = current.snapshot.consensus();
         let current_template = &current.template;
@@ -413,10 +448,19 @@ impl BlockAssembler {
             }
 
             let max_block_cycles = consensus.max_block_cycles();
-            let (txs, _txs_size, _cycles) = tx_pool_reader
-                .package_txs(max_block_cycles, txs_size_limit.expect("overflow checked"));
+            let (txs, _txs_size, _cycles) = tx_pool_reader.package_txs(
+                max_block_cycles,
+                txs_size_limit.expect("overflow checked"),
+                deadline,
+            );
             txs
         };
+        let (txs, _cycles) = Self::apply_cycles_budget(
+            txs,
+            &*txs_verify_cache.read().await,
+            consensus.max_block_cycles(),
+            self.config.max_block_transactions,
+        );
 
         if let Ok((dao, checked_txs, _failed_txs)) = Self::calc_dao(
             &current.snapshot,
@@ -424,11 +468,13 @@ impl BlockAssembler {
             current_template.cellbase.clone(),
             txs,
         ) {
+            let cycles = Self::total_cycles(&checked_txs, &*txs_verify_cache.read().await);
             let new_txs_size = checked_txs.iter().map(|tx| tx.size).sum();
             let new_total_size = current.size.calc_total_by_txs(new_txs_size);
             let mut builder = BlockTemplateBuilder::from_template(&current.template);
             builder
                 .set_transactions(checked_txs)
+                .cycles(cycles)
                 .work_id(self.work_id.fetch_add(1, Ordering::SeqCst))
                 .current_time(cmp::max(
                     unix_time_as_millis(),
@@ -443,12 +489,13 @@ impl BlockAssembler {
             current.size.total = new_total_size;
 
             trace!(
-                "[BlockAssembler] update_transactions-{} epoch-{} uncles-{} proposals-{} txs-{}",
+                "[BlockAssembler] update_transactions-{} epoch-{} uncles-{} proposals-{} txs-{} cycles-{}",
                 current.template.number,
                 current.template.epoch.number(),
                 current.template.uncles.len(),
                 current.template.proposals.len(),
                 current.template.transactions.len(),
+                current.template.cycles,
             );
         }
         Ok(())
@@ -459,17 +506,49 @@ impl BlockAssembler {
         (&current.template).into()
     }
 
+    /// The miner lock of the block `candidate_number`, chosen among the configured locks by
+    /// `config.reward_lock_policy`
+    ///
+    /// The weighted random choice is seeded by the block number, so every template of the same
+    /// block pays the same lock.
+    pub(crate) fn reward_lock(
+        config: &BlockAssemblerConfig,
+        candidate_number: BlockNumber,
+    ) -> Script {
+        let candidates: Vec<_> =
+            iter::once((&config.code_hash, &config.args, &config.hash_type, 1))
+                .chain(
+                    config
+                        .reward_locks
+                        .iter()
+                        .map(|lock| (&lock.code_hash, &lock.args, &lock.hash_type, lock.weight)),
+                )
+                .collect();
+        let index = match config.reward_lock_policy {
+            RewardLockPolicy::RoundRobin => (candidate_number % candidates.len() as u64) as usize,
+            RewardLockPolicy::WeightedRandom => {
+                let mut rng = StdRng::seed_from_u64(candidate_number);
+                WeightedIndex::new(candidates.iter().map(|candidate| candidate.3))
+                    .map(|weights| weights.sample(&mut rng))
+                    .unwrap_or(0)
+            }
+        };
+
+        let (code_hash, args, hash_type, _) = candidates[index];
+        let hash_type: ScriptHashType = hash_type.clone().into();
+        Script::new_builder()
+            .args(args.as_bytes().pack())
+            .code_hash(code_hash.pack())
+            .hash_type(hash_type.into())
+            .build()
+    }
+
     pub(crate) fn build_cellbase_witness(
         config: &BlockAssemblerConfig,
         snapshot: &Snapshot,
     ) -> CellbaseWitness {
-        let hash_type: ScriptHashType = config.hash_type.clone().into();
-        let cellbase_lock = Script::new_builder()
-            .args(config.args.as_bytes().pack())
-            .code_hash(config.code_hash.pack())
-            .hash_type(hash_type.into())
-            .build();
         let tip = snapshot.tip_header();
+        let cellbase_lock = Self::reward_lock(config, tip.number() + 1);
 
         let mut message = vec![];
         if let Some(version) = snapshot.compute_versionbits(tip) {
@@ -509,7 +588,7 @@ impl BlockAssembler {
             let input = CellInput::new_cellbase_input(candidate_number);
             let output = CellOutput::new_builder()
                 .capacity(block_reward.total.pack())
-                .lock(target_lock)
+                .lock(target_lock.clone())
                 .build();
 
             let witness = cellbase_witness.as_bytes().pack();
@@ -520,9 +599,11 @@ impl BlockAssembler {
             if no_finalization_target || insufficient_reward_to_create_cell {
                 tx_builder.build()
             } else {
+                let outputs = [(output, bytes::Bytes::default())];
+                Self::check_cellbase_outputs(&outputs, &target_lock, block_reward.total)?;
                 tx_builder
-                    .output(output)
-                    .output_data(Bytes::default())
+                    .outputs(outputs.iter().map(|(output, _)| output.clone()))
+                    .outputs_data(outputs.iter().map(|(_, data)| data.pack()))
                     .build()
             }
         };
@@ -530,6 +611,33 @@ impl BlockAssembler {
         Ok(tx)
     }
 
+    /// Check the cellbase outputs before packing them into a template, with the same rules as
+    /// `RewardVerifier`, so a template never carries a cellbase the chain rejects.
+    ///
+    /// Every output must pay the `target_lock` of the reward calculator and hold its occupied
+    /// capacity, and all outputs together must pay exactly the `reward`.
+    pub(crate) fn check_cellbase_outputs(
+        outputs: &[(CellOutput, bytes::Bytes)],
+        target_lock: &Script,
+        reward: Capacity,
+    ) -> Result<(), AnyError> {
+        let mut total = Capacity::zero();
+        for (index, (output, data)) in outputs.iter().enumerate() {
+            if &output.lock() != target_lock {
+                return Err(BlockAssemblerError::InvalidCellbaseRewardTarget(index).into());
+            }
+            if output.is_lack_of_capacity(Capacity::bytes(data.len())?)? {
+                return Err(BlockAssemblerError::InsufficientCellbaseOutputCapacity(index).into());
+            }
+            let capacity: Capacity = output.capacity().unpack();
+            total = total.safe_add(capacity)?;
+        }
+        if total != reward {
+            return Err(BlockAssemblerError::MismatchedCellbaseReward.into());
+        }
+        Ok(())
+    }
+
     pub(crate) fn build_extension(snapshot: &Snapshot) -> Result<Option<packed::Bytes>, AnyError> {
         let tip_header = snapshot.tip_header();
         // The use of the epoch number of the tip here leads to an off-by-one bug,
@@ -587,6 +695,67 @@ impl BlockAssembler {
         block.serialized_size_without_uncle_proposals()
     }
 
+    /// Accumulate the cycles of the packaged transactions against `max_block_cycles`.
+    ///
+    /// The cycles recorded in the verification cache are preferred, transactions without a
+    /// cache entry fall back to the cycles verified when they entered the pool. A transaction
+    /// which doesn't fit the budget is deferred to the next template and the following ones
+    /// are still tried, the same as `package_txs` does with the size. Packaged transactions
+    /// are sorted with ancestors first, a transaction depending on a deferred one is deferred
+    /// too, so an ancestor package is never split.
+    ///
+    /// `max_transactions` is an extra ceiling of the number of selected transactions.
+    pub(crate) fn apply_cycles_budget(
+        entries: Vec<TxEntry>,
+        txs_verify_cache: &TxVerificationCache,
+        max_block_cycles: Cycle,
+        max_transactions: Option<usize>,
+    ) -> (Vec<TxEntry>, Cycle) {
+        let max_transactions = max_transactions.unwrap_or(usize::MAX);
+        let mut total_cycles: Cycle = 0;
+        let mut selected = Vec::with_capacity(entries.len().min(max_transactions));
+        let mut deferred: HashSet<Byte32> = HashSet::new();
+        for entry in entries {
+            if selected.len() >= max_transactions {
+                break;
+            }
+            let tx = entry.transaction();
+            let depends_on_deferred = tx
+                .input_pts_iter()
+                .chain(tx.cell_deps_iter().map(|cell_dep| cell_dep.out_point()))
+                .any(|out_point| deferred.contains(&out_point.tx_hash()));
+            let cycles = Self::entry_cycles(&entry, txs_verify_cache);
+            match total_cycles.checked_add(cycles) {
+                Some(next_cycles) if next_cycles <= max_block_cycles && !depends_on_deferred => {
+                    total_cycles = next_cycles;
+                    selected.push(entry);
+                }
+                _ => {
+                    deferred.insert(tx.hash());
+                }
+            }
+        }
+        (selected, total_cycles)
+    }
+
+    /// The cycles of the transactions left after `calc_dao` drops the failed ones, counted the
+    /// same way as `apply_cycles_budget`.
+    pub(crate) fn total_cycles(
+        entries: &[TxEntry],
+        txs_verify_cache: &TxVerificationCache,
+    ) -> Cycle {
+        entries
+            .iter()
+            .map(|entry| Self::entry_cycles(entry, txs_verify_cache))
+            .sum()
+    }
+
+    fn entry_cycles(entry: &TxEntry, txs_verify_cache: &TxVerificationCache) -> Cycle {
+        txs_verify_cache
+            .peek(&entry.transaction().witness_hash())
+            .map_or(entry.cycles, |completed| completed.cycles)
+    }
+
     fn calc_dao(
         snapshot: &Snapshot,
         current_epoch: &EpochExt,
@@ -725,6 +894,8 @@ pub(crate) struct BlockTemplate {
     pub(crate) dao: Byte32,
     pub(crate) current_time: u64,
     pub(crate) extension: Option<Bytes>,
+    // accumulated cycles of transactions
+    pub(crate) cycles: Cycle,
 }
 
 impl<'a> From<&'a BlockTemplate> for JsonBlockTemplate {
@@ -745,6 +916,19 @@ impl<'a> From<&'a BlockTemplate> for JsonBlockTemplate {
                 .map(tx_entry_to_template)
                 .collect(),
             proposals: template.proposals.iter().map(Into::into).collect(),
+            total_cycles: template.cycles.into(),
+            total_size: (template
+                .transactions
+                .iter()
+                .map(|entry| entry.size as u64)
+                .sum::<u64>())
+            .into(),
+            total_fees: template
+                .transactions
+                .iter()
+                .map(|entry| entry.fee.as_u64())
+                .fold(0u64, u64::saturating_add)
+                .into(),
             cellbase: cellbase_to_template(&template.cellbase),
             work_id: template.work_id.into(),
             dao: template.dao.clone().into(),
@@ -774,6 +958,7 @@ pub(crate) struct BlockTemplateBuilder {
     pub(crate) dao: Option<Byte32>,
     pub(crate) current_time: Option<u64>,
     pub(crate) extension: Option<Bytes>,
+    pub(crate) cycles: Cycle,
 }
 
 impl BlockTemplateBuilder {
@@ -807,6 +992,7 @@ impl BlockTemplateBuilder {
             dao: None,
             current_time: None,
             extension: None,
+            cycles: 0,
         }
     }
 
@@ -829,6 +1015,7 @@ impl BlockTemplateBuilder {
             work_id: None,
             dao: Some(template.dao.clone()),
             current_time: None,
+            cycles: template.cycles,
         }
     }
 
@@ -878,6 +1065,11 @@ impl BlockTemplateBuilder {
         self
     }
 
+    pub(crate) fn cycles(&mut self, cycles: Cycle) -> &mut Self {
+        self.cycles = cycles;
+        self
+    }
+
     pub(crate) fn dao(&mut self, dao: Byte32) -> &mut Self {
         self.dao = Some(dao);
         self
@@ -918,6 +1110,7 @@ impl BlockTemplateBuilder {
             dao: self.dao.expect("dao assert checked"),
             current_time: self.current_time.expect("current_time assert checked"),
             extension: self.extension,
+            cycles: self.cycles,
         }
     }
 }
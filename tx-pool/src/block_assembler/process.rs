@@ -1,6 +1,60 @@
 use crate::service::{BlockAssemblerMessage, TxPoolService};
+use ckb_util::LinkedHashSet;
 use std::sync::Arc;
 
+/// The block assembler messages coalesced until the next template update
+pub(crate) struct QueuedMessages {
+    messages: LinkedHashSet<BlockAssemblerMessage>,
+    new_transactions: u16,
+    new_transactions_threshold: u16,
+}
+
+impl QueuedMessages {
+    pub(crate) fn new(new_transactions_threshold: u16) -> Self {
+        QueuedMessages {
+            messages: LinkedHashSet::new(),
+            new_transactions: 0,
+            new_transactions_threshold,
+        }
+    }
+
+    /// Queue `message`, returns true once `new_transactions_threshold` new transactions are
+    /// queued, then the template should be updated without waiting for the interval
+    pub(crate) fn push(&mut self, message: BlockAssemblerMessage) -> bool {
+        if matches!(
+            message,
+            BlockAssemblerMessage::Pending | BlockAssemblerMessage::Proposed
+        ) {
+            self.new_transactions = self.new_transactions.saturating_add(1);
+        }
+        self.messages.insert(message);
+        self.new_transactions_threshold > 0
+            && self.new_transactions >= self.new_transactions_threshold
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.messages.clear();
+        self.new_transactions = 0;
+    }
+
+    /// Update the template with the queued messages, and notify if anything was queued
+    pub(crate) async fn flush(&mut self, service: &TxPoolService) {
+        for message in self.messages.iter() {
+            process(service.clone(), message).await;
+        }
+        if !self.is_empty() {
+            if let Some(ref block_assembler) = service.block_assembler {
+                block_assembler.notify().await;
+            }
+        }
+        self.clear();
+    }
+}
+
 pub(crate) async fn process(service: TxPoolService, message: &BlockAssemblerMessage) {
     match message {
         BlockAssemblerMessage::Pending => {
@@ -10,7 +64,10 @@ pub(crate) async fn process(service: TxPoolService, message: &BlockAssemblerMess
         }
         BlockAssemblerMessage::Proposed => {
             if let Some(ref block_assembler) = service.block_assembler {
-                if let Err(e) = block_assembler.update_transactions(&service.tx_pool).await {
+                if let Err(e) = block_assembler
+                    .update_transactions(&service.tx_pool, &service.txs_verify_cache)
+                    .await
+                {
                     ckb_logger::error!("block_assembler update_transactions error {}", e);
                 }
             }
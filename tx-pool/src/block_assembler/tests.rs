@@ -1,11 +1,21 @@
+use ckb_app_config::{BlockAssemblerConfig, RewardLockConfig, RewardLockPolicy};
+use ckb_jsonrpc_types::{JsonBytes, ScriptHashType};
 use ckb_types::{
-    core::{BlockBuilder, BlockNumber, EpochNumberWithFraction},
+    bytes::Bytes,
+    core::{BlockBuilder, BlockNumber, Capacity, EpochNumberWithFraction, TransactionBuilder},
+    packed,
     prelude::*,
+    H256,
 };
+use ckb_verification::cache::{init_cache, Completed};
 
 use crate::block_assembler::candidate_uncles::{
     CandidateUncles, MAX_CANDIDATE_UNCLES, MAX_PER_HEIGHT,
 };
+use crate::block_assembler::{BlockAssembler, QueuedMessages};
+use crate::component::entry::TxEntry;
+use crate::error::BlockAssemblerError;
+use crate::service::BlockAssemblerMessage;
 
 #[test]
 fn test_candidate_uncles_basic() {
@@ -72,3 +82,317 @@ fn test_candidate_uncles_max_per_height() {
     assert_eq!(candidate_uncles.map.len(), 1);
     assert_eq!(candidate_uncles.len(), MAX_PER_HEIGHT);
 }
+
+#[test]
+fn test_apply_cycles_budget() {
+    let entries: Vec<TxEntry> = (0..4u8)
+        .map(|i| {
+            let tx = TransactionBuilder::default()
+                .witness(Bytes::from(vec![i]).pack())
+                .build();
+            TxEntry::dummy_resolve(tx, 100, Capacity::shannons(100), 100)
+        })
+        .collect();
+
+    let mut cache = init_cache();
+    // the cached cycles take precedence over the entry cycles
+    cache.put(
+        entries[1].transaction().witness_hash(),
+        Completed {
+            cycles: 200,
+            fee: Capacity::shannons(100),
+        },
+    );
+
+    let (selected, cycles) =
+        BlockAssembler::apply_cycles_budget(entries.clone(), &cache, 10_000, None);
+    assert_eq!(selected.len(), 4);
+    assert_eq!(cycles, 500);
+
+    // the txs exceeding the budget are deferred
+    let (selected, cycles) =
+        BlockAssembler::apply_cycles_budget(entries.clone(), &cache, 399, None);
+    assert_eq!(selected.len(), 2);
+    assert_eq!(cycles, 300);
+
+    let (selected, cycles) = BlockAssembler::apply_cycles_budget(entries.clone(), &cache, 99, None);
+    assert!(selected.is_empty());
+    assert_eq!(cycles, 0);
+
+    // a transaction dropped after the budget is applied no longer counts
+    let (mut selected, _cycles) =
+        BlockAssembler::apply_cycles_budget(entries, &cache, 10_000, None);
+    selected.remove(1);
+    assert_eq!(BlockAssembler::total_cycles(&selected, &cache), 300);
+}
+
+#[test]
+fn test_apply_cycles_budget_with_max_transactions() {
+    // cheap and expensive transactions interleaved
+    let entries: Vec<TxEntry> = (0..6u8)
+        .map(|i| {
+            let tx = TransactionBuilder::default()
+                .witness(Bytes::from(vec![i]).pack())
+                .build();
+            let cycles = if i % 2 == 0 { 10 } else { 1_000 };
+            TxEntry::dummy_resolve(tx, cycles, Capacity::shannons(100), 100)
+        })
+        .collect();
+    let cache = init_cache();
+
+    // the cycles limit is reached long before the count ceiling
+    let (selected, cycles) =
+        BlockAssembler::apply_cycles_budget(entries.clone(), &cache, 2_025, Some(5));
+    assert_eq!(selected.len(), 4);
+    assert_eq!(cycles, 2_020);
+
+    // the count ceiling is reached before the cycles limit
+    let (selected, cycles) =
+        BlockAssembler::apply_cycles_budget(entries.clone(), &cache, 10_000, Some(3));
+    assert_eq!(selected.len(), 3);
+    assert_eq!(cycles, 1_020);
+
+    let (selected, cycles) = BlockAssembler::apply_cycles_budget(entries, &cache, 10_000, None);
+    assert_eq!(selected.len(), 6);
+    assert_eq!(cycles, 3_030);
+}
+
+#[test]
+fn test_apply_cycles_budget_defers_packages() {
+    let tx = |i: u8| {
+        TransactionBuilder::default()
+            .witness(Bytes::from(vec![i]).pack())
+            .build()
+    };
+    let expensive = tx(0);
+    // spends the output of the expensive tx
+    let child = TransactionBuilder::default()
+        .input(packed::CellInput::new(
+            packed::OutPoint::new(expensive.hash(), 0),
+            0,
+        ))
+        .build();
+    let entries = vec![
+        TxEntry::dummy_resolve(tx(1), 100, Capacity::shannons(100), 100),
+        TxEntry::dummy_resolve(expensive, 1_000, Capacity::shannons(100), 100),
+        TxEntry::dummy_resolve(child, 100, Capacity::shannons(100), 100),
+        TxEntry::dummy_resolve(tx(2), 100, Capacity::shannons(100), 100),
+    ];
+    let cache = init_cache();
+
+    // the tx after the expensive one still fits, while the child of it is deferred along
+    let (selected, cycles) =
+        BlockAssembler::apply_cycles_budget(entries.clone(), &cache, 500, None);
+    let expected: Vec<_> = [&entries[0], &entries[3]]
+        .iter()
+        .map(|entry| entry.transaction().hash())
+        .collect();
+    let selected: Vec<_> = selected
+        .iter()
+        .map(|entry| entry.transaction().hash())
+        .collect();
+    assert_eq!(selected, expected);
+    assert_eq!(cycles, 200);
+
+    // the count ceiling is reached by the selected txs only
+    let (selected, cycles) = BlockAssembler::apply_cycles_budget(entries, &cache, 500, Some(2));
+    assert_eq!(selected.len(), 2);
+    assert_eq!(cycles, 200);
+}
+
+#[test]
+fn test_check_cellbase_outputs() {
+    let target_lock = packed::Script::default();
+    let output = |capacity: Capacity| {
+        packed::CellOutput::new_builder()
+            .capacity(capacity.pack())
+            .lock(target_lock.clone())
+            .build()
+    };
+    let data = Bytes::from(vec![0u8; 10]);
+    // the lock is empty, an output occupies 8 bytes for the capacity field, 32 bytes for
+    // the lock code hash, 1 byte for the lock hash type, and its data
+    let occupied = Capacity::bytes(8 + 32 + 1).unwrap();
+    let occupied_with_data = occupied
+        .safe_add(Capacity::bytes(data.len()).unwrap())
+        .unwrap();
+    let shannon = Capacity::shannons(1);
+    let reward = Capacity::bytes(1000).unwrap();
+    let extra = reward.safe_sub(occupied).unwrap();
+
+    let check = |outputs: &[(packed::CellOutput, Bytes)]| {
+        BlockAssembler::check_cellbase_outputs(outputs, &target_lock, reward)
+    };
+    let error_of = |outputs: &[(packed::CellOutput, Bytes)]| {
+        check(outputs)
+            .unwrap_err()
+            .downcast_ref::<BlockAssemblerError>()
+            .cloned()
+            .unwrap()
+    };
+
+    // the reward is split across two outputs of the target
+    let outputs = [
+        (output(occupied), Bytes::new()),
+        (output(extra), Bytes::new()),
+    ];
+    assert!(check(&outputs).is_ok());
+
+    // the second output pays another lock
+    let other_lock = packed::Script::new_builder().args(vec![1u8].pack()).build();
+    let outputs = [
+        (output(occupied), Bytes::new()),
+        (
+            output(extra).as_builder().lock(other_lock).build(),
+            Bytes::new(),
+        ),
+    ];
+    assert_eq!(
+        error_of(&outputs),
+        BlockAssemblerError::InvalidCellbaseRewardTarget(1)
+    );
+
+    // the first output lacks one shannon
+    let outputs = [
+        (output(occupied.safe_sub(shannon).unwrap()), Bytes::new()),
+        (output(extra.safe_add(shannon).unwrap()), Bytes::new()),
+    ];
+    assert_eq!(
+        error_of(&outputs),
+        BlockAssemblerError::InsufficientCellbaseOutputCapacity(0)
+    );
+
+    // the second output lacks one shannon for its data
+    let outputs = [
+        (
+            output(
+                reward
+                    .safe_sub(occupied_with_data)
+                    .unwrap()
+                    .safe_add(shannon)
+                    .unwrap(),
+            ),
+            Bytes::new(),
+        ),
+        (
+            output(occupied_with_data.safe_sub(shannon).unwrap()),
+            data.clone(),
+        ),
+    ];
+    assert_eq!(
+        error_of(&outputs),
+        BlockAssemblerError::InsufficientCellbaseOutputCapacity(1)
+    );
+
+    // the outputs hold exactly their occupied capacity and pay the reward
+    let outputs = [
+        (
+            output(reward.safe_sub(occupied_with_data).unwrap()),
+            Bytes::new(),
+        ),
+        (output(occupied_with_data), data),
+    ];
+    assert!(check(&outputs).is_ok());
+
+    // the outputs pay one shannon more or less than the reward
+    for capacity in [
+        extra.safe_add(shannon).unwrap(),
+        extra.safe_sub(shannon).unwrap(),
+    ] {
+        let outputs = [
+            (output(occupied), Bytes::new()),
+            (output(capacity), Bytes::new()),
+        ];
+        assert_eq!(
+            error_of(&outputs),
+            BlockAssemblerError::MismatchedCellbaseReward
+        );
+    }
+}
+
+// The miner lock has args `[0]`, the additional locks have args `[1]`, `[2]`, ...
+fn reward_locks_config(policy: RewardLockPolicy, weights: &[u32]) -> BlockAssemblerConfig {
+    BlockAssemblerConfig {
+        code_hash: H256::default(),
+        args: JsonBytes::from_vec(vec![0]),
+        hash_type: ScriptHashType::Data,
+        message: Default::default(),
+        use_binary_version_as_message_prefix: false,
+        binary_version: Default::default(),
+        update_interval_millis: 800,
+        new_transactions_threshold: 0,
+        notify: vec![],
+        notify_scripts: vec![],
+        notify_timeout_millis: 800,
+        assembly_deadline_millis: None,
+        max_block_transactions: None,
+        reward_locks: weights
+            .iter()
+            .enumerate()
+            .map(|(index, weight)| RewardLockConfig {
+                code_hash: H256::default(),
+                args: JsonBytes::from_vec(vec![index as u8 + 1]),
+                hash_type: ScriptHashType::Data,
+                weight: *weight,
+            })
+            .collect(),
+        reward_lock_policy: policy,
+    }
+}
+
+fn reward_lock_index(config: &BlockAssemblerConfig, number: BlockNumber) -> usize {
+    BlockAssembler::reward_lock(config, number)
+        .args()
+        .raw_data()[0] as usize
+}
+
+#[test]
+fn test_single_reward_lock() {
+    let config = reward_locks_config(RewardLockPolicy::WeightedRandom, &[]);
+    assert!((1..100).all(|number| reward_lock_index(&config, number) == 0));
+}
+
+#[test]
+fn test_round_robin_reward_locks() {
+    let config = reward_locks_config(RewardLockPolicy::RoundRobin, &[1, 1]);
+    let indexes: Vec<usize> = (1..=6)
+        .map(|number| reward_lock_index(&config, number))
+        .collect();
+    assert_eq!(indexes, vec![1, 2, 0, 1, 2, 0]);
+}
+
+#[test]
+fn test_weighted_random_reward_locks() {
+    let config = reward_locks_config(RewardLockPolicy::WeightedRandom, &[3, 0]);
+    let mut counts = [0usize; 3];
+    for number in 1..=4000 {
+        let index = reward_lock_index(&config, number);
+        // every template of the same block pays the same lock
+        assert_eq!(index, reward_lock_index(&config, number));
+        counts[index] += 1;
+    }
+    assert_eq!(counts[2], 0);
+    assert!((800..1200).contains(&counts[0]), "{counts:?}");
+    assert!((2800..3200).contains(&counts[1]), "{counts:?}");
+}
+
+#[test]
+fn test_queued_messages_new_transactions_threshold() {
+    let mut queue = QueuedMessages::new(3);
+    assert!(!queue.push(BlockAssemblerMessage::Pending));
+    assert!(!queue.push(BlockAssemblerMessage::Uncle));
+    assert!(!queue.push(BlockAssemblerMessage::Proposed));
+    // duplicated messages are coalesced, but every new transaction is counted
+    assert!(queue.push(BlockAssemblerMessage::Pending));
+
+    // a new tip drops the queued messages and restarts the count
+    queue.clear();
+    assert!(queue.is_empty());
+    assert!(!queue.push(BlockAssemblerMessage::Pending));
+    assert!(!queue.push(BlockAssemblerMessage::Pending));
+    assert!(queue.push(BlockAssemblerMessage::Pending));
+
+    // only the update interval flushes the queue when the threshold is disabled
+    let mut queue = QueuedMessages::new(0);
+    assert!((0..100).all(|_| !queue.push(BlockAssemblerMessage::Pending)));
+}
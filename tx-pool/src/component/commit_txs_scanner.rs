@@ -5,6 +5,7 @@ use ckb_types::{core::Cycle, packed::ProposalShortId};
 use ckb_util::LinkedHashMap;
 use multi_index_map::MultiIndexMap;
 use std::collections::HashSet;
+use std::time::Instant;
 
 // A template data struct used to store modified entries when package txs
 #[derive(MultiIndexMap, Clone)]
@@ -59,6 +60,8 @@ pub struct CommitTxsScanner<'a> {
     fetched_txs: HashSet<ProposalShortId>,
     // Keep track of entries that failed inclusion, to avoid duplicate work
     failed_txs: HashSet<ProposalShortId>,
+    // Stop selecting when the deadline is reached, and return the txs packaged so far
+    deadline: Option<Instant>,
 }
 
 impl<'a> CommitTxsScanner<'a> {
@@ -69,9 +72,17 @@ impl<'a> CommitTxsScanner<'a> {
             modified_entries: MultiIndexModifiedTxMap::default(),
             fetched_txs: HashSet::default(),
             failed_txs: HashSet::default(),
+            deadline: None,
         }
     }
 
+    /// Stop selecting at `deadline`, the txs packaged so far are still a valid commitment,
+    /// since a tx is always packaged together with its ancestors.
+    pub fn with_deadline(mut self, deadline: Option<Instant>) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
     /// find txs to commit, return TxEntry vector, total_size and total_cycles.
     pub fn txs_to_commit(
         mut self,
@@ -84,6 +95,14 @@ impl<'a> CommitTxsScanner<'a> {
 
         let mut iter = self.pool_map.sorted_proposed_iter().peekable();
         loop {
+            if matches!(self.deadline, Some(deadline) if Instant::now() >= deadline) {
+                ckb_logger::debug!(
+                    "[get_block_template] assembly deadline reached, {} txs packaged",
+                    self.entries.len()
+                );
+                break;
+            }
+
             let mut using_modified = false;
 
             if let Some(entry) = iter.peek() {
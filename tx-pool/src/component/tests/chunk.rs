@@ -1,20 +1,39 @@
 use crate::component::tests::util::build_tx;
-use crate::component::verify_queue::{Entry, VerifyQueue};
+use crate::component::verify_queue::{Entry, VerifyQueue, VerifyStatus};
 use ckb_network::SessionId;
-use ckb_types::core::TransactionBuilder;
+use ckb_types::core::{
+    cell::ResolvedTransaction, tx_pool::Reject, Capacity, TransactionBuilder, TransactionView,
+};
 use ckb_types::prelude::Pack;
 use ckb_types::H256;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::select;
 use tokio::sync::watch;
 use tokio::time::sleep;
 
 const MAX_TX_VERIFY_CYCLES: u64 = 70_000_000;
+
+// pop the best `Fresh` tx and remove it, as a verify worker does after processing it
+fn pop_processed(queue: &mut VerifyQueue, only_small_cycle: bool) -> Option<Entry> {
+    let entry = queue.pop_best_fresh(only_small_cycle)?;
+    queue.remove_tx(&entry.tx.proposal_short_id())
+}
+
+// the tx resolved on enqueue with its fee
+fn resolved(tx: &TransactionView, shannons: u64) -> Option<(Arc<ResolvedTransaction>, Capacity)> {
+    let rtx = ResolvedTransaction::dummy_resolve(tx.clone());
+    Some((Arc::new(rtx), Capacity::shannons(shannons)))
+}
+
 #[tokio::test]
 async fn verify_queue_basic() {
     let tx = TransactionBuilder::default().build();
     let entry = Entry {
         tx: tx.clone(),
         remote: None,
+        resolved: None,
     };
     let tx2 = build_tx(vec![(&tx.hash(), 0)], 1);
 
@@ -42,13 +61,13 @@ async fn verify_queue_basic() {
 
     assert!(!queue.add_tx(tx.clone(), None).unwrap());
 
-    assert_eq!(queue.pop_front(false).as_ref(), Some(&entry));
+    assert_eq!(pop_processed(&mut queue, false).as_ref(), Some(&entry));
     assert!(!queue.contains_key(&id));
 
     assert!(queue.add_tx(tx.clone(), None).unwrap());
     sleep(std::time::Duration::from_millis(100)).await;
 
-    assert_eq!(queue.pop_front(false).as_ref(), Some(&entry));
+    assert_eq!(pop_processed(&mut queue, false).as_ref(), Some(&entry));
 
     assert!(queue.add_tx(tx.clone(), None).unwrap());
     sleep(std::time::Duration::from_millis(100)).await;
@@ -60,11 +79,11 @@ async fn verify_queue_basic() {
     let counts = count.await.unwrap();
     assert_eq!(counts, 4);
 
-    let cur = queue.pop_front(false);
+    let cur = pop_processed(&mut queue, false);
     assert_eq!(cur.unwrap().tx, tx);
 
     assert!(!queue.is_empty());
-    let cur = queue.pop_front(false);
+    let cur = pop_processed(&mut queue, false);
     assert_eq!(cur.unwrap().tx, tx2);
 
     assert!(queue.is_empty());
@@ -122,24 +141,24 @@ async fn test_verify_different_cycles() {
     assert_eq!(queue.total_tx_size(), tx_size_sum);
 
     // tx0 should be the first tx in the queue
-    let cur = queue.pop_front(true);
+    let cur = pop_processed(&mut queue, true);
     assert_eq!(cur.unwrap().tx, tx0);
 
-    let cur = queue.pop_front(true);
+    let cur = pop_processed(&mut queue, true);
     assert_eq!(cur.unwrap().tx, tx2);
 
-    let cur = queue.pop_front(true);
+    let cur = pop_processed(&mut queue, true);
     assert_eq!(cur.unwrap().tx, tx3);
 
     // now there is no small cycle tx
-    let cur = queue.pop_front(true);
+    let cur = pop_processed(&mut queue, true);
     assert!(cur.is_none());
 
     // pop the tx with the large cycle
-    let cur = queue.pop_front(false);
+    let cur = pop_processed(&mut queue, false);
     assert_eq!(cur.unwrap().tx, tx1);
 
-    let cur = queue.pop_front(false);
+    let cur = pop_processed(&mut queue, false);
     assert!(cur.is_none());
 
     exit_tx.send(()).unwrap();
@@ -147,3 +166,273 @@ async fn test_verify_different_cycles() {
     assert_eq!(counts, 4);
     assert_eq!(queue.total_tx_size(), 0);
 }
+
+#[test]
+fn test_verify_queue_pop_best_fresh() {
+    let mut queue = VerifyQueue::new(MAX_TX_VERIFY_CYCLES);
+    let txs: Vec<_> = (0..4u8)
+        .map(|i| build_tx(vec![(&H256([i; 32]).pack(), 0)], 1))
+        .collect();
+
+    // all txs have the same size, so the fee decides the fee rate
+    assert!(
+        queue
+            .add_resolved_tx(txs[0].clone(), None, resolved(&txs[0], 1000))
+            .unwrap()
+            .0
+    );
+    assert!(queue.add_tx(txs[1].clone(), None).unwrap());
+    assert!(
+        queue
+            .add_resolved_tx(txs[2].clone(), None, resolved(&txs[2], 5000))
+            .unwrap()
+            .0
+    );
+    assert!(
+        queue
+            .add_resolved_tx(txs[3].clone(), None, resolved(&txs[3], 3000))
+            .unwrap()
+            .0
+    );
+
+    for (expected, fee) in [
+        (&txs[2], Some(5000)),
+        (&txs[3], Some(3000)),
+        (&txs[0], Some(1000)),
+        (&txs[1], None),
+    ] {
+        let entry = queue.pop_best_fresh(false).unwrap();
+        assert_eq!(&entry.tx, expected);
+        assert_eq!(entry.resolved.map(|(_rtx, fee)| fee.as_u64()), fee);
+        assert!(queue.contains_key(&expected.proposal_short_id()));
+    }
+    assert!(queue.pop_best_fresh(false).is_none());
+    assert!(!queue.has_fresh());
+    assert_eq!(queue.status_counts(), (0, 4, 0));
+}
+
+#[test]
+fn test_verify_queue_status_transition() {
+    let mut queue = VerifyQueue::new(MAX_TX_VERIFY_CYCLES);
+    let tx0 = build_tx(vec![(&H256([0; 32]).pack(), 0)], 1);
+    let tx1 = build_tx(vec![(&H256([1; 32]).pack(), 0)], 1);
+    let id0 = tx0.proposal_short_id();
+    assert!(queue.add_tx(tx0.clone(), None).unwrap());
+    assert!(queue.add_tx(tx1.clone(), None).unwrap());
+    let id1 = tx1.proposal_short_id();
+    assert_eq!(queue.ids_by_status(VerifyStatus::Fresh).len(), 2);
+
+    // Fresh -> Verifying, the entry stays in the queue
+    assert_eq!(queue.pop_best_fresh(false).unwrap().tx, tx0);
+    assert!(queue.contains_key(&id0));
+    assert_eq!(queue.ids_by_status(VerifyStatus::Fresh), vec![id1.clone()]);
+    assert_eq!(
+        queue.ids_by_status(VerifyStatus::Verifying),
+        vec![id0.clone()]
+    );
+
+    // a verifying entry is not picked again
+    assert_eq!(queue.pop_best_fresh(false).unwrap().tx, tx1);
+    assert!(queue.pop_best_fresh(false).is_none());
+    assert!(!queue.has_fresh());
+
+    // Verifying -> Fresh on a retryable failure
+    assert!(queue.requeue_fresh(&id1));
+    assert!(queue.has_fresh());
+    assert_eq!(queue.ids_by_status(VerifyStatus::Fresh), vec![id1]);
+
+    // Verifying -> Completed
+    assert!(queue.set_completed(&id0));
+    assert!(queue.ids_by_status(VerifyStatus::Verifying).is_empty());
+    assert_eq!(
+        queue.ids_by_status(VerifyStatus::Completed),
+        vec![id0.clone()]
+    );
+
+    queue.remove_tx(&id0);
+    assert!(queue.ids_by_status(VerifyStatus::Completed).is_empty());
+    assert!(!queue.set_completed(&id0));
+    assert_eq!(queue.len(), 1);
+}
+
+#[test]
+fn test_verify_queue_status_counts() {
+    let mut queue = VerifyQueue::new(MAX_TX_VERIFY_CYCLES);
+    assert_eq!(queue.status_counts(), (0, 0, 0));
+    let txs: Vec<_> = (0..4u8)
+        .map(|i| build_tx(vec![(&H256([i; 32]).pack(), 0)], 1))
+        .collect();
+    for tx in &txs {
+        assert!(queue.add_tx(tx.clone(), None).unwrap());
+    }
+    let ids = |txs: &[TransactionView]| -> HashSet<_> {
+        txs.iter().map(|tx| tx.proposal_short_id()).collect()
+    };
+    let ids_by_status = |queue: &VerifyQueue, status| -> HashSet<_> {
+        queue.ids_by_status(status).into_iter().collect()
+    };
+    assert_eq!(queue.status_counts(), (4, 0, 0));
+    assert_eq!(ids_by_status(&queue, VerifyStatus::Fresh), ids(&txs));
+
+    assert_eq!(queue.pop_best_fresh(false).unwrap().tx, txs[0]);
+    assert_eq!(queue.pop_best_fresh(false).unwrap().tx, txs[1]);
+    assert!(queue.set_completed(&txs[0].proposal_short_id()));
+    assert_eq!(queue.status_counts(), (2, 1, 1));
+    assert_eq!(ids_by_status(&queue, VerifyStatus::Fresh), ids(&txs[2..]));
+    assert_eq!(
+        ids_by_status(&queue, VerifyStatus::Verifying),
+        ids(&txs[1..2])
+    );
+    assert_eq!(
+        ids_by_status(&queue, VerifyStatus::Completed),
+        ids(&txs[..1])
+    );
+
+    assert!(queue.requeue_fresh(&txs[1].proposal_short_id()));
+    queue.remove_tx(&txs[0].proposal_short_id());
+    assert_eq!(queue.status_counts(), (3, 0, 0));
+    assert!(ids_by_status(&queue, VerifyStatus::Completed).is_empty());
+}
+
+#[test]
+fn test_verify_queue_evict_expired() {
+    let faketime_guard = ckb_systemtime::faketime();
+    faketime_guard.set_faketime(10_000);
+
+    let mut queue = VerifyQueue::new(MAX_TX_VERIFY_CYCLES);
+    let txs: Vec<_> = (0..4u8)
+        .map(|i| build_tx(vec![(&H256([i; 32]).pack(), 0)], 1))
+        .collect();
+    for (i, tx) in txs.iter().enumerate() {
+        faketime_guard.set_faketime(10_000 + i as u64 * 1_000);
+        assert!(queue.add_tx(tx.clone(), None).unwrap());
+    }
+    // a verifying tx is evicted as well
+    assert_eq!(queue.pop_best_fresh(false).unwrap().tx, txs[0]);
+
+    // txs[0] and txs[1] are 2s old or older
+    faketime_guard.set_faketime(13_000);
+    let evicted: Vec<_> = queue
+        .evict_expired(Duration::from_secs(2))
+        .into_iter()
+        .map(|entry| entry.tx)
+        .collect();
+    assert_eq!(evicted, txs[..2].to_vec());
+    assert_eq!(queue.len(), 2);
+    assert!(queue.contains_key(&txs[2].proposal_short_id()));
+    assert!(queue.contains_key(&txs[3].proposal_short_id()));
+    assert_eq!(
+        queue.total_tx_size(),
+        txs[2..]
+            .iter()
+            .map(|tx| tx.data().serialized_size_in_block())
+            .sum::<usize>()
+    );
+
+    assert!(queue.evict_expired(Duration::from_secs(2)).is_empty());
+    faketime_guard.set_faketime(20_000);
+    assert_eq!(queue.evict_expired(Duration::from_secs(2)).len(), 2);
+    assert!(queue.is_empty());
+}
+
+#[test]
+fn test_verify_queue_remove_txs() {
+    let mut queue = VerifyQueue::new(MAX_TX_VERIFY_CYCLES);
+    let txs: Vec<_> = (0..4u8)
+        .map(|i| build_tx(vec![(&H256([i; 32]).pack(), 0)], 1))
+        .collect();
+    for tx in &txs[..3] {
+        assert!(queue.add_tx(tx.clone(), None).unwrap());
+    }
+    let remove_txs = |queue: &mut VerifyQueue, txs: &[TransactionView]| -> Vec<_> {
+        queue
+            .remove_txs(txs.iter().map(|tx| tx.proposal_short_id()))
+            .into_iter()
+            .map(|entry| entry.tx)
+            .collect()
+    };
+
+    // absent
+    assert!(remove_txs(&mut queue, &txs[3..]).is_empty());
+    assert!(queue.remove_tx(&txs[3].proposal_short_id()).is_none());
+    assert_eq!(queue.len(), 3);
+
+    // present
+    assert_eq!(remove_txs(&mut queue, &txs[..1]), txs[..1].to_vec());
+    assert_eq!(queue.len(), 2);
+
+    // mixed, the removed entries keep the order of the ids
+    assert_eq!(
+        remove_txs(
+            &mut queue,
+            &[
+                txs[2].clone(),
+                txs[0].clone(),
+                txs[3].clone(),
+                txs[1].clone()
+            ]
+        ),
+        vec![txs[2].clone(), txs[1].clone()]
+    );
+    assert!(queue.is_empty());
+    assert_eq!(queue.total_tx_size(), 0);
+}
+
+#[test]
+fn test_verify_queue_full_eviction() {
+    let txs: Vec<_> = (0..6u8)
+        .map(|i| build_tx(vec![(&H256([i; 32]).pack(), 0)], 1))
+        .collect();
+    // all txs have the same size, the queue holds 3 of them
+    let tx_size = txs[0].data().serialized_size_in_block();
+    let new_queue = || VerifyQueue::with_max_tx_size(MAX_TX_VERIFY_CYCLES, tx_size * 3 + 1);
+    let remote = Some((1001, SessionId::default()));
+
+    // a full queue of remote txs: a local tx evicts the lowest fee rate one
+    let mut queue = new_queue();
+    for (tx, shannons) in txs[..3].iter().zip([3000, 1000, 2000]) {
+        assert!(
+            queue
+                .add_resolved_tx(tx.clone(), remote, resolved(tx, shannons))
+                .unwrap()
+                .0
+        );
+    }
+    let (added, evicted) = queue.add_resolved_tx(txs[3].clone(), None, None).unwrap();
+    assert!(added);
+    assert_eq!(evicted.len(), 1);
+    assert_eq!(evicted[0].tx, txs[1]);
+    assert!(!queue.contains_key(&txs[1].proposal_short_id()));
+    assert_eq!(queue.total_tx_size(), tx_size * 3);
+
+    // while a remote tx is rejected
+    let mut queue = new_queue();
+    for tx in &txs[..3] {
+        assert!(queue.add_tx(tx.clone(), remote).unwrap());
+    }
+    assert!(matches!(
+        queue.add_tx(txs[3].clone(), remote),
+        Err(Reject::Full(_))
+    ));
+    assert_eq!(queue.len(), 3);
+
+    // a mixed queue: local txs and txs being verified are never evicted
+    let mut queue = new_queue();
+    assert!(queue.add_tx(txs[0].clone(), remote).unwrap());
+    assert!(queue.add_tx(txs[1].clone(), None).unwrap());
+    assert!(queue.add_tx(txs[2].clone(), remote).unwrap());
+    assert_eq!(queue.pop_best_fresh(false).unwrap().tx, txs[0]);
+
+    let (added, evicted) = queue.add_resolved_tx(txs[3].clone(), None, None).unwrap();
+    assert!(added);
+    assert_eq!(evicted.len(), 1);
+    assert_eq!(evicted[0].tx, txs[2]);
+
+    // only a verifying remote tx and local txs are left
+    assert!(matches!(
+        queue.add_resolved_tx(txs[4].clone(), None, None),
+        Err(Reject::Full(_))
+    ));
+    assert!(queue.contains_key(&txs[0].proposal_short_id()));
+    assert_eq!(queue.len(), 3);
+}
@@ -6,20 +6,20 @@ use crate::component::tests::util::{
 use ckb_types::core::{capacity_bytes, ScriptHashType};
 use ckb_types::packed::{CellOutputBuilder, ScriptBuilder};
 use ckb_types::H256;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
-use crate::component::{entry::TxEntry, pool_map::PoolMap};
+use crate::component::{commit_txs_scanner::CommitTxsScanner, entry::TxEntry, pool_map::PoolMap};
 use ckb_types::{
     bytes::Bytes,
     core::{
         cell::{get_related_dep_out_points, CellMeta, ResolvedTransaction},
-        Capacity, DepType, TransactionBuilder, TransactionView,
+        Capacity, Cycle, DepType, TransactionBuilder, TransactionView,
     },
     h256,
     packed::{Byte32, CellDep, CellInput, CellOutput, OutPoint},
     prelude::*,
 };
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 fn dummy_resolve<F: Fn(&OutPoint) -> Option<Bytes>>(
@@ -798,3 +798,49 @@ fn test_pool_map_bench() {
     );
     assert!(diff < expect_diff_range);
 }
+
+#[test]
+fn test_txs_to_commit_with_deadline() {
+    let mut pool = PoolMap::new(DEFAULT_MAX_ANCESTORS_COUNT);
+    let mut parents = HashMap::new();
+    for i in 0..5000u32 {
+        let parent = build_tx(vec![(&Byte32::zero(), i)], 1);
+        let child = build_tx(vec![(&parent.hash(), 0)], 1);
+        parents.insert(child.hash(), parent.hash());
+        for (tx, fee) in [(parent, 100), (child, 100 + u64::from(i))] {
+            pool.add_proposed(TxEntry::dummy_resolve(
+                tx,
+                MOCK_CYCLES,
+                Capacity::shannons(fee),
+                100,
+            ))
+            .unwrap();
+        }
+    }
+
+    let (entries, _size, _cycles) =
+        CommitTxsScanner::new(&pool).txs_to_commit(usize::MAX, Cycle::MAX);
+    assert_eq!(entries.len(), 10000);
+
+    // the deadline is already reached
+    let (entries, size, cycles) = CommitTxsScanner::new(&pool)
+        .with_deadline(Some(Instant::now()))
+        .txs_to_commit(usize::MAX, Cycle::MAX);
+    assert!(entries.is_empty());
+    assert_eq!((size, cycles), (0, 0));
+
+    let (entries, size, _cycles) = CommitTxsScanner::new(&pool)
+        .with_deadline(Some(Instant::now() + Duration::from_millis(1)))
+        .txs_to_commit(usize::MAX, Cycle::MAX);
+    assert!(entries.len() <= 10000);
+    assert_eq!(size, entries.len() * 100);
+    // the partial result is still a valid commitment, every parent is packaged before its child
+    let mut packaged = HashSet::new();
+    for entry in entries {
+        let hash = entry.transaction().hash();
+        if let Some(parent) = parents.get(&hash) {
+            assert!(packaged.contains(parent));
+        }
+        packaged.insert(hash);
+    }
+}
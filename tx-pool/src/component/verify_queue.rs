@@ -2,27 +2,38 @@
 #![allow(missing_docs)]
 extern crate rustc_hash;
 extern crate slab;
+use ckb_chain_spec::consensus::MAX_BLOCK_INTERVAL;
 use ckb_logger::error;
 use ckb_network::PeerIndex;
 use ckb_systemtime::unix_time_as_millis;
 use ckb_types::{
-    core::{tx_pool::Reject, Cycle, TransactionView},
+    core::{
+        cell::ResolvedTransaction,
+        tx_pool::{get_transaction_weight, Reject},
+        Capacity, Cycle, FeeRate, TransactionView,
+    },
     packed::ProposalShortId,
 };
 use ckb_util::shrink_to_fit;
 use multi_index_map::MultiIndexMap;
+use std::cmp::Reverse;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Notify;
 
 // 256mb for total_tx_size limit, default max_tx_pool_size is 180mb
 const DEFAULT_MAX_VERIFY_QUEUE_TX_SIZE: usize = 256_000_000;
 const SHRINK_THRESHOLD: usize = 100;
+/// The txs waiting in the queue longer than this are evicted, the same as the orphan pool
+pub(crate) const VERIFY_QUEUE_EXPIRE_TIME: Duration = Duration::from_secs(100 * MAX_BLOCK_INTERVAL);
 
 /// The verify queue Entry to verify.
 #[derive(Debug, Clone, Eq)]
 pub struct Entry {
     pub(crate) tx: TransactionView,
     pub(crate) remote: Option<(Cycle, PeerIndex)>,
+    /// The tx resolved on enqueue and its fee, `None` if it couldn't be resolved then
+    pub(crate) resolved: Option<(Arc<ResolvedTransaction>, Capacity)>,
 }
 
 impl PartialEq for Entry {
@@ -31,14 +42,24 @@ impl PartialEq for Entry {
     }
 }
 
+/// The verification progress of a queued tx.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum VerifyStatus {
+    /// Waiting for a worker
+    Fresh,
+    /// Taken by a worker
+    Verifying,
+    /// Verified, waiting to be removed
+    Completed,
+}
+
 #[derive(MultiIndexMap, Clone)]
 struct VerifyEntry {
     /// The transaction id
     #[multi_index(hashed_unique)]
     id: ProposalShortId,
     /// The unix timestamp when entering the Txpool, unit: Millisecond
-    /// This field is used to sort the txs in the queue
-    /// We may add more other sort keys in the future
+    /// This field is used to evict the expired txs
     #[multi_index(ordered_non_unique)]
     added_time: u64,
 
@@ -46,6 +67,14 @@ struct VerifyEntry {
     #[multi_index(hashed_non_unique)]
     is_large_cycle: bool,
 
+    /// The declared fee rate and the insertion sequence, the earlier tx goes first on a tie.
+    /// A tx without a declared fee has a zero fee rate
+    #[multi_index(ordered_non_unique)]
+    fee_rate: (FeeRate, Reverse<u64>),
+
+    #[multi_index(hashed_non_unique)]
+    status: VerifyStatus,
+
     /// other sort key
     inner: Entry,
 }
@@ -60,6 +89,10 @@ pub(crate) struct VerifyQueue {
     total_tx_size: usize,
     /// large cycle threshold, from `pool_config.max_tx_verify_cycles`
     large_cycle_threshold: u64,
+    /// the limit of total tx size
+    max_tx_size: usize,
+    /// the insertion sequence of the next tx, breaks the ties of fee rates
+    next_sequence: u64,
 }
 
 impl VerifyQueue {
@@ -70,6 +103,16 @@ impl VerifyQueue {
             ready_rx: Arc::new(Notify::new()),
             total_tx_size: 0,
             large_cycle_threshold,
+            max_tx_size: DEFAULT_MAX_VERIFY_QUEUE_TX_SIZE,
+            next_sequence: 0,
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn with_max_tx_size(large_cycle_threshold: u64, max_tx_size: usize) -> Self {
+        VerifyQueue {
+            max_tx_size,
+            ..Self::new(large_cycle_threshold)
         }
     }
 
@@ -89,7 +132,7 @@ impl VerifyQueue {
 
     /// Returns true if the queue is full.
     pub fn is_full(&self, add_tx_size: usize) -> bool {
-        add_tx_size >= DEFAULT_MAX_VERIFY_QUEUE_TX_SIZE - self.total_tx_size
+        add_tx_size >= self.max_tx_size.saturating_sub(self.total_tx_size)
     }
 
     /// Returns true if the queue contains a tx with the specified id.
@@ -109,6 +152,13 @@ impl VerifyQueue {
 
     /// Remove a tx from the queue
     pub fn remove_tx(&mut self, id: &ProposalShortId) -> Option<Entry> {
+        let entry = self.take_tx(id);
+        self.shrink_to_fit();
+        entry
+    }
+
+    // remove a tx without shrinking the queue
+    fn take_tx(&mut self, id: &ProposalShortId) -> Option<Entry> {
         self.inner.remove_by_id(id).map(|e| {
             let tx_size = e.inner.tx.data().serialized_size_in_block();
             self.total_tx_size = self.total_tx_size.checked_sub(tx_size).unwrap_or_else(|| {
@@ -118,35 +168,92 @@ impl VerifyQueue {
                 );
                 0
             });
-            self.shrink_to_fit();
             e.inner
         })
     }
 
-    /// Remove multiple txs from the queue
-    pub fn remove_txs(&mut self, ids: impl Iterator<Item = ProposalShortId>) {
-        for id in ids {
-            self.remove_tx(&id);
-        }
+    /// Remove and return the txs which entered the queue `max_age` ago or earlier, whatever
+    /// their status is
+    pub fn evict_expired(&mut self, max_age: Duration) -> Vec<Entry> {
+        let deadline = unix_time_as_millis().saturating_sub(max_age.as_millis() as u64);
+        let expired: Vec<ProposalShortId> = self
+            .inner
+            .iter_by_added_time()
+            .take_while(|entry| entry.added_time <= deadline)
+            .map(|entry| entry.id.clone())
+            .collect();
+        let evicted = expired.iter().filter_map(|id| self.take_tx(id)).collect();
+        self.shrink_to_fit();
+        evicted
     }
 
-    /// Returns the first entry in the queue and remove it
-    pub fn pop_front(&mut self, only_small_cycle: bool) -> Option<Entry> {
-        if let Some(short_id) = self.peek(only_small_cycle) {
-            self.remove_tx(&short_id)
-        } else {
-            None
-        }
+    /// Remove multiple txs from the queue, returns the entries of those present
+    pub fn remove_txs(&mut self, ids: impl Iterator<Item = ProposalShortId>) -> Vec<Entry> {
+        let removed = ids.filter_map(|id| self.take_tx(&id)).collect();
+        self.shrink_to_fit();
+        removed
+    }
+
+    /// Returns the `Fresh` entry with the highest fee rate in the queue and mark it as
+    /// `Verifying`, it stays in the queue until it's removed, marked as `Completed` or requeued.
+    /// Txs with the same fee rate are returned in the order they were added.
+    pub fn pop_best_fresh(&mut self, only_small_cycle: bool) -> Option<Entry> {
+        let short_id = self
+            .inner
+            .iter_by_fee_rate()
+            .rev()
+            .filter(|entry| entry.status == VerifyStatus::Fresh)
+            .find(|entry| !only_small_cycle || !entry.is_large_cycle)
+            .map(|entry| entry.id.clone())?;
+        self.set_status(&short_id, VerifyStatus::Verifying)
     }
 
-    /// Returns the first entry in the queue
-    pub fn peek(&self, only_small_cycle: bool) -> Option<ProposalShortId> {
-        if only_small_cycle {
-            self.inner.iter_by_added_time().find(|e| !e.is_large_cycle)
-        } else {
-            self.inner.iter_by_added_time().next()
+    /// Returns true if any tx in the queue is waiting for a worker
+    pub fn has_fresh(&self) -> bool {
+        !self.inner.get_by_status(&VerifyStatus::Fresh).is_empty()
+    }
+
+    /// Mark a tx as `Completed`, returns false if it's not in the queue
+    pub fn set_completed(&mut self, id: &ProposalShortId) -> bool {
+        self.set_status(id, VerifyStatus::Completed).is_some()
+    }
+
+    /// Put a tx back to `Fresh`, so it will be verified again, returns false if it's not in
+    /// the queue
+    pub fn requeue_fresh(&mut self, id: &ProposalShortId) -> bool {
+        let requeued = self.set_status(id, VerifyStatus::Fresh).is_some();
+        if requeued {
+            self.ready_rx.notify_one();
         }
-        .map(|entry| entry.inner.tx.proposal_short_id())
+        requeued
+    }
+
+    /// Returns the number of `Fresh`, `Verifying` and `Completed` entries, read from the status
+    /// index
+    pub fn status_counts(&self) -> (usize, usize, usize) {
+        let count = |status| self.inner.get_by_status(&status).len();
+        (
+            count(VerifyStatus::Fresh),
+            count(VerifyStatus::Verifying),
+            count(VerifyStatus::Completed),
+        )
+    }
+
+    /// Returns the ids of all entries in the `status`
+    #[cfg(test)]
+    pub fn ids_by_status(&self, status: VerifyStatus) -> Vec<ProposalShortId> {
+        self.inner
+            .get_by_status(&status)
+            .into_iter()
+            .map(|entry| entry.id.clone())
+            .collect()
+    }
+
+    // update the indexed status in place
+    fn set_status(&mut self, id: &ProposalShortId, status: VerifyStatus) -> Option<Entry> {
+        self.inner
+            .modify_by_id(id, |entry| entry.status = status)
+            .map(|entry| entry.inner.clone())
     }
 
     /// If the queue did not have this tx present, true is returned.
@@ -156,24 +263,65 @@ impl VerifyQueue {
         tx: TransactionView,
         remote: Option<(Cycle, PeerIndex)>,
     ) -> Result<bool, Reject> {
+        self.add_resolved_tx(tx, remote, None)
+            .map(|(added, _evicted)| added)
+    }
+
+    /// Same as `add_tx`, the fee of the `resolved` tx ranks it in `pop_best_fresh` and in the
+    /// eviction, and the remote txs evicted to make room are returned. The resolved tx is kept
+    /// in the entry, so the worker doesn't resolve it again.
+    ///
+    /// The fee rate is weighed by the size only, as the cycles of a local tx are unknown
+    /// before it's verified.
+    ///
+    /// When the queue is full, a remote tx is rejected, while a local tx evicts the `Fresh`
+    /// remote txs with the lowest fee rates until it fits. A local tx or a tx being verified
+    /// is never evicted, the local tx is rejected if evicting every candidate isn't enough.
+    pub fn add_resolved_tx(
+        &mut self,
+        tx: TransactionView,
+        remote: Option<(Cycle, PeerIndex)>,
+        resolved: Option<(Arc<ResolvedTransaction>, Capacity)>,
+    ) -> Result<(bool, Vec<Entry>), Reject> {
         if self.contains_key(&tx.proposal_short_id()) {
-            return Ok(false);
+            return Ok((false, Vec::new()));
         }
         let tx_size = tx.data().serialized_size_in_block();
         let is_large_cycle = remote
             .map(|(cycles, _)| cycles > self.large_cycle_threshold)
             .unwrap_or(false);
+        let mut evicted = Vec::new();
         if self.is_full(tx_size) {
-            return Err(Reject::Full(format!(
-                "verify_queue total_tx_size exceeded, failed to add tx: {:#x}",
-                tx.hash()
-            )));
+            let full = || {
+                Reject::Full(format!(
+                    "verify_queue total_tx_size exceeded, failed to add tx: {:#x}",
+                    tx.hash()
+                ))
+            };
+            if remote.is_some() {
+                return Err(full());
+            }
+            let victims = self.eviction_victims(tx_size).ok_or_else(full)?;
+            evicted = victims.iter().filter_map(|id| self.take_tx(id)).collect();
+            self.shrink_to_fit();
         }
+        let fee_rate = resolved
+            .as_ref()
+            .map(|(_rtx, fee)| FeeRate::calculate(*fee, get_transaction_weight(tx_size, 0)))
+            .unwrap_or_else(FeeRate::zero);
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
         self.inner.insert(VerifyEntry {
             id: tx.proposal_short_id(),
             added_time: unix_time_as_millis(),
-            inner: Entry { tx, remote },
+            inner: Entry {
+                tx,
+                remote,
+                resolved,
+            },
             is_large_cycle,
+            fee_rate: (fee_rate, Reverse(sequence)),
+            status: VerifyStatus::Fresh,
         });
         self.total_tx_size = self.total_tx_size.checked_add(tx_size).unwrap_or_else(|| {
             error!(
@@ -183,7 +331,30 @@ impl VerifyQueue {
             self.total_tx_size
         });
         self.ready_rx.notify_one();
-        Ok(true)
+        Ok((true, evicted))
+    }
+
+    // The fewest `Fresh` remote txs with the lowest fee rates to make room for `tx_size`,
+    // returns `None` if all of them are not enough.
+    fn eviction_victims(&self, tx_size: usize) -> Option<Vec<ProposalShortId>> {
+        let fits = |freed: usize| {
+            tx_size
+                < self
+                    .max_tx_size
+                    .saturating_sub(self.total_tx_size.saturating_sub(freed))
+        };
+        let mut freed = 0;
+        let mut victims = Vec::new();
+        for entry in self.inner.iter_by_fee_rate() {
+            if fits(freed) {
+                break;
+            }
+            if entry.status == VerifyStatus::Fresh && entry.inner.remote.is_some() {
+                freed += entry.inner.tx.data().serialized_size_in_block();
+                victims.push(entry.id.clone());
+            }
+        }
+        fits(freed).then_some(victims)
     }
 
     /// When OnlySmallCycleTx Worker is wakeup, but found the tx is large cycle tx, notify other workers.
@@ -25,6 +25,15 @@ pub enum BlockAssemblerError {
     /// BlockAssembler calculate overflow
     #[error("Overflow")]
     Overflow,
+    /// The cellbase output at the index can't hold its occupied capacity
+    #[error("InsufficientCellbaseOutputCapacity {0}")]
+    InsufficientCellbaseOutputCapacity(usize),
+    /// The cellbase output at the index doesn't pay the reward target lock
+    #[error("InvalidCellbaseRewardTarget {0}")]
+    InvalidCellbaseRewardTarget(usize),
+    /// The cellbase outputs don't pay exactly the block reward
+    #[error("MismatchedCellbaseReward")]
+    MismatchedCellbaseReward,
 }
 
 impl_error_conversion_with_kind!(
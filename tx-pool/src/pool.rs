@@ -2,6 +2,7 @@
 extern crate rustc_hash;
 extern crate slab;
 use super::component::{commit_txs_scanner::CommitTxsScanner, TxEntry};
+use crate::block_assembler::BlockAssembler;
 use crate::callback::Callbacks;
 use crate::component::pool_map::{PoolEntry, PoolMap, Status};
 use crate::component::recent_reject::RecentReject;
@@ -12,7 +13,7 @@ use ckb_fee_estimator::Error as FeeEstimatorError;
 use ckb_logger::{debug, error, warn};
 use ckb_snapshot::Snapshot;
 use ckb_store::ChainStore;
-use ckb_types::core::tx_pool::PoolTxDetailInfo;
+use ckb_types::core::tx_pool::{ExclusionReason, PoolTxDetailInfo};
 use ckb_types::core::{BlockNumber, CapacityError, FeeRate};
 use ckb_types::packed::OutPoint;
 use ckb_types::{
@@ -23,9 +24,11 @@ use ckb_types::{
     },
     packed::{Byte32, ProposalShortId},
 };
+use ckb_verification::cache::TxVerificationCache;
 use lru::LruCache;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::Instant;
 
 const COMMITTED_HASH_CACHE_SIZE: usize = 100_000;
 const CONFLICTES_CACHE_SIZE: usize = 10_000;
@@ -537,9 +540,11 @@ impl TxPool {
         &self,
         max_block_cycles: Cycle,
         txs_size_limit: usize,
+        deadline: Option<Instant>,
     ) -> (Vec<TxEntry>, usize, Cycle) {
-        let (entries, size, cycles) =
-            CommitTxsScanner::new(&self.pool_map).txs_to_commit(txs_size_limit, max_block_cycles);
+        let (entries, size, cycles) = CommitTxsScanner::new(&self.pool_map)
+            .with_deadline(deadline)
+            .txs_to_commit(txs_size_limit, max_block_cycles);
 
         if !entries.is_empty() {
             ckb_logger::info!(
@@ -710,6 +715,54 @@ impl TxPool {
         }
     }
 
+    /// Explain why a transaction is not selected into the block template,
+    /// the same selection as the template is used, `package_txs` followed by
+    /// `BlockAssembler::apply_cycles_budget`
+    pub(crate) fn explain_tx_exclusion(
+        &self,
+        tx_hash: &Byte32,
+        max_block_cycles: Cycle,
+        txs_size_limit: usize,
+        txs_verify_cache: &TxVerificationCache,
+        max_transactions: Option<usize>,
+    ) -> ExclusionReason {
+        let id = ProposalShortId::from_tx_hash(tx_hash);
+        let Some(entry) = self.pool_map.get_by_id(&id) else {
+            return match self.recent_reject.as_ref().map(|db| db.get(tx_hash)) {
+                Some(Ok(Some(record))) => ExclusionReason::Rejected(record),
+                _ => ExclusionReason::Unknown,
+            };
+        };
+        match entry.status {
+            Status::Pending => return ExclusionReason::NotProposed,
+            Status::Gap => return ExclusionReason::InProposalGap,
+            Status::Proposed => {}
+        }
+
+        if let Some(ancestor) = self
+            .pool_map
+            .calc_ancestors(&id)
+            .iter()
+            .find(|ancestor| !self.pool_map.has_proposed(ancestor))
+            .and_then(|ancestor| self.pool_map.get_by_id(ancestor))
+        {
+            return ExclusionReason::DependencyExcluded(ancestor.inner.transaction().hash());
+        }
+
+        let (entries, _size, _cycles) = self.package_txs(max_block_cycles, txs_size_limit, None);
+        let (entries, _cycles) = BlockAssembler::apply_cycles_budget(
+            entries,
+            txs_verify_cache,
+            max_block_cycles,
+            max_transactions,
+        );
+        if entries.iter().any(|entry| entry.proposal_short_id() == id) {
+            ExclusionReason::Included
+        } else {
+            ExclusionReason::OverBudget
+        }
+    }
+
     fn build_recent_reject(config: &TxPoolConfig) -> Option<RecentReject> {
         if !config.recent_reject.as_os_str().is_empty() {
             let recent_reject_ttl =
@@ -2,6 +2,7 @@ use crate::callback::Callbacks;
 use crate::component::entry::TxEntry;
 use crate::component::orphan::Entry as OrphanEntry;
 use crate::component::pool_map::Status;
+use crate::component::verify_queue::VERIFY_QUEUE_EXPIRE_TIME;
 use crate::error::Reject;
 use crate::pool::TxPool;
 use crate::service::{BlockAssemblerMessage, TxPoolService, TxVerificationResult};
@@ -330,6 +331,29 @@ impl TxPoolService {
         (ret, snapshot)
     }
 
+    // Same as `pre_check`, but reuses the tx resolved on enqueue if its inputs are still live,
+    // or resolves it again
+    async fn pre_check_resolved(
+        &self,
+        tx: &TransactionView,
+        rtx: Arc<ResolvedTransaction>,
+        fee: Capacity,
+    ) -> (Result<PreCheckedTx, Reject>, Arc<Snapshot>) {
+        let tx_size = tx.data().serialized_size_in_block();
+
+        let (ret, snapshot) = self
+            .with_tx_pool_read_lock(|tx_pool, snapshot| -> Result<PreCheckedTx, Reject> {
+                check_txid_collision(tx_pool, tx)?;
+                let status = check_rtx(tx_pool, &snapshot, &rtx)?;
+                Ok((snapshot.tip_hash(), Arc::clone(&rtx), status, fee, tx_size))
+            })
+            .await;
+        match ret {
+            Err(Reject::Resolve(_)) => self.pre_check(tx).await,
+            ret => (ret, snapshot),
+        }
+    }
+
     pub(crate) fn non_contextual_verify(
         &self,
         tx: &TransactionView,
@@ -393,7 +417,7 @@ impl TxPoolService {
         }
 
         if let Some((ret, snapshot)) = self
-            ._process_tx(tx.clone(), remote.map(|r| r.0), None)
+            ._process_tx(tx.clone(), remote.map(|r| r.0), None, None)
             .await
         {
             self.after_process(tx, remote, &snapshot, &ret).await;
@@ -605,7 +629,7 @@ impl TxPoolService {
                         .await
                         .expect("enqueue suspended tx");
                 } else if let Some((ret, snapshot)) = self
-                    ._process_tx(orphan.tx.clone(), Some(orphan.cycle), None)
+                    ._process_tx(orphan.tx.clone(), Some(orphan.cycle), None, None)
                     .await
                 {
                     match ret {
@@ -698,13 +722,17 @@ impl TxPoolService {
         &self,
         tx: TransactionView,
         declared_cycles: Option<Cycle>,
+        resolved: Option<(Arc<ResolvedTransaction>, Capacity)>,
         command_rx: Option<&mut watch::Receiver<ChunkCommand>>,
     ) -> Option<(Result<Completed, Reject>, Arc<Snapshot>)> {
         let wtx_hash = tx.witness_hash();
         let instant = Instant::now();
         let is_sync_process = command_rx.is_none();
 
-        let (ret, snapshot) = self.pre_check(&tx).await;
+        let (ret, snapshot) = match resolved {
+            Some((rtx, fee)) => self.pre_check_resolved(&tx, rtx, fee).await,
+            None => self.pre_check(&tx).await,
+        };
 
         let (tip_hash, rtx, status, fee, tx_size) = try_or_return_with_snapshot!(ret, snapshot);
 
@@ -930,8 +958,52 @@ impl TxPoolService {
         tx: TransactionView,
         remote: Option<(Cycle, PeerIndex)>,
     ) -> Result<bool, Reject> {
-        let mut queue = self.verify_queue.write().await;
-        queue.add_tx(tx, remote)
+        let resolved = self.resolve_for_verify_queue(&tx).await;
+        let (ret, expired) = {
+            let mut queue = self.verify_queue.write().await;
+            let expired = queue.evict_expired(VERIFY_QUEUE_EXPIRE_TIME);
+            (queue.add_resolved_tx(tx, remote, resolved), expired)
+        };
+        // an expired tx is stale rather than invalid, the relayer is told to forget it only
+        for entry in expired {
+            let tx_hash = entry.tx.hash();
+            debug!("evict expired tx {} in verify_queue", tx_hash);
+            self.send_result_to_relayer(TxVerificationResult::Reject { tx_hash });
+        }
+        let (added, evicted) = ret?;
+        // the same as a remote tx rejected for a full pool, the relayer is told to forget it,
+        // and it's recorded so it's not requested again right away
+        for entry in evicted {
+            let tx_hash = entry.tx.hash();
+            let reject = Reject::Full(format!(
+                "evicted from verify_queue by a local tx: {tx_hash:#x}"
+            ));
+            if let Some((_cycles, peer)) = entry.remote {
+                debug!(
+                    "evict remote tx {} from peer {} in verify_queue for a local tx",
+                    tx_hash, peer
+                );
+            }
+            self.send_result_to_relayer(TxVerificationResult::Reject {
+                tx_hash: tx_hash.clone(),
+            });
+            self.put_recent_reject(&tx_hash, &reject).await;
+        }
+        Ok(added)
+    }
+
+    // The resolved tx and its fee ranking it in the verify queue, `None` if the tx can't be
+    // resolved against the pool yet, it's ranked the lowest then
+    async fn resolve_for_verify_queue(
+        &self,
+        tx: &TransactionView,
+    ) -> Option<(Arc<ResolvedTransaction>, Capacity)> {
+        let tx_pool = self.tx_pool.read().await;
+        let snapshot = tx_pool.snapshot();
+        let tx_size = tx.data().serialized_size_in_block();
+        let (rtx, _status) = resolve_tx(&tx_pool, snapshot, tx.clone(), false).ok()?;
+        let fee = check_tx_fee(&tx_pool, snapshot, &rtx, tx_size).ok()?;
+        Some((rtx, fee))
     }
 
     async fn remove_orphan_txs_by_attach<'a>(&self, txs: &LinkedHashSet<TransactionView>) {
@@ -1,6 +1,6 @@
 //! Tx-pool background service
 
-use crate::block_assembler::{self, BlockAssembler};
+use crate::block_assembler::{self, BlockAssembler, QueuedMessages};
 use crate::callback::{Callbacks, PendingCallback, ProposedCallback, RejectCallback};
 use crate::component::orphan::OrphanPool;
 use crate::component::pool_map::{PoolEntry, Status};
@@ -27,14 +27,15 @@ use ckb_types::{
     core::{
         cell::{CellProvider, CellStatus, OverlayCellProvider},
         tx_pool::{
-            EntryCompleted, PoolTxDetailInfo, Reject, TransactionWithStatus, TxPoolEntryInfo,
-            TxPoolIds, TxPoolInfo, TxStatus, TRANSACTION_SIZE_LIMIT,
+            EntryCompleted, ExclusionReason, PoolTxDetailInfo, Reject, TransactionWithStatus,
+            TxPoolEntryInfo, TxPoolIds, TxPoolInfo, TxStatus, VerifyQueueInfo,
+            TRANSACTION_SIZE_LIMIT,
         },
         BlockView, Cycle, EstimateMode, FeeRate, TransactionView, UncleBlockView, Version,
     },
     packed::{Byte32, OutPoint, ProposalShortId},
 };
-use ckb_util::{LinkedHashMap, LinkedHashSet};
+use ckb_util::LinkedHashMap;
 use ckb_verification::cache::TxVerificationCache;
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::{
@@ -115,10 +116,12 @@ pub(crate) enum Message {
     NewUncle(Notify<UncleBlockView>),
     ClearPool(Request<Arc<Snapshot>, ()>),
     ClearVerifyQueue(Request<(), ()>),
+    GetVerifyQueueInfo(Request<(), VerifyQueueInfo>),
     GetAllEntryInfo(Request<(), TxPoolEntryInfo>),
     GetAllIds(Request<(), TxPoolIds>),
     SavePool(Request<(), ()>),
     GetPoolTxDetails(Request<Byte32, PoolTxDetailInfo>),
+    ExplainTxExclusion(Request<Byte32, ExclusionReason>),
 
     UpdateIBDState(Request<bool, ()>),
     EstimateFeeRate(Request<(EstimateMode, bool), FeeEstimatesResult>),
@@ -335,6 +338,11 @@ impl TxPoolController {
         send_message!(self, ClearVerifyQueue, ())
     }
 
+    /// Return the verification progress of the txs in the verify queue
+    pub fn get_verify_queue_info(&self) -> Result<VerifyQueueInfo, AnyError> {
+        send_message!(self, GetVerifyQueueInfo, ())
+    }
+
     /// TODO(doc): @zhangsoledad
     pub fn get_all_entry_info(&self) -> Result<TxPoolEntryInfo, AnyError> {
         send_message!(self, GetAllEntryInfo, ())
@@ -350,6 +358,11 @@ impl TxPoolController {
         send_message!(self, GetPoolTxDetails, tx_hash)
     }
 
+    /// explain why a transaction is not selected into the block template
+    pub fn explain_tx_exclusion(&self, tx_hash: Byte32) -> Result<ExclusionReason, AnyError> {
+        send_message!(self, ExplainTxExclusion, tx_hash)
+    }
+
     /// Saves tx pool into disk.
     pub fn save_pool(&self) -> Result<(), AnyError> {
         info!("Please be patient, tx-pool are saving data into disk ...");
@@ -611,9 +624,10 @@ impl TxPoolServiceBuilder {
                     }
                 });
             } else {
+                let new_transactions_threshold = block_assembler.config.new_transactions_threshold;
                 self.handle.spawn(async move {
                     let mut interval = tokio::time::interval(interval);
-                    let mut queue = LinkedHashSet::new();
+                    let mut queue = QueuedMessages::new(new_transactions_threshold);
                     loop {
                         tokio::select! {
                             Some(message) = block_assembler_receiver.recv() => {
@@ -621,21 +635,13 @@ impl TxPoolServiceBuilder {
                                     let service_clone = process_service.clone();
                                     queue.clear();
                                     block_assembler::process(service_clone, &message).await;
-                                } else {
-                                    queue.insert(message);
+                                } else if queue.push(message) {
+                                    queue.flush(&process_service).await;
+                                    interval.reset();
                                 }
                             },
                             _ = interval.tick() => {
-                                for message in &queue {
-                                    let service_clone = process_service.clone();
-                                    block_assembler::process(service_clone, message).await;
-                                }
-                                if !queue.is_empty() {
-                                    if let Some(ref block_assembler) = process_service.block_assembler {
-                                        block_assembler.notify().await;
-                                    }
-                                }
-                                queue.clear();
+                                queue.flush(&process_service).await;
                             }
                             _ = signal_receiver.cancelled() => {
                                 info!("TxPool block_assembler process service received exit signal, exit now");
@@ -952,6 +958,18 @@ async fn process(mut service: TxPoolService, message: Message) {
                 error!("Responder sending clear_verify_queue failed {:?}", e)
             };
         }
+        Message::GetVerifyQueueInfo(Request { responder, .. }) => {
+            let (fresh_size, verifying_size, completed_size) =
+                service.verify_queue.read().await.status_counts();
+            let info = VerifyQueueInfo {
+                fresh_size,
+                verifying_size,
+                completed_size,
+            };
+            if let Err(e) = responder.send(info) {
+                error!("Responder sending get_verify_queue_info failed {:?}", e)
+            };
+        }
         Message::GetPoolTxDetails(Request {
             responder,
             arguments: tx_hash,
@@ -965,6 +983,31 @@ async fn process(mut service: TxPoolService, message: Message) {
                 error!("responder send get_pool_tx_details failed {:?}", e)
             };
         }
+        Message::ExplainTxExclusion(Request {
+            responder,
+            arguments: tx_hash,
+        }) => {
+            let max_block_cycles = service.consensus.max_block_cycles();
+            let (txs_size_limit, max_transactions) = match service.block_assembler {
+                Some(ref block_assembler) => (
+                    block_assembler.txs_size_limit().await,
+                    block_assembler.config.max_block_transactions,
+                ),
+                None => (service.consensus.max_block_bytes() as usize, None),
+            };
+            let txs_verify_cache = service.txs_verify_cache.read().await;
+            let tx_pool = service.tx_pool.read().await;
+            let reason = tx_pool.explain_tx_exclusion(
+                &tx_hash,
+                max_block_cycles,
+                txs_size_limit,
+                &txs_verify_cache,
+                max_transactions,
+            );
+            if let Err(e) = responder.send(reason) {
+                error!("Responder sending explain_tx_exclusion failed {:?}", e)
+            };
+        }
         Message::GetAllEntryInfo(Request { responder, .. }) => {
             let tx_pool = service.tx_pool.read().await;
             let info = tx_pool.get_all_entry_info();
@@ -1027,6 +1070,7 @@ async fn process(mut service: TxPoolService, message: Message) {
             let (txs, _size, _cycles) = tx_pool.package_txs(
                 max_block_cycles,
                 bytes_limit.unwrap_or(max_block_bytes) as usize,
+                None,
             );
             if let Err(e) = responder.send(txs) {
                 error!("Responder sending plug_entry failed {:?}", e);
@@ -1122,7 +1166,10 @@ impl TxPoolService {
 
     pub async fn update_block_assembler_after_tx_pool_reorg(&self) {
         if let Some(ref block_assembler) = self.block_assembler {
-            if let Err(e) = block_assembler.update_full(&self.tx_pool).await {
+            if let Err(e) = block_assembler
+                .update_full(&self.tx_pool, &self.txs_verify_cache)
+                .await
+            {
                 error!("block_assembler update failed {:?}", e);
             }
             block_assembler.notify().await;
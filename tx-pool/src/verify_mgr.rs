@@ -11,7 +11,7 @@ use tokio::task::JoinHandle;
 #[derive(Clone, Debug, PartialEq)]
 enum WorkerRole {
     OnlySmallCycleTx,
-    SubmitTimeFirst,
+    FeeRateFirst,
 }
 
 struct Worker {
@@ -91,13 +91,13 @@ impl Worker {
             // pick a entry to run verify
             let entry = {
                 let mut tasks = self.tasks.write().await;
-                match tasks.pop_front(self.role == WorkerRole::OnlySmallCycleTx) {
+                match tasks.pop_best_fresh(self.role == WorkerRole::OnlySmallCycleTx) {
                     Some(entry) => entry,
                     None => {
-                        if !tasks.is_empty() {
+                        if tasks.has_fresh() {
                             tasks.re_notify();
                             debug!(
-                                "Worker (role: {:?}) didn't got tx after pop_front, but tasks is not empty, notify other Workers now",
+                                "Worker (role: {:?}) didn't got tx after pop_best_fresh, but tasks is not empty, notify other Workers now",
                             self.role
                         );
                         }
@@ -106,21 +106,31 @@ impl Worker {
                 }
             };
 
+            // the entry stays in the queue as `Verifying` until it's processed
+            let short_id = entry.tx.proposal_short_id();
             if let Some((res, snapshot)) = self
                 .service
                 ._process_tx(
                     entry.tx.clone(),
                     entry.remote.map(|e| e.0),
+                    entry.resolved,
                     Some(&mut self.command_rx),
                 )
                 .await
             {
+                if res.is_err() && self.exit_signal.is_cancelled() {
+                    // the verification is interrupted by the stop command, it's not a reject
+                    self.tasks.write().await.requeue_fresh(&short_id);
+                    return;
+                }
+                self.tasks.write().await.set_completed(&short_id);
                 self.service
                     .after_process(entry.tx, entry.remote, &snapshot, &res)
                     .await;
             } else {
                 info!("_process_tx for tx: {} returned none", entry.tx.hash());
             }
+            self.tasks.write().await.remove_tx(&short_id);
         }
     }
 }
@@ -149,7 +159,7 @@ impl VerifyMgr {
                     let role = if idx == 0 && worker_num > 1 {
                         WorkerRole::OnlySmallCycleTx
                     } else {
-                        WorkerRole::SubmitTimeFirst
+                        WorkerRole::FeeRateFirst
                     };
                     let (child_tx, child_rx) = watch::channel(ChunkCommand::Resume);
                     (
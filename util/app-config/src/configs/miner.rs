@@ -11,6 +11,12 @@ pub struct Config {
     pub client: ClientConfig,
     /// Miner workers config options.
     pub workers: Vec<WorkerConfig>,
+    /// The grace window in milliseconds during which the workers keep sealing a template
+    /// which has just become stale, before switching to the new one.
+    ///
+    /// The default is 0, the stale template is abandoned immediately.
+    #[serde(default)]
+    pub stale_grace_millis: u64,
 }
 
 /// RPC client config options.
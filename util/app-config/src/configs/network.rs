@@ -110,6 +110,30 @@ pub struct SyncConfig {
     /// Proof of minimum work during synchronization
     #[serde(skip, default)]
     pub min_chain_work: U256,
+    /// The seconds an orphan block waits for its parent before it's evicted, default is 600,
+    /// at least 1
+    #[serde(default)]
+    pub orphan_block_max_age_secs: Option<u64>,
+    /// How to handle a block which is already verified on the main chain or pending verification
+    #[serde(default)]
+    pub duplicate_block_policy: DuplicateBlockPolicy,
+    /// Log the accepted and rejected blocks of every peer periodically, default is false
+    #[serde(default)]
+    pub log_peer_block_stats: bool,
+    /// The minimum number of connected sync peers before exiting IBD, default is 0
+    #[serde(default)]
+    pub min_peers_to_exit_ibd: usize,
+}
+
+/// The policy for handling a submitted block which is already known.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateBlockPolicy {
+    /// Return the known block result directly, without verifying it again
+    #[default]
+    ShortCircuit,
+    /// Process the block again, as if it were never seen
+    Reverify,
 }
 
 /// Header map config options.
@@ -125,6 +149,35 @@ pub struct HeaderMapConfig {
     /// The maximum amount memory limit
     #[serde(default = "default_memory_limit")]
     pub memory_limit: ByteUnit,
+    /// The maximum number of retries of a transient disk backend error, default is 3
+    #[serde(default)]
+    pub backend_retry_attempts: Option<usize>,
+    /// The disk backend storing the headers spilled from memory, default is sled
+    #[serde(default)]
+    pub backend: HeaderMapBackend,
+    /// Keep the headers under the tmp dir across restarts instead of a fresh temporary
+    /// directory, default is false
+    #[serde(default)]
+    pub persistent: bool,
+    /// The interval in milliseconds of trimming the memory map down to `memory_limit`,
+    /// default is 5000
+    #[serde(default)]
+    pub trim_interval_ms: Option<u64>,
+    /// Keep all headers in memory if the disk backend can't be created, instead of failing
+    /// to start, default is false
+    #[serde(default)]
+    pub memory_fallback: bool,
+}
+
+/// The storage engine of the header map disk backend.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HeaderMapBackend {
+    /// A sled database in a temporary directory
+    #[default]
+    Sled,
+    /// A RocksDB database in a temporary directory
+    Rocksdb,
 }
 
 impl Default for HeaderMapConfig {
@@ -133,6 +186,11 @@ impl Default for HeaderMapConfig {
             primary_limit: None,
             backend_close_threshold: None,
             memory_limit: default_memory_limit(),
+            backend_retry_attempts: None,
+            backend: HeaderMapBackend::default(),
+            persistent: false,
+            trim_interval_ms: None,
+            memory_fallback: false,
         }
     }
 }
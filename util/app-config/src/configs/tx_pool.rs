@@ -62,6 +62,10 @@ pub struct BlockAssemblerConfig {
     /// A field to control update interval millis
     #[serde(default = "default_update_interval_millis")]
     pub update_interval_millis: u64,
+    /// Update the template as soon as this many new transactions arrive, without waiting for
+    /// the update interval (default 0, disabled)
+    #[serde(default)]
+    pub new_transactions_threshold: u16,
     /// Notify url
     #[serde(default)]
     pub notify: Vec<Url>,
@@ -71,6 +75,51 @@ pub struct BlockAssemblerConfig {
     /// Notify timeout
     #[serde(default = "default_notify_timeout_millis")]
     pub notify_timeout_millis: u64,
+    /// Stop selecting transactions after the deadline millis and use the best template found so far
+    /// (default disabled)
+    #[serde(default)]
+    pub assembly_deadline_millis: Option<u64>,
+    /// The maximum number of transactions in a template, the template is always bounded by the
+    /// block cycles and bytes limits (default unlimited)
+    #[serde(default)]
+    pub max_block_transactions: Option<usize>,
+    /// Additional lock scripts to spread the miner rewards, the lock above is always a
+    /// candidate with weight 1 (default empty, all rewards go to the lock above)
+    #[serde(default)]
+    pub reward_locks: Vec<RewardLockConfig>,
+    /// How to choose the reward lock of every block among the candidates
+    #[serde(default)]
+    pub reward_lock_policy: RewardLockPolicy,
+}
+
+/// An additional miner lock script.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct RewardLockConfig {
+    /// The lock script code hash.
+    pub code_hash: H256,
+    /// The lock script args.
+    pub args: JsonBytes,
+    /// The lock script hash type.
+    pub hash_type: ScriptHashType,
+    /// The relative chance to be chosen by the weighted random policy (default 1).
+    #[serde(default = "default_reward_lock_weight")]
+    pub weight: u32,
+}
+
+/// The policy to choose the reward lock of every block.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RewardLockPolicy {
+    /// Take turns by the block number
+    #[default]
+    RoundRobin,
+    /// Choose randomly in proportion to the weights
+    WeightedRandom,
+}
+
+const fn default_reward_lock_weight() -> u32 {
+    1
 }
 
 const fn default_use_binary_version_as_message_prefix() -> bool {
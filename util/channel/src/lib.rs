@@ -1,6 +1,6 @@
 //! Reexports `crossbeam_channel` to uniform the dependency version.
 pub use crossbeam_channel::{
-    after, bounded, select, tick, unbounded, Receiver, RecvError, RecvTimeoutError, Select,
+    after, bounded, never, select, tick, unbounded, Receiver, RecvError, RecvTimeoutError, Select,
     SendError, Sender, TrySendError,
 };
 
@@ -70,3 +70,5 @@ pub const MAX_RELAY_TXS_NUM_PER_BATCH: usize = 32767;
 pub const MAX_UNKNOWN_TX_HASHES_SIZE: usize = 50000;
 /// The soft limit to the number of unknown transactions per peer
 pub const MAX_UNKNOWN_TX_HASHES_SIZE_PER_PEER: usize = MAX_RELAY_TXS_NUM_PER_BATCH;
+/// The maximum number of transactions requested from a peer but not responded yet
+pub const MAX_OUTSTANDING_RELAY_TXS_PER_PEER: usize = 4096;
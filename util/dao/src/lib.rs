@@ -8,7 +8,7 @@ use ckb_types::{
     bytes::Bytes,
     core::{
         cell::{CellMeta, ResolvedTransaction},
-        Capacity, CapacityResult, EpochExt, HeaderView, ScriptHashType,
+        BlockNumber, Capacity, CapacityResult, EpochExt, HeaderView, ScriptHashType,
     },
     packed::{Byte32, CellOutput, Script, WitnessArgs},
     prelude::*,
@@ -62,13 +62,12 @@ impl<'a, DL: CellDataProvider + EpochProvider + HeaderProvider> DaoCalculator<'a
             .get_epoch_ext(target)
             .ok_or(DaoError::InvalidHeader)?;
 
-        let target_g2 = target_epoch
-            .secondary_block_issuance(target.number(), self.consensus.secondary_epoch_reward())?;
-        let (_, target_parent_c, _, target_parent_u) = extract_dao_data(target_parent.dao());
-        let reward128 = u128::from(target_g2.as_u64()) * u128::from(target_parent_u.as_u64())
-            / u128::from(target_parent_c.as_u64());
-        let reward = u64::try_from(reward128).map_err(|_| DaoError::Overflow)?;
-        Ok(Capacity::shannons(reward))
+        secondary_block_reward(
+            self.consensus,
+            target.number(),
+            &target_epoch,
+            target_parent.dao(),
+        )
     }
 
     /// Calculates the new dao field with specified [`EpochExt`].
@@ -339,3 +338,20 @@ pub fn modified_occupied_capacity(
     }
     cell_meta.occupied_capacity()
 }
+
+/// Returns the secondary block reward of the non-genesis block `target_number`, the share
+/// `U / C` of the secondary issuance, where `U` and `C` are read from `target_parent_dao`.
+pub fn secondary_block_reward(
+    consensus: &Consensus,
+    target_number: BlockNumber,
+    target_epoch: &EpochExt,
+    target_parent_dao: Byte32,
+) -> Result<Capacity, DaoError> {
+    let target_g2 =
+        target_epoch.secondary_block_issuance(target_number, consensus.secondary_epoch_reward())?;
+    let (_, target_parent_c, _, target_parent_u) = extract_dao_data(target_parent_dao);
+    let reward128 = u128::from(target_g2.as_u64()) * u128::from(target_parent_u.as_u64())
+        / u128::from(target_parent_c.as_u64());
+    let reward = u64::try_from(reward128).map_err(|_| DaoError::Overflow)?;
+    Ok(Capacity::shannons(reward))
+}
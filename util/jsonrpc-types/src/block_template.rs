@@ -1,6 +1,6 @@
 use crate::{
-    BlockNumber, Byte32, Cycle, EpochNumberWithFraction, Header, JsonBytes, ProposalShortId,
-    Timestamp, Transaction, Uint32, Uint64, Version,
+    BlockNumber, Byte32, Capacity, Cycle, EpochNumberWithFraction, Header, JsonBytes,
+    ProposalShortId, Timestamp, Transaction, Uint32, Uint64, Version,
 };
 use ckb_types::{packed, prelude::*, H256};
 use schemars::JsonSchema;
@@ -69,6 +69,20 @@ pub struct BlockTemplate {
     pub transactions: Vec<TransactionTemplate>,
     /// Provided proposal ids list of transactions for the new block.
     pub proposals: Vec<ProposalShortId>,
+    /// The accumulated cycles of the provided transactions.
+    ///
+    /// This is the amount counted against `cycles_limit` when the node selected `transactions`,
+    /// the cellbase is not included.
+    #[serde(default)]
+    pub total_cycles: Cycle,
+    /// The accumulated serialized size of the provided transactions.
+    ///
+    /// The cellbase, uncles and proposals are not included.
+    #[serde(default)]
+    pub total_size: Uint64,
+    /// The accumulated fees of the provided transactions.
+    #[serde(default)]
+    pub total_fees: Capacity,
     /// Provided cellbase transaction template.
     ///
     /// Miners must use it as the cellbase transaction without changes in the assembled block.
@@ -56,3 +56,27 @@ pub struct MainLoggerConfig {
     /// `null` means keeping the current option unchanged.
     pub color: Option<bool>,
 }
+
+/// A block verification phase which can be disabled at runtime.
+///
+/// Mainnet refuses to disable `non_contextual`, `reward` and `script`.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationPhase {
+    /// The epoch of the block
+    Epoch,
+    /// The uncles of the block
+    Uncles,
+    /// The proposal window of the committed transactions
+    TwoPhaseCommit,
+    /// The dao field of the block header
+    DaoHeader,
+    /// The reward paid by the cellbase
+    Reward,
+    /// The checks which don't need the chain state
+    NonContextual,
+    /// The scripts of the transactions
+    Script,
+    /// The block extension
+    Extension,
+}
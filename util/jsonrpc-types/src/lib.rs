@@ -36,7 +36,7 @@ pub use self::blockchain::{
 };
 pub use self::bytes::JsonBytes;
 pub use self::cell::{CellData, CellInfo, CellWithStatus};
-pub use self::debug::{ExtraLoggerConfig, MainLoggerConfig};
+pub use self::debug::{ExtraLoggerConfig, MainLoggerConfig, VerificationPhase};
 pub use self::experiment::{DaoWithdrawingCalculationKind, EstimateCycles};
 pub use self::fee_estimator::EstimateMode;
 pub use self::fee_rate::FeeRateDef;
@@ -49,7 +49,7 @@ pub use self::net::{
 pub use self::pool::{
     AncestorsScoreSortKey, EntryCompleted, OutputsValidator, PoolTransactionEntry,
     PoolTransactionReject, PoolTxDetailInfo, RawTxPool, TxPoolEntries, TxPoolEntry, TxPoolIds,
-    TxPoolInfo,
+    TxPoolInfo, VerifyQueueInfo,
 };
 pub use self::proposal_short_id::ProposalShortId;
 pub use self::subscription::Topic;
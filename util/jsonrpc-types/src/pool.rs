@@ -3,6 +3,7 @@ use ckb_types::core::service::PoolTransactionEntry as CorePoolTransactionEntry;
 use ckb_types::core::tx_pool::{
     AncestorsScoreSortKey as CoreAncestorsScoreSortKey, PoolTxDetailInfo as CorePoolTxDetailInfo,
     Reject, TxEntryInfo, TxPoolEntryInfo, TxPoolIds as CoreTxPoolIds, TxPoolInfo as CoreTxPoolInfo,
+    VerifyQueueInfo as CoreVerifyQueueInfo,
 };
 use ckb_types::prelude::Unpack;
 use ckb_types::H256;
@@ -84,6 +85,27 @@ impl From<CoreTxPoolInfo> for TxPoolInfo {
     }
 }
 
+/// The verification progress of the transactions in the verify queue.
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
+pub struct VerifyQueueInfo {
+    /// Count of transactions waiting for a verify worker.
+    pub fresh: Uint64,
+    /// Count of transactions being verified.
+    pub verifying: Uint64,
+    /// Count of verified transactions waiting to be removed from the queue.
+    pub completed: Uint64,
+}
+
+impl From<CoreVerifyQueueInfo> for VerifyQueueInfo {
+    fn from(info: CoreVerifyQueueInfo) -> Self {
+        VerifyQueueInfo {
+            fresh: (info.fresh_size as u64).into(),
+            verifying: (info.verifying_size as u64).into(),
+            completed: (info.completed_size as u64).into(),
+        }
+    }
+}
+
 /// The transaction entry in the pool.
 #[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
 pub struct PoolTransactionEntry {
@@ -421,7 +421,7 @@ impl Launcher {
             .enable_integration_test(
                 shared.clone(),
                 network_controller.clone(),
-                chain_controller,
+                chain_controller.clone(),
                 rpc_config
                     .extra_well_known_lock_scripts
                     .iter()
@@ -439,7 +439,7 @@ impl Launcher {
                 &self.args.config.db,
                 &self.args.config.indexer,
             )
-            .enable_debug();
+            .enable_debug(chain_controller);
         builder.enable_subscription(shared.clone());
         let io_handler = builder.build();
 
@@ -73,9 +73,14 @@ impl MockChain {
                 use_binary_version_as_message_prefix: true,
                 binary_version: "LightClientServer".to_string(),
                 update_interval_millis: 800,
+                new_transactions_threshold: 0,
                 notify: vec![],
                 notify_scripts: vec![],
                 notify_timeout_millis: 800,
+                assembly_deadline_millis: None,
+                max_block_transactions: None,
+                reward_locks: vec![],
+                reward_lock_policy: Default::default(),
             };
             SharedBuilder::with_temp_db()
                 .consensus(consensus)
@@ -73,7 +73,13 @@ pub struct Metrics {
     pub ckb_chain_lonely_block_ch_len: IntGauge,
     pub ckb_chain_unverified_block_ch_len: IntGauge,
     pub ckb_chain_preload_unverified_block_ch_len: IntGauge,
+    /// The number of messages queued in every ckb_chain channel
+    pub ckb_chain_channel_len: IntGaugeVec,
+    /// The capacity of every ckb_chain channel
+    pub ckb_chain_channel_capacity: IntGaugeVec,
     pub ckb_chain_load_full_unverified_block: Histogram,
+    /// The duration of every sub-verifier of the contextual block verification (seconds)
+    pub ckb_chain_contextual_verifier_duration: HistogramVec,
     /// ckb_sync_msg_process duration (seconds)
     pub ckb_sync_msg_process_duration: HistogramVec,
     /// ckb_sync_block_fetch duraiton (seconds)
@@ -82,8 +88,18 @@ pub struct Metrics {
     pub ckb_header_map_limit_memory_duration: Histogram,
     // ckb_header_map_limit_memory operation duration (seconds)
     pub ckb_header_map_ops_duration: HistogramVec,
+    // ckb_header_map backend operation duration (seconds)
+    pub ckb_header_map_backend_ops_duration: HistogramVec,
     // how many headers in the HeaderMap's memory map?
     pub ckb_header_map_memory_count: IntGauge,
+    /// The estimated bytes of the headers in the HeaderMap's memory map
+    pub ckb_header_map_memory_bytes: IntGauge,
+    /// How many headers are spilled into the HeaderMap's disk backend
+    pub ckb_header_map_backend_count: IntGauge,
+    /// How many headers are evicted from the HeaderMap's memory map into the backend
+    pub ckb_header_map_evicted_count: IntCounter,
+    /// The bytes the HeaderMap's disk backend takes on disk
+    pub ckb_header_map_backend_disk_bytes: IntGauge,
     // how many times the HeaderMap's memory map is hit?
     pub ckb_header_map_memory_hit_miss_count: CkbHeaderMapMemoryHitMissStatistics,
     /// Gauge for tracking the size of all frozen data
@@ -126,6 +142,16 @@ pub struct Metrics {
     pub ckb_network_ban_peer: IntCounter,
     pub ckb_inflight_blocks_count: IntGauge,
     pub ckb_inflight_timeout_count: IntCounter,
+    /// Counter for the nonces tried by the miner workers
+    pub ckb_miner_hash_attempts: IntCounter,
+    /// Counter for the nonces found by the miner workers
+    pub ckb_miner_nonces_found: IntCounter,
+    /// Gauge for the hash rate of every miner worker
+    pub ckb_miner_hash_rate: GaugeVec,
+    /// Counter for the blocks sealed by the miner, labelled by submission status
+    pub ckb_miner_blocks: IntCounterVec,
+    /// Gauge for the difficulty of the block template being sealed
+    pub ckb_miner_target_difficulty: Gauge,
 }
 
 static METRICS: std::sync::LazyLock<Metrics> = std::sync::LazyLock::new(|| {
@@ -175,10 +201,25 @@ static METRICS: std::sync::LazyLock<Metrics> = std::sync::LazyLock::new(|| {
             "ckb_chain_preload_unverified_block_ch_len",
             "The CKB chain fill unverified block channel length",
         ).unwrap(),
+    ckb_chain_channel_len: register_int_gauge_vec!(
+            "ckb_chain_channel_len",
+            "The CKB chain channel length",
+            &["channel"],
+        ).unwrap(),
+    ckb_chain_channel_capacity: register_int_gauge_vec!(
+            "ckb_chain_channel_capacity",
+            "The CKB chain channel capacity",
+            &["channel"],
+        ).unwrap(),
     ckb_chain_load_full_unverified_block: register_histogram!(
             "ckb_chain_load_full_unverified_block",
             "The CKB chain load_full_unverified_block duration (seconds)"
         ).unwrap(),
+    ckb_chain_contextual_verifier_duration: register_histogram_vec!(
+            "ckb_chain_contextual_verifier_duration",
+            "The CKB chain contextual sub-verifier duration (seconds)",
+            &["verifier"],
+        ).unwrap(),
     ckb_sync_msg_process_duration: register_histogram_vec!(
             "ckb_sync_msg_process_duration",
             "The CKB sync message process duration (seconds)",
@@ -197,10 +238,31 @@ static METRICS: std::sync::LazyLock<Metrics> = std::sync::LazyLock::new(|| {
             "The CKB header map operation duration (seconds)",
             &["operation"],
         ).unwrap(),
+    ckb_header_map_backend_ops_duration: register_histogram_vec!(
+            "ckb_header_map_backend_ops_duration",
+            "The CKB header map backend operation duration (seconds)",
+            &["operation"],
+        ).unwrap(),
     ckb_header_map_memory_count: register_int_gauge!(
             "ckb_header_map_memory_count",
             "The CKB HeaderMap memory count",
         ).unwrap(),
+    ckb_header_map_memory_bytes: register_int_gauge!(
+            "ckb_header_map_memory_bytes",
+            "The CKB HeaderMap memory bytes",
+        ).unwrap(),
+    ckb_header_map_backend_count: register_int_gauge!(
+            "ckb_header_map_backend_count",
+            "The CKB HeaderMap backend count",
+        ).unwrap(),
+    ckb_header_map_evicted_count: register_int_counter!(
+            "ckb_header_map_evicted_count",
+            "The CKB HeaderMap evicted headers count",
+        ).unwrap(),
+    ckb_header_map_backend_disk_bytes: register_int_gauge!(
+            "ckb_header_map_backend_disk_bytes",
+            "The CKB HeaderMap backend disk bytes",
+        ).unwrap(),
     ckb_header_map_memory_hit_miss_count: CkbHeaderMapMemoryHitMissStatistics::from(
             &register_int_counter_vec!(
             "ckb_header_map_memory_hit_miss_count",
@@ -312,6 +374,28 @@ static METRICS: std::sync::LazyLock<Metrics> = std::sync::LazyLock::new(|| {
             "ckb_inflight_timeout_count",
             "The CKB inflight timeout count"
     ).unwrap(),
+    ckb_miner_hash_attempts: register_int_counter!(
+        "ckb_miner_hash_attempts",
+        "The CKB miner hashing attempts"
+    ).unwrap(),
+    ckb_miner_nonces_found: register_int_counter!(
+        "ckb_miner_nonces_found",
+        "The CKB miner nonces found"
+    ).unwrap(),
+    ckb_miner_hash_rate: register_gauge_vec!(
+        "ckb_miner_hash_rate",
+        "The CKB miner hash rate of every worker",
+        &["worker"]
+    ).unwrap(),
+    ckb_miner_blocks: register_int_counter_vec!(
+        "ckb_miner_blocks",
+        "The CKB miner blocks by submission status",
+        &["status"]
+    ).unwrap(),
+    ckb_miner_target_difficulty: register_gauge!(
+        "ckb_miner_target_difficulty",
+        "The CKB miner block template difficulty"
+    ).unwrap(),
     }
 });
 
@@ -347,6 +431,47 @@ pub fn handle() -> Option<&'static Metrics> {
     }
 }
 
+/// Helpers for the tests asserting on the metrics of other crates.
+///
+/// Once enabled, the metrics are shared by every test of the process, so a test should only
+/// assert on the metrics it drives, or on the delta of a counter.
+#[doc(hidden)]
+pub mod testing {
+    use super::{Metrics, ENABLE_COLLECT_METRICS, METRICS, METRICS_SERVICE_ENABLED};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    /// Enable the metrics in every thread of the process.
+    pub fn enable() -> &'static Metrics {
+        // other tests may have enabled them already
+        let _ = METRICS_SERVICE_ENABLED.set(true);
+        super::handle().expect("metrics enabled")
+    }
+
+    /// Enable the metrics in the current thread only, the other threads follow the metrics
+    /// service as usual.
+    pub fn enable_in_current_thread() -> &'static Metrics {
+        ENABLE_COLLECT_METRICS
+            .with(|enable_collect_metrics| enable_collect_metrics.set(Some(true)));
+        &METRICS
+    }
+
+    /// Poll `condition` until it holds, returns false if it still doesn't after `timeout`.
+    pub fn wait_for(timeout: Duration, condition: impl Fn(&Metrics) -> bool) -> bool {
+        let metrics: &Metrics = &METRICS;
+        let deadline = Instant::now() + timeout;
+        loop {
+            if condition(metrics) {
+                return true;
+            }
+            if Instant::now() >= deadline {
+                return false;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::METRICS;
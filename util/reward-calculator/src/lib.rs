@@ -1,12 +1,12 @@
 //! This mod implemented a ckb block reward calculator
 
 use ckb_chain_spec::consensus::Consensus;
-use ckb_dao::DaoCalculator;
+use ckb_dao::{secondary_block_reward, DaoCalculator};
 use ckb_dao_utils::DaoError;
 use ckb_logger::debug;
 use ckb_store::ChainStore;
 use ckb_types::{
-    core::{BlockReward, Capacity, CapacityResult, HeaderView},
+    core::{BlockReward, Capacity, CapacityResult, EpochExt, HeaderView},
     packed::{Byte32, CellbaseWitness, ProposalShortId, Script},
     prelude::*,
 };
@@ -132,24 +132,12 @@ impl<'a, CS: ChainStore> RewardCalculator<'a, CS> {
     // Miner get (tx_fee - 40% of tx fee) for tx commitment.
     // Be careful of the rounding, tx_fee - 40% of tx fee is different from 60% of tx fee.
     fn txs_fees(&self, target: &HeaderView) -> CapacityResult<Capacity> {
-        let consensus = self.consensus;
         let target_ext = self
             .store
             .get_block_ext(&target.hash())
             .expect("block body stored");
 
-        target_ext
-            .txs_fees
-            .iter()
-            .try_fold(Capacity::zero(), |acc, tx_fee| {
-                tx_fee
-                    .safe_mul_ratio(consensus.proposer_reward_ratio())
-                    .and_then(|proposer| {
-                        tx_fee
-                            .safe_sub(proposer)
-                            .and_then(|miner| acc.safe_add(miner))
-                    })
-            })
+        committer_txs_fees(self.consensus, &target_ext.txs_fees)
     }
 
     /// Earliest proposer get 40% of tx fee as reward when tx committed
@@ -284,3 +272,78 @@ impl<'a, CS: ChainStore> RewardCalculator<'a, CS> {
         ids_set
     }
 }
+
+// The committer's share of the transaction fees, see `RewardCalculator::txs_fees`.
+fn committer_txs_fees(consensus: &Consensus, txs_fees: &[Capacity]) -> CapacityResult<Capacity> {
+    txs_fees.iter().try_fold(Capacity::zero(), |acc, tx_fee| {
+        tx_fee
+            .safe_mul_ratio(consensus.proposer_reward_ratio())
+            .and_then(|proposer| {
+                tx_fee
+                    .safe_sub(proposer)
+                    .and_then(|miner| acc.safe_add(miner))
+            })
+    })
+}
+
+/// All the inputs needed to calculate the block reward of a target block, see
+/// [`block_reward_offline`](fn.block_reward_offline.html).
+pub struct RewardInputs<'a> {
+    /// The target block header
+    pub target: &'a HeaderView,
+    /// The dao field of the target block's parent, unused if the target is the genesis block
+    pub target_parent_dao: Byte32,
+    /// The epoch of the target block
+    pub target_epoch: &'a EpochExt,
+    /// Fees of the transactions committed in the target block, cellbase excluded
+    pub txs_fees: &'a [Capacity],
+    /// Fees of the committed transactions whose earliest proposals are in the target block or
+    /// its uncles
+    pub earliest_proposed_txs_fees: &'a [Capacity],
+}
+
+/// Calculates the block reward of a target block purely from the given inputs, without
+/// reading the store, so that offline tooling can project rewards of hypothetical blocks.
+///
+/// The store-backed [`RewardCalculator`](struct.RewardCalculator.html) remains the
+/// authoritative one used by the verification.
+pub fn block_reward_offline(
+    consensus: &Consensus,
+    inputs: &RewardInputs,
+) -> Result<BlockReward, DaoError> {
+    let target = inputs.target;
+    let txs_fees = committer_txs_fees(consensus, inputs.txs_fees)?;
+    let proposal_reward =
+        inputs
+            .earliest_proposed_txs_fees
+            .iter()
+            .try_fold(Capacity::zero(), |acc, tx_fee| {
+                tx_fee
+                    .safe_mul_ratio(consensus.proposer_reward_ratio())
+                    .and_then(|proposer| acc.safe_add(proposer))
+            })?;
+    let primary = inputs.target_epoch.block_reward(target.number())?;
+    let secondary = if target.number() == 0 {
+        Capacity::zero()
+    } else {
+        secondary_block_reward(
+            consensus,
+            target.number(),
+            inputs.target_epoch,
+            inputs.target_parent_dao.clone(),
+        )?
+    };
+
+    let total = txs_fees
+        .safe_add(proposal_reward)?
+        .safe_add(primary)?
+        .safe_add(secondary)?;
+
+    Ok(BlockReward {
+        total,
+        primary,
+        secondary,
+        tx_fee: txs_fees,
+        proposal_reward,
+    })
+}
@@ -1,17 +1,21 @@
 use ckb_chain_spec::consensus::{Consensus, ConsensusBuilder, ProposalWindow};
+use ckb_dao_utils::pack_dao_data;
 use ckb_db::RocksDB;
 use ckb_db_schema::COLUMNS;
 use ckb_occupied_capacity::IntoCapacity;
 use ckb_store::{ChainDB, ChainStore};
 use ckb_types::{
-    core::{BlockBuilder, BlockExt, EpochNumberWithFraction, HeaderBuilder, TransactionBuilder},
+    core::{
+        BlockBuilder, BlockExt, Capacity, EpochNumberWithFraction, HeaderBuilder,
+        TransactionBuilder,
+    },
     packed::ProposalShortId,
     prelude::*,
 };
 use std::collections::HashSet;
 use tempfile::TempDir;
 
-use crate::RewardCalculator;
+use crate::{block_reward_offline, RewardCalculator, RewardInputs};
 
 #[test]
 fn get_proposal_ids_by_hash() {
@@ -321,3 +325,59 @@ fn test_proposal_reward() {
 
     assert_eq!(proposal_reward, expected.into_capacity());
 }
+
+#[test]
+fn test_block_reward_offline() {
+    // Default PROPOSER_REWARD_RATIO is Ratio(4, 10)
+    let consensus = Consensus::default();
+    let epoch = consensus.genesis_epoch_ext().to_owned();
+    let target = HeaderBuilder::default()
+        .number(1.pack())
+        .epoch(EpochNumberWithFraction::new(0, 1, epoch.length()).pack())
+        .build();
+    // half of the issued capacity is occupied
+    let target_parent_dao = pack_dao_data(
+        0,
+        1_000_000u32.into_capacity(),
+        Capacity::zero(),
+        500_000u32.into_capacity(),
+    );
+    let txs_fees = [100u32, 20, 33, 34].map(IntoCapacity::into_capacity);
+    let earliest_proposed_txs_fees = [10u32, 25].map(IntoCapacity::into_capacity);
+
+    let reward = block_reward_offline(
+        &consensus,
+        &RewardInputs {
+            target: &target,
+            target_parent_dao,
+            target_epoch: &epoch,
+            txs_fees: &txs_fees,
+            earliest_proposed_txs_fees: &earliest_proposed_txs_fees,
+        },
+    )
+    .unwrap();
+
+    let expected_tx_fee: u32 = [100u32, 20, 33, 34].iter().map(|x| x - x * 4 / 10).sum();
+    let expected_proposal_reward: u32 = [10u32, 25].iter().map(|x| x * 4 / 10).sum();
+    let expected_secondary = epoch
+        .secondary_block_issuance(1, consensus.secondary_epoch_reward())
+        .unwrap()
+        .as_u64()
+        / 2;
+    assert_eq!(reward.tx_fee, expected_tx_fee.into_capacity());
+    assert_eq!(
+        reward.proposal_reward,
+        expected_proposal_reward.into_capacity()
+    );
+    assert_eq!(reward.primary, epoch.block_reward(1).unwrap());
+    assert_eq!(reward.secondary, Capacity::shannons(expected_secondary));
+    assert_eq!(
+        reward.total,
+        reward
+            .tx_fee
+            .safe_add(reward.proposal_reward)
+            .and_then(|c| c.safe_add(reward.primary))
+            .and_then(|c| c.safe_add(reward.secondary))
+            .unwrap()
+    );
+}
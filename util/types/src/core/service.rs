@@ -2,7 +2,8 @@
 //!
 //! A CKB service acts as an actor, which processes requests from a channel and sends back the
 //! response via one shot channel.
-use crate::core::{Capacity, Cycle, TransactionView};
+use crate::core::{BlockNumber, Capacity, Cycle, TransactionView};
+use crate::packed::Byte32;
 use ckb_channel::Sender;
 use std::sync::mpsc;
 /// Default channel size to send control signals.
@@ -44,3 +45,33 @@ pub struct PoolTransactionEntry {
     /// The unix timestamp when entering the Txpool, unit: Millisecond
     pub timestamp: u64,
 }
+
+/// The block verification phase
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VerificationPhase {
+    /// Verification without the chain context, done before the block is stored
+    NonContextual,
+    /// Verification with the chain context, done when the block is attached to the main chain
+    Contextual,
+}
+
+/// Notify block rejection, a structured record of the verification error for analytics
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockRejection {
+    /// The rejected block number
+    pub number: BlockNumber,
+    /// The rejected block hash
+    pub hash: Byte32,
+    /// The phase which rejects the block
+    pub phase: VerificationPhase,
+    /// The general category of the error, e.g. `Header`, `Block`
+    pub error_kind: String,
+    /// The category of the header or block error, e.g. `Pow`, `Commit`
+    pub error_sub_kind: Option<String>,
+    /// The index of the offending transaction, if applicable
+    pub tx_index: Option<u32>,
+    /// The general category of the offending transaction's error, e.g. `Script`, `OutPoint`
+    pub tx_error_kind: Option<String>,
+    /// The full error message
+    pub message: String,
+}
@@ -131,6 +131,26 @@ pub enum TxStatus {
     Rejected(String),
 }
 
+/// The reason why a transaction is not selected into the block template
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExclusionReason {
+    /// The transaction is selected into the block template.
+    Included,
+    /// The node has not seen the transaction.
+    Unknown,
+    /// The transaction has been rejected by the pool, e.g. its fee rate is below
+    /// `min_fee_rate` or it spends an immature cellbase.
+    Rejected(String),
+    /// The transaction is pending, it has not been proposed yet.
+    NotProposed,
+    /// The transaction has been proposed, but the proposal window is not reached yet.
+    InProposalGap,
+    /// The transaction depends on a transaction which can not be committed yet.
+    DependencyExcluded(Byte32),
+    /// The block template is full, the transaction exceeds the size or cycles budget.
+    OverBudget,
+}
+
 /// Tx-pool entry info
 #[derive(Debug, PartialEq, Eq)]
 pub struct TxEntryInfo {
@@ -361,6 +381,17 @@ pub struct TxPoolInfo {
     pub verify_queue_size: usize,
 }
 
+/// The verification progress of the transactions in the verify queue.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct VerifyQueueInfo {
+    /// Count of transactions waiting for a verify worker.
+    pub fresh_size: usize,
+    /// Count of transactions being verified.
+    pub verifying_size: usize,
+    /// Count of verified transactions waiting to be removed from the queue.
+    pub completed_size: usize,
+}
+
 /// A struct as a sorted key in tx-pool
 #[derive(Eq, PartialEq, Clone, Debug, Default)]
 pub struct AncestorsScoreSortKey {
@@ -1,3 +1,6 @@
+use crate::proposal_window_cache::{load_block_proposals, BlockProposals, ProposalWindowCache};
+use crate::time_relative_cache::TimeRelativeCache;
+use crate::uncle_inclusion_cache::UncleInclusionCache;
 use crate::uncles_verifier::{UncleProvider, UnclesVerifier};
 use ckb_async_runtime::Handle;
 use ckb_chain_spec::{
@@ -27,7 +30,8 @@ use ckb_verification::cache::{
 };
 use ckb_verification::{
     BlockErrorKind, CellbaseError, CommitError, ContextualTransactionVerifier,
-    DaoScriptSizeVerifier, TimeRelativeTransactionVerifier, UnknownParentError,
+    DaoScriptSizeVerifier, InvalidChainRootError, TimeRelativeTransactionVerifier,
+    UnknownParentError,
 };
 use ckb_verification::{BlockTransactionsError, EpochError, TxVerifyEnv};
 use ckb_verification_traits::Switch;
@@ -92,16 +96,39 @@ impl<CS: ChainStore> ConsensusProvider for VerifyContext<CS> {
 pub struct UncleVerifierContext<'a, 'b, CS> {
     epoch: &'b EpochExt,
     context: &'a VerifyContext<CS>,
+    cache: Option<&'a UncleInclusionCache>,
 }
 
 impl<'a, 'b, CS: ChainStore> UncleVerifierContext<'a, 'b, CS> {
     pub(crate) fn new(context: &'a VerifyContext<CS>, epoch: &'b EpochExt) -> Self {
-        UncleVerifierContext { epoch, context }
+        UncleVerifierContext {
+            epoch,
+            context,
+            cache: None,
+        }
+    }
+
+    /// Same as `new`, but the inclusion lookups are read through `cache`
+    pub(crate) fn with_cache(
+        context: &'a VerifyContext<CS>,
+        epoch: &'b EpochExt,
+        cache: &'a UncleInclusionCache,
+    ) -> Self {
+        UncleVerifierContext {
+            epoch,
+            context,
+            cache: Some(cache),
+        }
     }
 }
 
 impl<'a, 'b, CS: ChainStore> UncleProvider for UncleVerifierContext<'a, 'b, CS> {
     fn double_inclusion(&self, hash: &Byte32) -> bool {
+        if let Some(cache) = self.cache {
+            return cache
+                .get_or_load(self.context.store.as_ref(), hash)
+                .is_included();
+        }
         self.context.store.get_block_number(hash).is_some() || self.context.store.is_uncle(hash)
     }
 
@@ -110,6 +137,14 @@ impl<'a, 'b, CS: ChainStore> UncleProvider for UncleVerifierContext<'a, 'b, CS>
         let uncle_number = uncle.number();
         let store = &self.context.store;
 
+        if let Some(cache) = self.cache {
+            return cache
+                .get_or_load(store.as_ref(), &parent_hash)
+                .number()
+                .map(|number| (number + 1) == uncle_number)
+                .unwrap_or(false);
+        }
+
         if store.get_block_number(&parent_hash).is_some() {
             return store
                 .get_block_header(&parent_hash)
@@ -136,11 +171,36 @@ impl<'a, 'b, CS: ChainStore> UncleProvider for UncleVerifierContext<'a, 'b, CS>
 pub struct TwoPhaseCommitVerifier<'a, CS> {
     context: &'a VerifyContext<CS>,
     block: &'a BlockView,
+    cache: Option<&'a ProposalWindowCache>,
 }
 
 impl<'a, CS: ChainStore + VersionbitsIndexer> TwoPhaseCommitVerifier<'a, CS> {
     pub fn new(context: &'a VerifyContext<CS>, block: &'a BlockView) -> Self {
-        TwoPhaseCommitVerifier { context, block }
+        TwoPhaseCommitVerifier {
+            context,
+            block,
+            cache: None,
+        }
+    }
+
+    /// Same as `new`, but the proposals of the ancestors are read through `cache`
+    pub fn with_cache(
+        context: &'a VerifyContext<CS>,
+        block: &'a BlockView,
+        cache: &'a ProposalWindowCache,
+    ) -> Self {
+        TwoPhaseCommitVerifier {
+            context,
+            block,
+            cache: Some(cache),
+        }
+    }
+
+    fn block_proposals(&self, block_hash: &Byte32) -> Option<BlockProposals> {
+        match self.cache {
+            Some(cache) => cache.get_or_load(self.context.store.as_ref(), block_hash),
+            None => load_block_proposals(self.context.store.as_ref(), block_hash),
+        }
     }
 
     pub fn verify(&self) -> Result<(), Error> {
@@ -161,26 +221,16 @@ impl<'a, CS: ChainStore + VersionbitsIndexer> TwoPhaseCommitVerifier<'a, CS> {
         let mut proposal_txs_ids = HashSet::new();
 
         while proposal_end >= proposal_start {
-            let header = self
-                .context
-                .store
-                .get_block_header(&block_hash)
+            let proposals = self
+                .block_proposals(&block_hash)
                 .ok_or(CommitError::AncestorNotFound)?;
-            if header.is_genesis() {
+            if proposals.is_genesis {
                 break;
             }
 
-            if let Some(ids) = self.context.store.get_block_proposal_txs_ids(&block_hash) {
-                proposal_txs_ids.extend(ids);
-            }
-            if let Some(uncles) = self.context.store.get_block_uncles(&block_hash) {
-                uncles
-                    .data()
-                    .into_iter()
-                    .for_each(|uncle| proposal_txs_ids.extend(uncle.proposals()));
-            }
+            proposal_txs_ids.extend(proposals.ids.iter().cloned());
 
-            block_hash = header.data().raw().parent_hash();
+            block_hash = proposals.parent_hash;
             proposal_end -= 1;
         }
 
@@ -262,10 +312,8 @@ impl<'a, 'b, CS: ChainStore + VersionbitsIndexer> RewardVerifier<'a, 'b, CS> {
             if cellbase
                 .transaction
                 .outputs()
-                .get(0)
-                .expect("cellbase should have output")
-                .lock()
-                != target_lock
+                .into_iter()
+                .any(|output| output.lock() != target_lock)
             {
                 return Err((CellbaseError::InvalidRewardTarget).into());
             }
@@ -298,6 +346,14 @@ impl<'a, 'b, 'c, CS: ChainStore + VersionbitsIndexer> DaoHeaderVerifier<'a, 'b,
     }
 
     pub fn verify(&self) -> Result<(), Error> {
+        if self.dao_field()? != self.header.dao() {
+            return Err((BlockErrorKind::InvalidDAO).into());
+        }
+        Ok(())
+    }
+
+    // The dao field calculated from the parent and the resolved transactions
+    fn dao_field(&self) -> Result<Byte32, Error> {
         let dao = DaoCalculator::new(
             &self.context.consensus,
             &self.context.store.borrow_as_data_loader(),
@@ -312,11 +368,7 @@ impl<'a, 'b, 'c, CS: ChainStore + VersionbitsIndexer> DaoHeaderVerifier<'a, 'b,
             );
             e
         })?;
-
-        if dao != self.header.dao() {
-            return Err((BlockErrorKind::InvalidDAO).into());
-        }
-        Ok(())
+        Ok(dao)
     }
 }
 
@@ -326,6 +378,8 @@ struct BlockTxsVerifier<'a, 'b, CS> {
     handle: &'a Handle,
     txs_verify_cache: &'a Arc<RwLock<TxVerificationCache>>,
     parent: &'b HeaderView,
+    deterministic: bool,
+    time_relative_cache: Option<&'a TimeRelativeCache>,
 }
 
 impl<'a, 'b, CS: ChainStore + VersionbitsIndexer + 'static> BlockTxsVerifier<'a, 'b, CS> {
@@ -335,6 +389,7 @@ impl<'a, 'b, CS: ChainStore + VersionbitsIndexer + 'static> BlockTxsVerifier<'a,
         handle: &'a Handle,
         txs_verify_cache: &'a Arc<RwLock<TxVerificationCache>>,
         parent: &'b HeaderView,
+        deterministic: bool,
     ) -> Self {
         BlockTxsVerifier {
             context,
@@ -342,9 +397,19 @@ impl<'a, 'b, CS: ChainStore + VersionbitsIndexer + 'static> BlockTxsVerifier<'a,
             handle,
             txs_verify_cache,
             parent,
+            deterministic,
+            time_relative_cache: None,
         }
     }
 
+    // Under `skip_script_verify`, a transaction completed in the verification cache which
+    // already passed the time-relative verification in this very block reuses the cached
+    // cycles without the recheck.
+    fn time_relative_cache(mut self, cache: Option<&'a TimeRelativeCache>) -> Self {
+        self.time_relative_cache = cache;
+        self
+    }
+
     fn fetched_cache(&self, rtxs: &'a [Arc<ResolvedTransaction>]) -> HashMap<Byte32, CacheEntry> {
         let (sender, receiver) = oneshot::channel();
         let txs_verify_cache = Arc::clone(self.txs_verify_cache);
@@ -376,12 +441,19 @@ impl<'a, 'b, CS: ChainStore + VersionbitsIndexer + 'static> BlockTxsVerifier<'a,
 
     fn update_cache(&self, ret: Vec<(Byte32, Completed)>) {
         let txs_verify_cache = Arc::clone(self.txs_verify_cache);
-        self.handle.spawn(async move {
+        let update = async move {
             let mut guard = txs_verify_cache.write().await;
             for (k, v) in ret {
                 guard.put(k, v);
             }
-        });
+        };
+        // The deterministic mode waits for the cache update, the cache state is settled when
+        // `verify` returns.
+        if self.deterministic {
+            self.handle.block_on(update);
+        } else {
+            self.handle.spawn(update);
+        }
     }
 
     pub fn verify(
@@ -398,41 +470,29 @@ impl<'a, 'b, CS: ChainStore + VersionbitsIndexer + 'static> BlockTxsVerifier<'a,
         };
 
         let tx_env = Arc::new(TxVerifyEnv::new_commit(&self.header));
+        let header_hash = self.header.hash();
+        let time_relative_verified = |wtx_hash: &Byte32| {
+            skip_script_verify
+                && self
+                    .time_relative_cache
+                    .is_some_and(|cache| cache.contains(&header_hash, wtx_hash))
+        };
 
         // make verifiers orthogonal
-        let ret = resolved
-            .par_iter()
-            .enumerate()
-            .map(|(index, tx)| {
-                let wtx_hash = tx.transaction.witness_hash();
+        let verify_tx = |(index, tx): (usize, &Arc<ResolvedTransaction>)| {
+            let wtx_hash = tx.transaction.witness_hash();
 
-                if let Some(completed) = fetched_cache.get(&wtx_hash) {
-                    TimeRelativeTransactionVerifier::new(
-                            Arc::clone(tx),
-                            Arc::clone(&self.context.consensus),
-                            self.context.store.as_data_loader(),
-                            Arc::clone(&tx_env),
-                        )
-                        .verify()
-                        .map_err(|error| {
-                            BlockTransactionsError {
-                                index: index as u32,
-                                error,
-                            }
-                            .into()
-                        })
-                        .map(|_| (wtx_hash, *completed))
+            if let Some(completed) = fetched_cache.get(&wtx_hash) {
+                if time_relative_verified(&wtx_hash) {
+                    Ok((wtx_hash, *completed))
                 } else {
-                    ContextualTransactionVerifier::new(
+                    TimeRelativeTransactionVerifier::new(
                         Arc::clone(tx),
                         Arc::clone(&self.context.consensus),
                         self.context.store.as_data_loader(),
                         Arc::clone(&tx_env),
                     )
-                    .verify(
-                        self.context.consensus.max_block_cycles(),
-                        skip_script_verify,
-                    )
+                    .verify()
                     .map_err(|error| {
                         BlockTransactionsError {
                             index: index as u32,
@@ -440,20 +500,66 @@ impl<'a, 'b, CS: ChainStore + VersionbitsIndexer + 'static> BlockTxsVerifier<'a,
                         }
                         .into()
                     })
-                    .map(|completed| (wtx_hash, completed))
-                }.and_then(|result| {
-                    if self.context.consensus.rfc0044_active(self.parent.epoch().number()) {
-                        DaoScriptSizeVerifier::new(
-                            Arc::clone(tx),
-                            Arc::clone(&self.context.consensus),
-                            self.context.store.as_data_loader(),
-                        ).verify()?;
+                    .map(|_| (wtx_hash, *completed))
+                }
+            } else {
+                ContextualTransactionVerifier::new(
+                    Arc::clone(tx),
+                    Arc::clone(&self.context.consensus),
+                    self.context.store.as_data_loader(),
+                    Arc::clone(&tx_env),
+                )
+                .verify(
+                    self.context.consensus.max_block_cycles(),
+                    skip_script_verify,
+                )
+                .map_err(|error| {
+                    BlockTransactionsError {
+                        index: index as u32,
+                        error,
                     }
-                    Ok(result)
+                    .into()
                 })
+                .map(|completed| (wtx_hash, completed))
+            }
+            .and_then(|result| {
+                if self
+                    .context
+                    .consensus
+                    .rfc0044_active(self.parent.epoch().number())
+                {
+                    DaoScriptSizeVerifier::new(
+                        Arc::clone(tx),
+                        Arc::clone(&self.context.consensus),
+                        self.context.store.as_data_loader(),
+                    )
+                    .verify()?;
+                }
+                Ok(result)
             })
-            .skip(1) // skip cellbase tx
-            .collect::<Result<Vec<(Byte32, Completed)>, Error>>()?;
+        };
+        let ret = if self.deterministic {
+            resolved
+                .iter()
+                .enumerate()
+                .map(verify_tx)
+                .skip(1) // skip cellbase tx
+                .collect::<Result<Vec<(Byte32, Completed)>, Error>>()?
+        } else {
+            resolved
+                .par_iter()
+                .enumerate()
+                .map(verify_tx)
+                .skip(1) // skip cellbase tx
+                .collect::<Result<Vec<(Byte32, Completed)>, Error>>()?
+        };
+
+        if let Some(cache) = self.time_relative_cache {
+            cache.insert(
+                &header_hash,
+                ret.iter().map(|(wtx_hash, _)| wtx_hash.clone()),
+            );
+        }
 
         let sum: Cycle = ret.iter().map(|(_, cache_entry)| cache_entry.cycles).sum();
         let cache_entires = ret
@@ -556,7 +662,7 @@ impl<'a, 'b, CS: ChainStore + VersionbitsIndexer, MS: MMRStore<HeaderDigest>>
                 if extension.is_empty() {
                     return Err(BlockErrorKind::EmptyBlockExtension.into());
                 }
-                if extension.len() > 96 {
+                if extension.len() as u64 > self.context.consensus.max_block_extension_bytes() {
                     return Err(BlockErrorKind::ExceededMaximumBlockExtensionBytes.into());
                 }
                 if mmr_active {
@@ -564,15 +670,17 @@ impl<'a, 'b, CS: ChainStore + VersionbitsIndexer, MS: MMRStore<HeaderDigest>>
                         return Err(BlockErrorKind::InvalidBlockExtension.into());
                     }
 
-                    let chain_root = self
-                        .chain_root_mmr
-                        .get_root()
-                        .map_err(|e| InternalErrorKind::MMR.other(e))?;
-                    let actual_root_hash = chain_root.calc_mmr_hash();
+                    let actual_root_hash = self.chain_root_hash()?;
                     let expected_root_hash =
                         Byte32::new_unchecked(extension.raw_data().slice(..32));
                     if actual_root_hash != expected_root_hash {
-                        return Err(BlockErrorKind::InvalidChainRoot.into());
+                        return Err(InvalidChainRootError {
+                            block_number: block.number(),
+                            block_hash: block.hash(),
+                            expected: expected_root_hash,
+                            actual: actual_root_hash,
+                        }
+                        .into());
                     }
                 }
             }
@@ -587,6 +695,43 @@ impl<'a, 'b, CS: ChainStore + VersionbitsIndexer, MS: MMRStore<HeaderDigest>>
         }
         Ok(())
     }
+
+    // The chain root hash which the block extension commits to
+    fn chain_root_hash(&self) -> Result<Byte32, Error> {
+        let chain_root = self
+            .chain_root_mmr
+            .get_root()
+            .map_err(|e| InternalErrorKind::MMR.other(e))?;
+        Ok(chain_root.calc_mmr_hash())
+    }
+}
+
+/// The intermediate results of a block's contextual verification, see
+/// [`ContextualBlockVerifier::verify_with_trace`].
+#[derive(Debug, Default)]
+pub struct VerificationTrace {
+    /// The result of every enabled phase, in the verification order
+    pub phases: Vec<(&'static str, Result<(), Error>)>,
+    /// The cycles of every non-cellbase transaction, empty if any of them fails
+    pub tx_cycles: Vec<Cycle>,
+    /// The dao field calculated from the parent
+    pub dao: Option<Byte32>,
+    /// The reward target lock and the block reward finalized by this block
+    pub reward: Option<(Script, BlockReward)>,
+    /// The chain root hash which the block extension should commit to
+    pub chain_root: Option<Byte32>,
+}
+
+// Record how long a sub-verifier takes, keyed by `verifier`, it's a no-op when the metrics
+// service is disabled
+fn timed<T>(verifier: &str, verify: impl FnOnce() -> T) -> T {
+    let _timer = ckb_metrics::handle().map(|metrics| {
+        metrics
+            .ckb_chain_contextual_verifier_duration
+            .with_label_values(&[verifier])
+            .start_timer()
+    });
+    verify()
 }
 
 /// Context-dependent verification checks for block
@@ -604,6 +749,10 @@ pub struct ContextualBlockVerifier<'a, CS, MS> {
     handle: &'a Handle,
     txs_verify_cache: Arc<RwLock<TxVerificationCache>>,
     chain_root_mmr: &'a ChainRootMMR<MS>,
+    deterministic: bool,
+    proposal_window_cache: Option<Arc<ProposalWindowCache>>,
+    uncle_inclusion_cache: Option<Arc<UncleInclusionCache>>,
+    time_relative_cache: Option<Arc<TimeRelativeCache>>,
 }
 
 impl<'a, CS: ChainStore + VersionbitsIndexer + 'static, MS: MMRStore<HeaderDigest>>
@@ -623,17 +772,70 @@ impl<'a, CS: ChainStore + VersionbitsIndexer + 'static, MS: MMRStore<HeaderDiges
             switch,
             txs_verify_cache,
             chain_root_mmr,
+            deterministic: false,
+            proposal_window_cache: None,
+            uncle_inclusion_cache: None,
+            time_relative_cache: None,
         }
     }
 
-    /// Perform context-dependent verification checks for block
-    pub fn verify(
-        &'a self,
-        resolved: &'a [Arc<ResolvedTransaction>],
-        block: &'a BlockView,
-    ) -> Result<(Cycle, Vec<Completed>), Error> {
+    /// Read the proposal window through `cache`, which is meant to be shared by the
+    /// verification of consecutive blocks
+    pub fn proposal_window_cache(mut self, cache: Arc<ProposalWindowCache>) -> Self {
+        self.proposal_window_cache = Some(cache);
+        self
+    }
+
+    /// Read the uncle inclusion lookups through `cache`, which is meant to be shared by the
+    /// verification of consecutive blocks
+    pub fn uncle_inclusion_cache(mut self, cache: Arc<UncleInclusionCache>) -> Self {
+        self.uncle_inclusion_cache = Some(cache);
+        self
+    }
+
+    /// Remember the transactions passing the time-relative verification in `cache`, which is
+    /// meant to be shared by the verification of consecutive blocks
+    ///
+    /// When the script verification is disabled, a transaction completed in the verification
+    /// cache skips the time-relative recheck if it already passed it in the same block.
+    pub fn time_relative_cache(mut self, cache: Arc<TimeRelativeCache>) -> Self {
+        self.time_relative_cache = Some(cache);
+        self
+    }
+
+    fn uncle_verifier_context<'b>(
+        &'b self,
+        epoch: &'b EpochExt,
+    ) -> UncleVerifierContext<'b, 'b, CS> {
+        match self.uncle_inclusion_cache {
+            Some(ref cache) => UncleVerifierContext::with_cache(&self.context, epoch, cache),
+            None => UncleVerifierContext::new(&self.context, epoch),
+        }
+    }
+
+    fn two_phase_commit_verifier<'b>(
+        &'b self,
+        block: &'b BlockView,
+    ) -> TwoPhaseCommitVerifier<'b, CS> {
+        match self.proposal_window_cache {
+            Some(ref cache) => TwoPhaseCommitVerifier::with_cache(&self.context, block, cache),
+            None => TwoPhaseCommitVerifier::new(&self.context, block),
+        }
+    }
+
+    /// Verify the transactions one by one in the block order instead of in parallel, and the DAO
+    /// field before the reward instead of concurrently
+    ///
+    /// The result is the same either way, but only this mode reproduces the order of the
+    /// cycles accumulation and the verification cache updates, it's meant for conformance
+    /// tests, production keeps the parallel verification.
+    pub fn deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+
+    fn parent_and_epoch(&self, block: &BlockView) -> Result<(HeaderView, EpochExt), Error> {
         let parent_hash = block.data().header().raw().parent_hash();
-        let header = block.header();
         let parent = self
             .context
             .store
@@ -653,41 +855,172 @@ impl<'a, CS: ChainStore + VersionbitsIndexer + 'static, MS: MMRStore<HeaderDiges
                 })?
                 .epoch()
         };
+        Ok((parent, epoch_ext))
+    }
+
+    // The DAO field and the reward don't depend on each other, they are computed concurrently
+    // unless in the deterministic mode. The DAO error still takes precedence when both fail.
+    fn verify_dao_and_reward(
+        &self,
+        resolved: &[Arc<ResolvedTransaction>],
+        parent: &HeaderView,
+        header: &HeaderView,
+    ) -> Result<(), Error> {
+        let context = &self.context;
+        let switch = self.switch;
+        let verify_dao = || {
+            if switch.disable_daoheader() {
+                return Ok(());
+            }
+            timed("dao_header", || {
+                DaoHeaderVerifier::new(context, resolved, parent, header).verify()
+            })
+        };
+        let verify_reward = || {
+            if switch.disable_reward() {
+                return Ok(());
+            }
+            timed("reward", || {
+                RewardVerifier::new(context, resolved, parent).verify()
+            })
+        };
+
+        if self.deterministic {
+            verify_dao()?;
+            verify_reward()
+        } else {
+            let (dao, reward) = rayon::join(verify_dao, verify_reward);
+            dao.and(reward)
+        }
+    }
 
+    /// Perform context-dependent verification checks for block
+    pub fn verify(
+        &'a self,
+        resolved: &'a [Arc<ResolvedTransaction>],
+        block: &'a BlockView,
+    ) -> Result<(Cycle, Vec<Completed>), Error> {
+        let header = block.header();
+        let (parent, epoch_ext) = self.parent_and_epoch(block)?;
+
+        // This is the only header-level check done here, and it can't be skipped for blocks
+        // whose headers were accepted during header sync (e.g. promoted orphans): the
+        // `HeaderVerifier` used there only checks the epoch is well-formed and continuous,
+        // the epoch number and compact target are never checked against the `EpochExt`.
+        // Timestamp, version and PoW checks are not repeated here at all.
         if !self.switch.disable_epoch() {
-            EpochVerifier::new(&epoch_ext, block).verify()?;
+            timed("epoch", || EpochVerifier::new(&epoch_ext, block).verify())?;
         }
 
         if !self.switch.disable_uncles() {
-            let uncle_verifier_context = UncleVerifierContext::new(&self.context, &epoch_ext);
-            UnclesVerifier::new(uncle_verifier_context, block).verify()?;
+            timed("uncles", || {
+                let uncle_verifier_context = self.uncle_verifier_context(&epoch_ext);
+                UnclesVerifier::new(uncle_verifier_context, block).verify()
+            })?;
         }
 
         if !self.switch.disable_two_phase_commit() {
-            TwoPhaseCommitVerifier::new(&self.context, block).verify()?;
+            timed("two_phase_commit", || {
+                self.two_phase_commit_verifier(block).verify()
+            })?;
+        }
+
+        self.verify_dao_and_reward(resolved, &parent, &header)?;
+
+        if !self.switch.disable_extension() {
+            timed("extension", || {
+                BlockExtensionVerifier::new(&self.context, self.chain_root_mmr, &parent)
+                    .verify(block)
+            })?;
+        }
+
+        // Cellbase-only block, there's nothing for `BlockTxsVerifier` to verify,
+        // skip the cache fetching and the parallel verification setup.
+        if resolved.len() == 1 {
+            return Ok((0, vec![]));
+        }
+
+        let ret = timed("block_txs", || {
+            BlockTxsVerifier::new(
+                self.context.clone(),
+                header,
+                self.handle,
+                &self.txs_verify_cache,
+                &parent,
+                self.deterministic,
+            )
+            .time_relative_cache(self.time_relative_cache.as_deref())
+            .verify(resolved, self.switch.disable_script())
+        })?;
+        Ok(ret)
+    }
+
+    /// Run every enabled phase of `verify` and collect the intermediate results, for
+    /// investigating a consensus disagreement with another implementation
+    ///
+    /// Unlike `verify`, a failing phase doesn't stop the following ones. The transactions are
+    /// verified one by one as in the deterministic mode. This is a heavy diagnostic path, never
+    /// use it to verify blocks in the main loop.
+    pub fn verify_with_trace(
+        &'a self,
+        resolved: &'a [Arc<ResolvedTransaction>],
+        block: &'a BlockView,
+    ) -> Result<VerificationTrace, Error> {
+        let (parent, epoch_ext) = self.parent_and_epoch(block)?;
+        let mut trace = VerificationTrace::default();
+
+        if !self.switch.disable_epoch() {
+            let result = EpochVerifier::new(&epoch_ext, block).verify();
+            trace.phases.push(("epoch", result));
+        }
+
+        if !self.switch.disable_uncles() {
+            let uncle_verifier_context = self.uncle_verifier_context(&epoch_ext);
+            let result = UnclesVerifier::new(uncle_verifier_context, block).verify();
+            trace.phases.push(("uncles", result));
+        }
+
+        if !self.switch.disable_two_phase_commit() {
+            let result = self.two_phase_commit_verifier(block).verify();
+            trace.phases.push(("two_phase_commit", result));
         }
 
         if !self.switch.disable_daoheader() {
-            DaoHeaderVerifier::new(&self.context, resolved, &parent, &block.header()).verify()?;
+            let header = block.header();
+            let verifier = DaoHeaderVerifier::new(&self.context, resolved, &parent, &header);
+            trace.dao = verifier.dao_field().ok();
+            trace.phases.push(("dao_header", verifier.verify()));
         }
 
         if !self.switch.disable_reward() {
-            RewardVerifier::new(&self.context, resolved, &parent).verify()?;
+            trace.reward = self.context.finalize_block_reward(&parent).ok();
+            let result = RewardVerifier::new(&self.context, resolved, &parent).verify();
+            trace.phases.push(("reward", result));
         }
 
         if !self.switch.disable_extension() {
-            BlockExtensionVerifier::new(&self.context, self.chain_root_mmr, &parent)
-                .verify(block)?;
+            let verifier = BlockExtensionVerifier::new(&self.context, self.chain_root_mmr, &parent);
+            trace.chain_root = verifier.chain_root_hash().ok();
+            trace.phases.push(("extension", verifier.verify(block)));
         }
 
-        let ret = BlockTxsVerifier::new(
-            self.context.clone(),
-            header,
-            self.handle,
-            &self.txs_verify_cache,
-            &parent,
-        )
-        .verify(resolved, self.switch.disable_script())?;
-        Ok(ret)
+        if resolved.len() > 1 {
+            let result = BlockTxsVerifier::new(
+                self.context.clone(),
+                block.header(),
+                self.handle,
+                &self.txs_verify_cache,
+                &parent,
+                true,
+            )
+            .time_relative_cache(self.time_relative_cache.as_deref())
+            .verify(resolved, self.switch.disable_script())
+            .map(|(_, completed)| {
+                trace.tx_cycles = completed.iter().map(|completed| completed.cycles).collect();
+            });
+            trace.phases.push(("block_txs", result));
+        }
+
+        Ok(trace)
     }
 }
@@ -2,9 +2,17 @@
 //!
 //! This crate implements CKB contextual verification by newtypes abstraction struct
 mod contextual_block_verifier;
+mod proposal_window_cache;
 #[cfg(test)]
 mod tests;
+mod time_relative_cache;
+mod uncle_inclusion_cache;
 mod uncles_verifier;
 
-pub use crate::contextual_block_verifier::{ContextualBlockVerifier, VerifyContext};
+pub use crate::contextual_block_verifier::{
+    ContextualBlockVerifier, VerificationTrace, VerifyContext,
+};
+pub use crate::proposal_window_cache::ProposalWindowCache;
+pub use crate::time_relative_cache::TimeRelativeCache;
+pub use crate::uncle_inclusion_cache::UncleInclusionCache;
 const LOG_TARGET: &str = "ckb_chain";
@@ -0,0 +1,90 @@
+//! Memoize the proposals every ancestor contributes to the proposal window.
+use ckb_store::ChainStore;
+use ckb_types::packed::{Byte32, ProposalShortId};
+use ckb_util::Mutex;
+use lru::LruCache;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+// enough for the proposal windows of a few forks, the mainnet farthest is 10
+const DEFAULT_CAPACITY: usize = 128;
+
+/// The proposals of a block and its uncles, and its parent to continue the walk.
+#[derive(Clone)]
+pub(crate) struct BlockProposals {
+    pub(crate) parent_hash: Byte32,
+    pub(crate) is_genesis: bool,
+    pub(crate) ids: Arc<HashSet<ProposalShortId>>,
+}
+
+/// An LRU cache of the proposals contributed by each block, shared by the verification of
+/// consecutive blocks, whose proposal windows overlap almost entirely.
+///
+/// A block's proposals and parent never change, so the entries are keyed by block hash and
+/// stay valid across reorgs, the main chain walk itself still starts from the store.
+pub struct ProposalWindowCache {
+    inner: Mutex<LruCache<Byte32, BlockProposals>>,
+}
+
+impl Default for ProposalWindowCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl ProposalWindowCache {
+    /// Create a cache holding the proposals of at most `capacity` blocks
+    pub fn new(capacity: usize) -> Self {
+        ProposalWindowCache {
+            inner: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    /// The number of blocks cached
+    pub fn len(&self) -> usize {
+        self.inner.lock().len()
+    }
+
+    /// Returns true if no block is cached
+    pub fn is_empty(&self) -> bool {
+        self.inner.lock().is_empty()
+    }
+
+    pub(crate) fn get_or_load<CS: ChainStore>(
+        &self,
+        store: &CS,
+        block_hash: &Byte32,
+    ) -> Option<BlockProposals> {
+        if let Some(proposals) = self.inner.lock().get(block_hash) {
+            return Some(proposals.clone());
+        }
+        let proposals = load_block_proposals(store, block_hash)?;
+        self.inner.lock().put(block_hash.clone(), proposals.clone());
+        Some(proposals)
+    }
+}
+
+/// Read the proposals of a block and its uncles from the store
+pub(crate) fn load_block_proposals<CS: ChainStore>(
+    store: &CS,
+    block_hash: &Byte32,
+) -> Option<BlockProposals> {
+    let header = store.get_block_header(block_hash)?;
+    let mut ids = HashSet::new();
+    if !header.is_genesis() {
+        if let Some(proposals) = store.get_block_proposal_txs_ids(block_hash) {
+            ids.extend(proposals);
+        }
+        if let Some(uncles) = store.get_block_uncles(block_hash) {
+            uncles
+                .data()
+                .into_iter()
+                .for_each(|uncle| ids.extend(uncle.proposals()));
+        }
+    }
+    Some(BlockProposals {
+        parent_hash: header.parent_hash(),
+        is_genesis: header.is_genesis(),
+        ids: Arc::new(ids),
+    })
+}
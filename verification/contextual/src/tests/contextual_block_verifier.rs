@@ -1,25 +1,44 @@
-use super::super::contextual_block_verifier::{EpochVerifier, TwoPhaseCommitVerifier};
-use crate::contextual_block_verifier::{RewardVerifier, VerifyContext};
+use super::super::contextual_block_verifier::{
+    BlockExtensionVerifier, EpochVerifier, TwoPhaseCommitVerifier,
+};
+use crate::contextual_block_verifier::{ContextualBlockVerifier, RewardVerifier, VerifyContext};
+use crate::{ProposalWindowCache, TimeRelativeCache};
 use ckb_chain::{start_chain_services, ChainController};
 use ckb_chain_spec::consensus::{Consensus, ConsensusBuilder};
-use ckb_error::assert_error_eq;
+use ckb_chain_spec::versionbits::VersionbitsIndexer;
+use ckb_constant::hardfork::mainnet;
+use ckb_dao::DaoCalculator;
+use ckb_db::{
+    iter::{DBIter, IteratorMode},
+    DBPinnableSlice,
+};
+use ckb_db_schema::Col;
+use ckb_error::{assert_error_eq, Error};
 use ckb_shared::{Shared, SharedBuilder};
-use ckb_store::{ChainDB, ChainStore};
-use ckb_test_chain_utils::always_success_cell;
+use ckb_store::{ChainDB, ChainStore, Freezer, StoreCache};
+use ckb_test_chain_utils::{always_success_cell, calculate_reward, MockChain, MockStore};
 use ckb_types::{
     bytes::Bytes,
     core::{
-        capacity_bytes, cell::ResolvedTransaction, BlockBuilder, BlockNumber, BlockView, Capacity,
-        EpochExt, EpochNumberWithFraction, HeaderBuilder, HeaderView, TransactionBuilder,
-        TransactionView, UncleBlockView,
+        capacity_bytes,
+        cell::{resolve_transaction, BlockCellProvider, OverlayCellProvider, ResolvedTransaction},
+        BlockBuilder, BlockNumber, BlockView, Capacity, EpochExt, EpochNumberWithFraction,
+        HeaderBuilder, HeaderView, TransactionBuilder, TransactionView, UncleBlockView,
     },
     packed::{Byte32, CellDep, CellInput, CellOutputBuilder, OutPoint, ProposalShortId, Script},
     prelude::*,
-    utilities::DIFF_TWO,
+    utilities::{merkle_mountain_range::ChainRootMMR, DIFF_TWO},
+};
+use ckb_verification::cache::{init_cache, Completed};
+use ckb_verification::{
+    BlockError, BlockErrorKind, BlockTransactionsError, CellbaseError, CommitError, EpochError,
+    InvalidChainRootError, TransactionError,
 };
-use ckb_verification::{CellbaseError, CommitError, EpochError};
 use ckb_verification_traits::Switch;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use tokio::sync::RwLock;
 
 fn gen_block(
     parent_header: &HeaderView,
@@ -157,6 +176,60 @@ pub fn test_should_have_no_output_in_cellbase_no_finalization_target() {
     assert_error_eq!(ret.unwrap_err(), CellbaseError::InvalidRewardTarget,);
 }
 
+#[test]
+fn test_cellbase_with_multiple_outputs() {
+    let (_chain, shared) = start_chain(None);
+    let consensus = shared.consensus();
+    let (_, _, always_success_script) = always_success_cell();
+
+    let mock_store = MockStore::new(&consensus.genesis_block().header(), shared.store());
+    let mut chain = MockChain::new(consensus.genesis_block().header(), consensus);
+    for _ in 0..=consensus.finalization_delay_length() {
+        chain.gen_empty_block(&mock_store);
+    }
+    let parent = chain.tip_header();
+    let reward = calculate_reward(&mock_store, consensus, &parent);
+    let half = Capacity::shannons(reward.as_u64() / 2);
+    let rest = reward.safe_sub(half).unwrap();
+
+    let verify = |second_lock: Script| {
+        let cellbase = TransactionBuilder::default()
+            .input(CellInput::new_cellbase_input(parent.number() + 1))
+            .witness(always_success_script.clone().into_witness())
+            .output(
+                CellOutputBuilder::default()
+                    .capacity(half.pack())
+                    .lock(always_success_script.clone())
+                    .build(),
+            )
+            .output(
+                CellOutputBuilder::default()
+                    .capacity(rest.pack())
+                    .lock(second_lock)
+                    .build(),
+            )
+            .outputs_data(vec![Bytes::new().pack(); 2])
+            .build();
+        let cellbase = ResolvedTransaction {
+            transaction: cellbase,
+            resolved_cell_deps: vec![],
+            resolved_inputs: vec![],
+            resolved_dep_groups: vec![],
+        };
+        let context = VerifyContext::new(
+            Arc::new(mock_store.store().clone()),
+            shared.cloned_consensus(),
+        );
+        RewardVerifier::new(&context, &[Arc::new(cellbase)], &parent).verify()
+    };
+
+    assert!(verify(always_success_script.clone()).is_ok());
+    assert_error_eq!(
+        verify(Script::default()).unwrap_err(),
+        CellbaseError::InvalidRewardTarget,
+    );
+}
+
 #[test]
 fn test_epoch_number() {
     let actual_epoch = EpochNumberWithFraction::new(2, 0, 1);
@@ -273,6 +346,142 @@ fn test_proposal() {
     assert!(verifier.verify().is_ok());
 }
 
+// Counts the reads that reach the db, the store cache is bypassed
+pub(super) struct CountingStore {
+    pub(super) inner: ChainDB,
+    pub(super) reads: AtomicUsize,
+}
+
+impl CountingStore {
+    pub(super) fn take_reads(&self) -> usize {
+        self.reads.swap(0, Ordering::SeqCst)
+    }
+}
+
+impl ChainStore for CountingStore {
+    fn cache(&self) -> Option<&StoreCache> {
+        None
+    }
+
+    fn freezer(&self) -> Option<&Freezer> {
+        self.inner.freezer()
+    }
+
+    fn get(&self, col: Col, key: &[u8]) -> Option<DBPinnableSlice> {
+        self.reads.fetch_add(1, Ordering::SeqCst);
+        self.inner.get(col, key)
+    }
+
+    fn get_iter(&self, col: Col, mode: IteratorMode) -> DBIter {
+        self.inner.get_iter(col, mode)
+    }
+}
+
+impl VersionbitsIndexer for CountingStore {
+    fn block_epoch_index(&self, block_hash: &Byte32) -> Option<Byte32> {
+        ChainStore::get_block_epoch_index(self, block_hash)
+    }
+
+    fn epoch_ext(&self, index: &Byte32) -> Option<EpochExt> {
+        ChainStore::get_epoch_ext(self, index)
+    }
+
+    fn block_header(&self, block_hash: &Byte32) -> Option<HeaderView> {
+        ChainStore::get_block_header(self, block_hash)
+    }
+
+    fn cellbase(&self, block_hash: &Byte32) -> Option<TransactionView> {
+        ChainStore::get_cellbase(self, block_hash)
+    }
+}
+
+#[test]
+fn test_proposal_window_cache() {
+    let (
+        chain_controller,
+        shared,
+        mut prev_tx_hash,
+        always_success_script,
+        always_success_out_point,
+    ) = setup_env();
+
+    let mut txs20 = Vec::new();
+    for _ in 0..20 {
+        let tx = create_transaction(
+            &prev_tx_hash,
+            &always_success_script,
+            &always_success_out_point,
+        );
+        txs20.push(tx.clone());
+        prev_tx_hash = tx.data().calc_tx_hash();
+    }
+
+    let proposal_window = shared.consensus().tx_proposal_window();
+    let mut parent = shared
+        .store()
+        .get_block_header(&shared.store().get_block_hash(0).unwrap())
+        .unwrap();
+
+    // txs20 is proposed in block(1), every other block proposes a dummy id
+    let mut parents = Vec::new();
+    for number in 1..(proposal_window.farthest() * 3) {
+        let proposals = if number == 1 {
+            txs20
+                .iter()
+                .map(|tx| tx.data().proposal_short_id())
+                .collect()
+        } else {
+            vec![ProposalShortId::new([number as u8; 10])]
+        };
+        let block = gen_block(&parent, vec![], proposals, vec![]);
+        chain_controller
+            .blocking_process_block_with_switch(Arc::new(block.clone()), Switch::DISABLE_ALL)
+            .unwrap();
+        parent = block.header();
+        parents.push(parent.clone());
+    }
+
+    let store = Arc::new(CountingStore {
+        inner: shared.store().clone(),
+        reads: AtomicUsize::new(0),
+    });
+    let context = VerifyContext::new(Arc::clone(&store), shared.cloned_consensus());
+    let blocks: Vec<_> = parents
+        .iter()
+        .map(|parent| gen_block(parent, txs20.clone(), vec![], vec![]))
+        .collect();
+
+    store.take_reads();
+    let uncached: Vec<_> = blocks
+        .iter()
+        .map(|block| {
+            TwoPhaseCommitVerifier::new(&context, block)
+                .verify()
+                .map_err(|err| err.to_string())
+        })
+        .collect();
+    let uncached_reads = store.take_reads();
+
+    let cache = ProposalWindowCache::default();
+    let cached: Vec<_> = blocks
+        .iter()
+        .map(|block| {
+            TwoPhaseCommitVerifier::with_cache(&context, block, &cache)
+                .verify()
+                .map_err(|err| err.to_string())
+        })
+        .collect();
+    let cached_reads = store.take_reads();
+
+    assert_eq!(uncached, cached);
+    assert!(cached.iter().any(Result::is_ok));
+    assert!(cached.iter().any(Result::is_err));
+    assert!(
+        cached_reads < uncached_reads,
+        "cached reads {cached_reads} should be fewer than uncached reads {uncached_reads}"
+    );
+}
+
 #[test]
 fn test_uncle_proposal() {
     let (
@@ -349,3 +558,678 @@ fn test_uncle_proposal() {
     let verifier = TwoPhaseCommitVerifier::new(&context, &block);
     assert!(verifier.verify().is_ok());
 }
+
+#[test]
+fn test_deterministic_block_txs_verification() {
+    let (
+        _chain_controller,
+        shared,
+        genesis_tx_hash,
+        always_success_script,
+        always_success_out_point,
+    ) = setup_env();
+    let parent = shared.consensus().genesis_block().header();
+
+    let txs: Vec<TransactionView> = (1..=20)
+        .map(|index| {
+            TransactionBuilder::default()
+                .input(CellInput::new(
+                    OutPoint::new(genesis_tx_hash.clone(), index),
+                    0,
+                ))
+                .output(
+                    CellOutputBuilder::default()
+                        .capacity(capacity_bytes!(100).pack())
+                        .lock(always_success_script.clone())
+                        .build(),
+                )
+                .output_data(Bytes::new().pack())
+                .cell_dep(
+                    CellDep::new_builder()
+                        .out_point(always_success_out_point.clone())
+                        .build(),
+                )
+                .build()
+        })
+        .collect();
+    let block = gen_block(&parent, txs, vec![], vec![]);
+
+    let snapshot = Arc::clone(&shared.snapshot());
+    let block_cp = BlockCellProvider::new(&block).unwrap();
+    let cell_provider = OverlayCellProvider::new(&block_cp, snapshot.as_ref());
+    let mut seen_inputs = HashSet::new();
+    let resolved: Vec<Arc<ResolvedTransaction>> = block
+        .transactions()
+        .into_iter()
+        .map(|tx| {
+            Arc::new(
+                resolve_transaction(tx, &mut seen_inputs, &cell_provider, snapshot.as_ref())
+                    .unwrap(),
+            )
+        })
+        .collect();
+
+    let switch = Switch::DISABLE_EPOCH
+        | Switch::DISABLE_UNCLES
+        | Switch::DISABLE_TWO_PHASE_COMMIT
+        | Switch::DISABLE_DAOHEADER
+        | Switch::DISABLE_REWARD
+        | Switch::DISABLE_EXTENSION;
+    let chain_root_mmr = ChainRootMMR::new(0, snapshot.as_ref());
+    let verify = |deterministic: bool| {
+        let txs_verify_cache = Arc::new(RwLock::new(init_cache()));
+        let ret = ContextualBlockVerifier::new(
+            dummy_context(&shared),
+            shared.async_handle(),
+            switch,
+            Arc::clone(&txs_verify_cache),
+            &chain_root_mmr,
+        )
+        .deterministic(deterministic)
+        .verify(&resolved, &block)
+        .expect("verify block");
+        (ret, txs_verify_cache)
+    };
+
+    let ((cycles, completed), txs_verify_cache) = verify(true);
+    assert!(cycles > 0);
+    assert_eq!(completed.len(), 20);
+    // the cache is settled once the deterministic verification returns
+    let cached: Vec<_> = {
+        let cache = txs_verify_cache.blocking_read();
+        resolved
+            .iter()
+            .skip(1)
+            .map(|rtx| cache.peek(&rtx.transaction.witness_hash()).cloned())
+            .collect()
+    };
+    assert_eq!(
+        cached,
+        completed.iter().cloned().map(Some).collect::<Vec<_>>()
+    );
+
+    // the parallel verification produces the same result
+    let ((parallel_cycles, parallel_completed), _) = verify(false);
+    assert_eq!(parallel_cycles, cycles);
+    assert_eq!(parallel_completed, completed);
+}
+
+#[test]
+fn test_verify_with_trace() {
+    let (
+        _chain_controller,
+        shared,
+        genesis_tx_hash,
+        always_success_script,
+        always_success_out_point,
+    ) = setup_env();
+    let parent = shared.consensus().genesis_block().header();
+
+    let txs: Vec<TransactionView> = (1..=2)
+        .map(|index| {
+            TransactionBuilder::default()
+                .input(CellInput::new(
+                    OutPoint::new(genesis_tx_hash.clone(), index),
+                    0,
+                ))
+                .output(
+                    CellOutputBuilder::default()
+                        .capacity(capacity_bytes!(100).pack())
+                        .lock(always_success_script.clone())
+                        .build(),
+                )
+                .output_data(Bytes::new().pack())
+                .cell_dep(
+                    CellDep::new_builder()
+                        .out_point(always_success_out_point.clone())
+                        .build(),
+                )
+                .build()
+        })
+        .collect();
+    // the dao field is left empty and the cellbase has an output before the finalization
+    let block = gen_block(&parent, txs, vec![], vec![]);
+
+    let snapshot = Arc::clone(&shared.snapshot());
+    let block_cp = BlockCellProvider::new(&block).unwrap();
+    let cell_provider = OverlayCellProvider::new(&block_cp, snapshot.as_ref());
+    let mut seen_inputs = HashSet::new();
+    let resolved: Vec<Arc<ResolvedTransaction>> = block
+        .transactions()
+        .into_iter()
+        .map(|tx| {
+            Arc::new(
+                resolve_transaction(tx, &mut seen_inputs, &cell_provider, snapshot.as_ref())
+                    .unwrap(),
+            )
+        })
+        .collect();
+
+    let switch = Switch::DISABLE_EPOCH
+        | Switch::DISABLE_UNCLES
+        | Switch::DISABLE_TWO_PHASE_COMMIT
+        | Switch::DISABLE_EXTENSION;
+    let chain_root_mmr = ChainRootMMR::new(0, snapshot.as_ref());
+    let verifier = ContextualBlockVerifier::new(
+        dummy_context(&shared),
+        shared.async_handle(),
+        switch,
+        Arc::new(RwLock::new(init_cache())),
+        &chain_root_mmr,
+    );
+    let trace = verifier
+        .verify_with_trace(&resolved, &block)
+        .expect("trace block");
+
+    // a failing phase doesn't stop the following ones
+    let phases: Vec<_> = trace.phases.iter().map(|(phase, _)| *phase).collect();
+    assert_eq!(phases, vec!["dao_header", "reward", "block_txs"]);
+    assert_error_eq!(
+        trace.phases[0].1.as_ref().unwrap_err().clone(),
+        BlockErrorKind::InvalidDAO,
+    );
+    assert_error_eq!(
+        trace.phases[1].1.as_ref().unwrap_err().clone(),
+        CellbaseError::InvalidRewardTarget,
+    );
+    assert!(trace.phases[2].1.is_ok());
+
+    assert!(trace.dao.is_some());
+    assert_ne!(trace.dao, Some(block.header().dao()));
+    assert!(trace.reward.is_some());
+    assert_eq!(trace.tx_cycles.len(), 2);
+    assert!(trace.tx_cycles.iter().all(|cycles| *cycles > 0));
+    assert_eq!(trace.chain_root, None);
+}
+
+#[test]
+fn test_time_relative_cache() {
+    let (
+        _chain_controller,
+        shared,
+        genesis_tx_hash,
+        always_success_script,
+        always_success_out_point,
+    ) = setup_env();
+    let parent = shared.consensus().genesis_block().header();
+
+    // immature until block 100, only the time-relative verification rejects it
+    let tx = TransactionBuilder::default()
+        .input(CellInput::new(OutPoint::new(genesis_tx_hash, 1), 100))
+        .output(
+            CellOutputBuilder::default()
+                .capacity(capacity_bytes!(100).pack())
+                .lock(always_success_script)
+                .build(),
+        )
+        .output_data(Bytes::new().pack())
+        .cell_dep(
+            CellDep::new_builder()
+                .out_point(always_success_out_point)
+                .build(),
+        )
+        .build();
+    let wtx_hash = tx.witness_hash();
+    let block = gen_block(&parent, vec![tx], vec![], vec![]);
+
+    let snapshot = Arc::clone(&shared.snapshot());
+    let block_cp = BlockCellProvider::new(&block).unwrap();
+    let cell_provider = OverlayCellProvider::new(&block_cp, snapshot.as_ref());
+    let mut seen_inputs = HashSet::new();
+    let resolved: Vec<Arc<ResolvedTransaction>> = block
+        .transactions()
+        .into_iter()
+        .map(|tx| {
+            Arc::new(
+                resolve_transaction(tx, &mut seen_inputs, &cell_provider, snapshot.as_ref())
+                    .unwrap(),
+            )
+        })
+        .collect();
+
+    let completed = Completed {
+        cycles: 1000,
+        fee: Capacity::zero(),
+    };
+    let switch = Switch::DISABLE_EPOCH
+        | Switch::DISABLE_UNCLES
+        | Switch::DISABLE_TWO_PHASE_COMMIT
+        | Switch::DISABLE_DAOHEADER
+        | Switch::DISABLE_REWARD
+        | Switch::DISABLE_EXTENSION;
+    let chain_root_mmr = ChainRootMMR::new(0, snapshot.as_ref());
+    let verify = |switch: Switch, time_relative_cache: Arc<TimeRelativeCache>| {
+        let mut txs_verify_cache = init_cache();
+        txs_verify_cache.put(wtx_hash.clone(), completed);
+        ContextualBlockVerifier::new(
+            dummy_context(&shared),
+            shared.async_handle(),
+            switch,
+            Arc::new(RwLock::new(txs_verify_cache)),
+            &chain_root_mmr,
+        )
+        .deterministic(true)
+        .time_relative_cache(time_relative_cache)
+        .verify(&resolved, &block)
+    };
+    let immature = || BlockTransactionsError {
+        index: 1,
+        error: TransactionError::Immature { index: 0 }.into(),
+    };
+
+    // as if the tx passed the time-relative verification when the block was verified before
+    let verified = Arc::new(TimeRelativeCache::default());
+    verified.insert(&block.hash(), std::iter::once(wtx_hash.clone()));
+
+    // the cached cycles are reused without the recheck
+    assert_eq!(
+        verify(switch | Switch::DISABLE_SCRIPT, Arc::clone(&verified)).unwrap(),
+        (1000, vec![completed])
+    );
+    // still rechecked when the scripts are verified
+    assert_error_eq!(
+        verify(switch, Arc::clone(&verified)).unwrap_err(),
+        immature(),
+    );
+    // still rechecked when the tx never passed in this block
+    assert_error_eq!(
+        verify(switch | Switch::DISABLE_SCRIPT, Default::default()).unwrap_err(),
+        immature(),
+    );
+}
+
+#[test]
+fn test_parallel_dao_and_reward_verification() {
+    let (
+        _chain_controller,
+        shared,
+        genesis_tx_hash,
+        always_success_script,
+        always_success_out_point,
+    ) = setup_env();
+    let parent = shared.consensus().genesis_block().header();
+
+    let txs: Vec<TransactionView> = (1..=2)
+        .map(|index| {
+            TransactionBuilder::default()
+                .input(CellInput::new(
+                    OutPoint::new(genesis_tx_hash.clone(), index),
+                    0,
+                ))
+                .output(
+                    CellOutputBuilder::default()
+                        .capacity(capacity_bytes!(100).pack())
+                        .lock(always_success_script.clone())
+                        .build(),
+                )
+                .output_data(Bytes::new().pack())
+                .cell_dep(
+                    CellDep::new_builder()
+                        .out_point(always_success_out_point.clone())
+                        .build(),
+                )
+                .build()
+        })
+        .collect();
+    // the dao field is left empty and the cellbase has an output before the finalization
+    let block = gen_block(&parent, txs, vec![], vec![]);
+
+    let snapshot = Arc::clone(&shared.snapshot());
+    let block_cp = BlockCellProvider::new(&block).unwrap();
+    let cell_provider = OverlayCellProvider::new(&block_cp, snapshot.as_ref());
+    let mut seen_inputs = HashSet::new();
+    let resolved: Vec<Arc<ResolvedTransaction>> = block
+        .transactions()
+        .into_iter()
+        .map(|tx| {
+            Arc::new(
+                resolve_transaction(tx, &mut seen_inputs, &cell_provider, snapshot.as_ref())
+                    .unwrap(),
+            )
+        })
+        .collect();
+
+    let chain_root_mmr = ChainRootMMR::new(0, snapshot.as_ref());
+    let verifier = |switch: Switch| {
+        ContextualBlockVerifier::new(
+            dummy_context(&shared),
+            shared.async_handle(),
+            switch,
+            Arc::new(RwLock::new(init_cache())),
+            &chain_root_mmr,
+        )
+    };
+    let base = Switch::DISABLE_EPOCH
+        | Switch::DISABLE_UNCLES
+        | Switch::DISABLE_TWO_PHASE_COMMIT
+        | Switch::DISABLE_EXTENSION;
+    let dao = verifier(base)
+        .verify_with_trace(&resolved, &block)
+        .expect("trace block")
+        .dao
+        .expect("dao field");
+    let block_with_dao = block
+        .as_advanced_builder()
+        .header(block.header().as_advanced_builder().dao(dao).build())
+        .build();
+
+    for block in [&block, &block_with_dao] {
+        for switch in [
+            base,
+            base | Switch::DISABLE_DAOHEADER,
+            base | Switch::DISABLE_REWARD,
+            base | Switch::DISABLE_DAOHEADER | Switch::DISABLE_REWARD,
+        ] {
+            let verify = |deterministic: bool| {
+                verifier(switch)
+                    .deterministic(deterministic)
+                    .verify(&resolved, block)
+                    .map(|(cycles, _)| cycles)
+                    .map_err(|err| err.to_string())
+            };
+            assert_eq!(verify(true), verify(false), "switch: {switch:?}");
+        }
+    }
+
+    // the dao error takes precedence when both phases fail
+    let err = verifier(base)
+        .verify(&resolved, &block)
+        .expect_err("invalid dao and reward");
+    assert_error_eq!(err, BlockErrorKind::InvalidDAO);
+    let err = verifier(base)
+        .verify(&resolved, &block_with_dao)
+        .expect_err("invalid reward");
+    assert_error_eq!(err, CellbaseError::InvalidRewardTarget);
+}
+
+fn genesis_chain_root(shared: &Shared) -> Byte32 {
+    let snapshot = shared.snapshot();
+    let chain_root_mmr = snapshot.chain_root_mmr(0);
+    chain_root_mmr.get_root().unwrap().calc_mmr_hash()
+}
+
+fn gen_block_with_extension(shared: &Shared, extension: Vec<u8>) -> BlockView {
+    let genesis = shared
+        .store()
+        .get_block_header(&shared.store().get_block_hash(0).unwrap())
+        .unwrap();
+    gen_block(&genesis, vec![], vec![], vec![])
+        .as_advanced_builder()
+        .extension(Some(Bytes::from(extension).pack()))
+        .build()
+}
+
+// Verify the extension of a child of genesis
+fn verify_block_extension_of(
+    shared: &Shared,
+    consensus: Consensus,
+    block: &BlockView,
+) -> Result<(), Error> {
+    let snapshot = shared.snapshot();
+    let genesis = snapshot
+        .get_block_header(&snapshot.get_block_hash(0).unwrap())
+        .unwrap();
+    let chain_root_mmr = snapshot.chain_root_mmr(0);
+    let context = VerifyContext::new(Arc::new(shared.store().clone()), Arc::new(consensus));
+    BlockExtensionVerifier::new(&context, &chain_root_mmr, &genesis).verify(block)
+}
+
+// Verify a child of genesis carrying an extension of `len` bytes, which starts with the chain
+// root whenever there is room for it
+fn verify_block_extension(shared: &Shared, consensus: Consensus, len: usize) -> Result<(), Error> {
+    let mut extension = vec![0u8; len];
+    if len >= 32 {
+        extension[..32].copy_from_slice(genesis_chain_root(shared).as_slice());
+    }
+    let block = gen_block_with_extension(shared, extension);
+    verify_block_extension_of(shared, consensus, &block)
+}
+
+#[test]
+fn test_block_extension_size_limit() {
+    let (_chain_controller, shared, _, _, _) = setup_env();
+    let mmr_active = shared.consensus().clone();
+    assert!(mmr_active.rfc0044_active(0));
+    assert_eq!(mmr_active.max_block_extension_bytes(), 96);
+    let mmr_inactive = Consensus {
+        id: mainnet::CHAIN_SPEC_NAME.to_owned(),
+        ..mmr_active.clone()
+    };
+    assert!(!mmr_inactive.rfc0044_active(0));
+
+    // the chain root is required once rfc0044 is active
+    assert_error_eq!(
+        verify_block_extension(&shared, mmr_active.clone(), 31).unwrap_err(),
+        BlockErrorKind::InvalidBlockExtension,
+    );
+    assert!(verify_block_extension(&shared, mmr_active.clone(), 32).is_ok());
+    assert!(verify_block_extension(&shared, mmr_inactive.clone(), 1).is_ok());
+    assert_error_eq!(
+        verify_block_extension(&shared, mmr_inactive.clone(), 0).unwrap_err(),
+        BlockErrorKind::EmptyBlockExtension,
+    );
+
+    for consensus in [mmr_active, mmr_inactive] {
+        for max in [96, 128] {
+            let consensus = Consensus {
+                max_block_extension_bytes: max,
+                ..consensus.clone()
+            };
+            let max = max as usize;
+            assert!(verify_block_extension(&shared, consensus.clone(), max).is_ok());
+            assert_error_eq!(
+                verify_block_extension(&shared, consensus, max + 1).unwrap_err(),
+                BlockErrorKind::ExceededMaximumBlockExtensionBytes,
+            );
+        }
+    }
+}
+
+#[test]
+fn test_invalid_chain_root_error() {
+    let (_chain_controller, shared, _, _, _) = setup_env();
+    let chain_root = genesis_chain_root(&shared);
+    let wrong_root = Byte32::new([1u8; 32]);
+    let block = gen_block_with_extension(&shared, wrong_root.as_slice().to_vec());
+
+    let err = verify_block_extension_of(&shared, shared.consensus().clone(), &block).unwrap_err();
+    let block_error = err.downcast_ref::<BlockError>().unwrap();
+    assert_eq!(block_error.kind(), BlockErrorKind::InvalidChainRoot);
+    assert_eq!(
+        block_error.downcast_ref::<InvalidChainRootError>(),
+        Some(&InvalidChainRootError {
+            block_number: 1,
+            block_hash: block.hash(),
+            expected: wrong_root.clone(),
+            actual: chain_root.clone(),
+        })
+    );
+    let message = err.to_string();
+    assert!(message.contains(&wrong_root.to_string()));
+    assert!(message.contains(&chain_root.to_string()));
+}
+
+#[test]
+fn test_disable_extension_verification() {
+    let (_chain_controller, shared, _, _, _) = setup_env();
+    // the extension is expected to start with the chain root
+    let block = gen_block_with_extension(&shared, vec![1u8; 32]);
+
+    let snapshot = Arc::clone(&shared.snapshot());
+    let block_cp = BlockCellProvider::new(&block).unwrap();
+    let cell_provider = OverlayCellProvider::new(&block_cp, snapshot.as_ref());
+    let resolved: Vec<Arc<ResolvedTransaction>> = block
+        .transactions()
+        .into_iter()
+        .map(|tx| {
+            Arc::new(
+                resolve_transaction(tx, &mut HashSet::new(), &cell_provider, snapshot.as_ref())
+                    .unwrap(),
+            )
+        })
+        .collect();
+
+    let chain_root_mmr = snapshot.chain_root_mmr(0);
+    let verify = |switch: Switch| {
+        ContextualBlockVerifier::new(
+            dummy_context(&shared),
+            shared.async_handle(),
+            switch,
+            Arc::new(RwLock::new(init_cache())),
+            &chain_root_mmr,
+        )
+        .verify(&resolved, &block)
+    };
+
+    let others = Switch::DISABLE_ALL - Switch::DISABLE_EXTENSION;
+    let err = verify(others).unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<BlockError>().map(BlockError::kind),
+        Some(BlockErrorKind::InvalidChainRoot)
+    );
+    assert!(verify(others | Switch::DISABLE_EXTENSION).is_ok());
+    assert!(verify(Switch::DISABLE_ALL).is_ok());
+}
+
+fn resolve_block(shared: &Shared, block: &BlockView) -> Vec<Arc<ResolvedTransaction>> {
+    let snapshot = shared.snapshot();
+    let block_cp = BlockCellProvider::new(block).unwrap();
+    let cell_provider = OverlayCellProvider::new(&block_cp, snapshot.as_ref());
+    let mut seen_inputs = HashSet::new();
+    block
+        .transactions()
+        .into_iter()
+        .map(|tx| {
+            Arc::new(
+                resolve_transaction(tx, &mut seen_inputs, &cell_provider, snapshot.as_ref())
+                    .unwrap(),
+            )
+        })
+        .collect()
+}
+
+// A child of `parent` which passes every contextual verifier
+fn gen_valid_block(
+    shared: &Shared,
+    parent: &HeaderView,
+    transactions: Vec<TransactionView>,
+    proposals: Vec<ProposalShortId>,
+) -> BlockView {
+    let snapshot = shared.snapshot();
+    let number = parent.number() + 1;
+    let epoch = shared
+        .consensus()
+        .next_epoch_ext(parent, &snapshot.borrow_as_data_loader())
+        .unwrap()
+        .epoch();
+    // no reward before the finalization target
+    let cellbase = TransactionBuilder::default()
+        .input(CellInput::new_cellbase_input(number))
+        .build();
+    let block = BlockBuilder::default()
+        .parent_hash(parent.hash())
+        .number(number.pack())
+        .timestamp((parent.timestamp() + 1).pack())
+        .epoch(epoch.number_with_fraction(number).pack())
+        .compact_target(epoch.compact_target().pack())
+        .transaction(cellbase)
+        .transactions(transactions)
+        .proposals(proposals)
+        .build();
+
+    let resolved = resolve_block(shared, &block);
+    let dao = DaoCalculator::new(shared.consensus(), &snapshot.borrow_as_data_loader())
+        .dao_field(resolved.iter().map(AsRef::as_ref), parent)
+        .unwrap();
+    let chain_root = snapshot
+        .chain_root_mmr(parent.number())
+        .get_root()
+        .unwrap()
+        .calc_mmr_hash();
+    block
+        .as_advanced_builder()
+        .dao(dao)
+        .extension(Some(chain_root.as_bytes().pack()))
+        .build()
+}
+
+#[test]
+fn test_verifier_metrics() {
+    const VERIFIERS: [&str; 7] = [
+        "epoch",
+        "uncles",
+        "two_phase_commit",
+        "dao_header",
+        "reward",
+        "extension",
+        "block_txs",
+    ];
+    let metrics = ckb_metrics::testing::enable();
+    let (
+        chain_controller,
+        shared,
+        genesis_tx_hash,
+        always_success_script,
+        always_success_out_point,
+    ) = setup_env();
+    let tx = TransactionBuilder::default()
+        .input(CellInput::new(OutPoint::new(genesis_tx_hash, 1), 0))
+        .output(
+            CellOutputBuilder::default()
+                .capacity(capacity_bytes!(100).pack())
+                .lock(always_success_script)
+                .build(),
+        )
+        .output_data(Bytes::new().pack())
+        .cell_dep(
+            CellDep::new_builder()
+                .out_point(always_success_out_point)
+                .build(),
+        )
+        .build();
+
+    // propose the transaction, then wait for the proposal window to commit it
+    let mut parent = shared.consensus().genesis_block().header();
+    for number in 1..=shared.consensus().tx_proposal_window().closest() {
+        let proposals = if number == 1 {
+            vec![tx.proposal_short_id()]
+        } else {
+            vec![]
+        };
+        let block = gen_valid_block(&shared, &parent, vec![], proposals);
+        chain_controller
+            .blocking_process_block_with_switch(Arc::new(block.clone()), Switch::DISABLE_ALL)
+            .unwrap();
+        parent = block.header();
+    }
+    let block = gen_valid_block(&shared, &parent, vec![tx], vec![]);
+    let resolved = resolve_block(&shared, &block);
+
+    // other tests of the process may verify blocks too
+    let samples = || {
+        VERIFIERS.map(|verifier| {
+            metrics
+                .ckb_chain_contextual_verifier_duration
+                .with_label_values(&[verifier])
+                .get_sample_count()
+        })
+    };
+    let before = samples();
+
+    let snapshot = shared.snapshot();
+    let chain_root_mmr = snapshot.chain_root_mmr(parent.number());
+    let verifier = ContextualBlockVerifier::new(
+        dummy_context(&shared),
+        shared.async_handle(),
+        Switch::NONE,
+        Arc::new(RwLock::new(init_cache())),
+        &chain_root_mmr,
+    );
+    let (cycles, completed) = verifier.verify(&resolved, &block).expect("verify block");
+    assert!(cycles > 0);
+    assert_eq!(completed.len(), 1);
+
+    for ((verifier, before), after) in VERIFIERS.iter().zip(before).zip(samples()) {
+        assert!(after > before, "no sample of {verifier}");
+    }
+}
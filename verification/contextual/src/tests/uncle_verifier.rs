@@ -1,7 +1,9 @@
 #![allow(clippy::field_reassign_with_default)]
 
+use super::contextual_block_verifier::CountingStore;
 use crate::contextual_block_verifier::{UncleVerifierContext, VerifyContext};
 use crate::uncles_verifier::UnclesVerifier;
+use crate::UncleInclusionCache;
 use ckb_chain::{start_chain_services, ChainController};
 use ckb_chain_spec::consensus::Consensus;
 use ckb_error::assert_error_eq;
@@ -18,6 +20,7 @@ use ckb_types::{
 use ckb_verification::UnclesError;
 use ckb_verification_traits::Switch;
 use rand::random;
+use std::sync::atomic::AtomicUsize;
 use std::sync::Arc;
 
 fn gen_block(parent_header: &HeaderView, nonce: u128, epoch: &EpochExt) -> BlockView {
@@ -524,3 +527,79 @@ fn test_uncle_with_uncle_descendant() {
         assert!(verifier.verify().is_ok());
     }
 }
+
+#[test]
+fn test_uncle_inclusion_cache() {
+    let (shared, chain1, chain2) = prepare();
+    let store = Arc::new(CountingStore {
+        inner: shared.store().clone(),
+        reads: AtomicUsize::new(0),
+    });
+    let context = VerifyContext::new(Arc::clone(&store), shared.cloned_consensus());
+
+    // sibling uncles sharing the same parent
+    let uncles: Vec<_> = (0..2u128)
+        .map(|i| {
+            chain2[16]
+                .clone()
+                .as_advanced_builder()
+                .parent_hash(chain1[15].hash())
+                .nonce(i.pack())
+                .build()
+                .as_uncle()
+        })
+        .collect();
+    let blocks: Vec<_> = [
+        uncles.clone(),
+        vec![uncles[0].clone(), uncles[0].clone()],
+        vec![chain1[16].clone().as_uncle()],
+    ]
+    .into_iter()
+    .map(|uncles| {
+        chain1[18]
+            .clone()
+            .as_advanced_builder()
+            .set_uncles(uncles)
+            .build()
+    })
+    .collect();
+
+    let epoch = epoch(&shared, &chain1, 17);
+    let verify_all = |cache: Option<&UncleInclusionCache>| -> Vec<_> {
+        blocks
+            .iter()
+            .map(|block| {
+                let uncle_verifier_context = match cache {
+                    Some(cache) => UncleVerifierContext::with_cache(&context, &epoch, cache),
+                    None => UncleVerifierContext::new(&context, &epoch),
+                };
+                UnclesVerifier::new(uncle_verifier_context, block)
+                    .verify()
+                    .map_err(|err| err.to_string())
+            })
+            .collect()
+    };
+
+    store.take_reads();
+    let uncached = verify_all(None);
+    let uncached_reads = store.take_reads();
+
+    let cache = UncleInclusionCache::default();
+    let cached = verify_all(Some(&cache));
+    store.take_reads();
+    let recached = verify_all(Some(&cache));
+    let recached_reads = store.take_reads();
+
+    assert_eq!(uncached, cached);
+    assert_eq!(uncached, recached);
+    assert!(cached[0].is_ok());
+    assert!(cached[1].is_err());
+    assert!(cached[2].is_err());
+    // only the main chain blocks are cached, the uncle parent and the uncle included as a block
+    assert_eq!(cache.len(), 2);
+    // a cached block still costs a main chain check, instead of the whole lookup
+    assert!(
+        recached_reads < uncached_reads,
+        "recached reads {recached_reads} should be fewer than uncached reads {uncached_reads}"
+    );
+}
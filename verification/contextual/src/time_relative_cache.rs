@@ -0,0 +1,58 @@
+//! Remember the transactions passing the time-relative verification in a block.
+use ckb_types::packed::Byte32;
+use ckb_util::Mutex;
+use lru::LruCache;
+
+// the same as the size of the tx verification cache
+const DEFAULT_CAPACITY: usize = 1000 * 30;
+
+/// An LRU cache of the transactions which passed the time-relative verification when committed
+/// in a block, it saves the recheck when the same block is verified again, e.g. re-applied
+/// during a reorg.
+///
+/// The time-relative verification depends on nothing but the transaction and the commit
+/// environment derived from the block header, so the entries are keyed by the header hash
+/// along with the transaction witness hash. The same transaction in another block never
+/// matches an entry.
+pub struct TimeRelativeCache {
+    inner: Mutex<LruCache<(Byte32, Byte32), ()>>,
+}
+
+impl Default for TimeRelativeCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl TimeRelativeCache {
+    /// Create a cache holding at most `capacity` transactions
+    pub fn new(capacity: usize) -> Self {
+        TimeRelativeCache {
+            inner: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    /// The number of transactions cached
+    pub fn len(&self) -> usize {
+        self.inner.lock().len()
+    }
+
+    /// Returns true if no transaction is cached
+    pub fn is_empty(&self) -> bool {
+        self.inner.lock().is_empty()
+    }
+
+    pub(crate) fn contains(&self, header_hash: &Byte32, wtx_hash: &Byte32) -> bool {
+        self.inner
+            .lock()
+            .get(&(header_hash.clone(), wtx_hash.clone()))
+            .is_some()
+    }
+
+    pub(crate) fn insert(&self, header_hash: &Byte32, wtx_hashes: impl Iterator<Item = Byte32>) {
+        let mut guard = self.inner.lock();
+        for wtx_hash in wtx_hashes {
+            guard.put((header_hash.clone(), wtx_hash), ());
+        }
+    }
+}
@@ -0,0 +1,96 @@
+//! Memoize the uncle inclusion lookups of the uncles verification.
+use ckb_store::ChainStore;
+use ckb_types::{core::BlockNumber, packed::Byte32};
+use ckb_util::Mutex;
+use lru::LruCache;
+
+// enough for the uncles and their parents of a few sibling blocks
+const DEFAULT_CAPACITY: usize = 256;
+
+/// Where a block hash is included in the chain ending at a given block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Inclusion {
+    /// A block of the chain, with the number of its header
+    Block(Option<BlockNumber>),
+    /// An uncle embedded in the chain, with its number
+    Uncle(BlockNumber),
+    /// Neither a block nor an uncle of the chain
+    Absent,
+}
+
+impl Inclusion {
+    pub(crate) fn is_included(&self) -> bool {
+        !matches!(self, Inclusion::Absent)
+    }
+
+    pub(crate) fn number(&self) -> Option<BlockNumber> {
+        match self {
+            Inclusion::Block(number) => *number,
+            Inclusion::Uncle(number) => Some(*number),
+            Inclusion::Absent => None,
+        }
+    }
+}
+
+/// An LRU cache of the main chain blocks found by the uncle inclusion lookups, shared by the
+/// verification of consecutive blocks, whose uncle parents are mostly the same.
+///
+/// The entries are keyed by the block hash along with its number. A cached block is checked
+/// against the main chain by its number before use, so an entry rolled back by a reorg is
+/// loaded again. Whether a hash is an uncle of the chain or not included at all changes with
+/// every block, such lookups are always read from the store.
+pub struct UncleInclusionCache {
+    inner: Mutex<LruCache<Byte32, BlockNumber>>,
+}
+
+impl Default for UncleInclusionCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl UncleInclusionCache {
+    /// Create a cache holding at most `capacity` lookups
+    pub fn new(capacity: usize) -> Self {
+        UncleInclusionCache {
+            inner: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    /// The number of lookups cached
+    pub fn len(&self) -> usize {
+        self.inner.lock().len()
+    }
+
+    /// Returns true if no lookup is cached
+    pub fn is_empty(&self) -> bool {
+        self.inner.lock().is_empty()
+    }
+
+    pub(crate) fn get_or_load<CS: ChainStore>(&self, store: &CS, hash: &Byte32) -> Inclusion {
+        let cached = self.inner.lock().get(hash).copied();
+        if let Some(number) = cached {
+            if store.get_block_hash(number).as_ref() == Some(hash) {
+                return Inclusion::Block(Some(number));
+            }
+            // not in the main chain of `store` anymore
+            self.inner.lock().pop(hash);
+        }
+        let inclusion = load_inclusion(store, hash);
+        if let Inclusion::Block(Some(number)) = inclusion {
+            self.inner.lock().put(hash.clone(), number);
+        }
+        inclusion
+    }
+}
+
+/// Read where `hash` is included in the main chain from the store
+pub(crate) fn load_inclusion<CS: ChainStore>(store: &CS, hash: &Byte32) -> Inclusion {
+    if store.get_block_number(hash).is_some() {
+        return Inclusion::Block(store.get_block_header(hash).map(|header| header.number()));
+    }
+    match store.get_uncle_header(hash) {
+        Some(header) => Inclusion::Uncle(header.number()),
+        None => Inclusion::Absent,
+    }
+}
@@ -1,5 +1,6 @@
 use crate::{
     transaction_verifier::NonContextualTransactionVerifier, BlockErrorKind, CellbaseError,
+    UnclesError,
 };
 use ckb_chain_spec::consensus::Consensus;
 use ckb_error::Error;
@@ -18,17 +19,37 @@ use std::collections::HashSet;
 /// - [`BlockBytesVerifier`](./struct.BlockBytesVerifier.html)
 /// - [`BlockExtensionVerifier`](./struct.BlockExtensionVerifier.html)
 /// - [`BlockProposalsLimitVerifier`](./struct.BlockProposalsLimitVerifier.html)
+/// - [`BlockUnclesLimitVerifier`](./struct.BlockUnclesLimitVerifier.html)
 /// - [`DuplicateVerifier`](./struct.DuplicateVerifier.html)
 /// - [`MerkleRootVerifier`](./struct.MerkleRootVerifier.html)
 #[derive(Clone)]
 pub struct BlockVerifier<'a> {
     consensus: &'a Consensus,
+    max_block_bytes: Option<u64>,
 }
 
 impl<'a> BlockVerifier<'a> {
     /// Constructs a BlockVerifier
     pub fn new(consensus: &'a Consensus) -> Self {
-        BlockVerifier { consensus }
+        BlockVerifier {
+            consensus,
+            max_block_bytes: None,
+        }
+    }
+
+    /// Verify the block against a reduced block size limit, useful for testing the size limit
+    /// enforcement without a genuinely huge block.
+    ///
+    /// The override can only tighten the limit, the consensus `max_block_bytes` is used if it is smaller.
+    pub fn with_max_block_bytes(mut self, max_block_bytes: u64) -> Self {
+        self.max_block_bytes = Some(max_block_bytes);
+        self
+    }
+
+    fn max_block_bytes(&self) -> u64 {
+        let consensus_limit = self.consensus.max_block_bytes();
+        self.max_block_bytes
+            .map_or(consensus_limit, |limit| limit.min(consensus_limit))
     }
 }
 
@@ -37,7 +58,8 @@ impl<'a> Verifier for BlockVerifier<'a> {
 
     fn verify(&self, target: &BlockView) -> Result<(), Error> {
         let max_block_proposals_limit = self.consensus.max_block_proposals_limit();
-        let max_block_bytes = self.consensus.max_block_bytes();
+        let max_block_bytes = self.max_block_bytes();
+        BlockUnclesLimitVerifier::new(self.consensus.max_uncles_num() as u32).verify(target)?;
         BlockProposalsLimitVerifier::new(max_block_proposals_limit).verify(target)?;
         BlockBytesVerifier::new(max_block_bytes).verify(target)?;
         CellbaseVerifier::new().verify(target)?;
@@ -218,6 +240,34 @@ impl BlockProposalsLimitVerifier {
     }
 }
 
+/// BlockUnclesLimitVerifier.
+///
+/// Check block uncles count limit, so that a block with an impossible uncles count is
+/// rejected before the per-uncle store lookups of the contextual `UnclesVerifier`.
+#[derive(Clone)]
+pub struct BlockUnclesLimitVerifier {
+    max_uncles_num: u32,
+}
+
+impl BlockUnclesLimitVerifier {
+    pub fn new(max_uncles_num: u32) -> Self {
+        BlockUnclesLimitVerifier { max_uncles_num }
+    }
+
+    pub fn verify(&self, block: &BlockView) -> Result<(), Error> {
+        let uncles_count = block.data().uncles().len() as u32;
+        if uncles_count <= self.max_uncles_num {
+            Ok(())
+        } else {
+            Err(UnclesError::OverCount {
+                max: self.max_uncles_num,
+                actual: uncles_count,
+            }
+            .into())
+        }
+    }
+}
+
 /// BlockBytesVerifier.
 ///
 /// Check block size limit.
@@ -1,7 +1,7 @@
 use crate::error::{
     BlockError, BlockErrorKind, BlockTransactionsError, BlockVersionError, CellbaseError,
-    CommitError, EpochError, HeaderError, HeaderErrorKind, InvalidParentError, NumberError,
-    PowError, TimestampError, UnclesError, UnknownParentError,
+    CommitError, EpochError, HeaderError, HeaderErrorKind, InvalidChainRootError,
+    InvalidParentError, NumberError, PowError, TimestampError, UnclesError, UnknownParentError,
 };
 use ckb_error::{
     impl_error_conversion_with_adaptor, impl_error_conversion_with_kind, Error, ErrorKind,
@@ -34,6 +34,11 @@ impl_error_conversion_with_kind!(
 impl_error_conversion_with_kind!(CommitError, BlockErrorKind::Commit, BlockError);
 impl_error_conversion_with_kind!(CellbaseError, BlockErrorKind::Cellbase, BlockError);
 impl_error_conversion_with_kind!(UnclesError, BlockErrorKind::Uncles, BlockError);
+impl_error_conversion_with_kind!(
+    InvalidChainRootError,
+    BlockErrorKind::InvalidChainRoot,
+    BlockError
+);
 
 impl_error_conversion_with_adaptor!(InvalidParentError, HeaderError, Error);
 impl_error_conversion_with_adaptor!(BlockVersionError, HeaderError, Error);
@@ -49,3 +54,4 @@ impl_error_conversion_with_adaptor!(UnknownParentError, BlockError, Error);
 impl_error_conversion_with_adaptor!(CommitError, BlockError, Error);
 impl_error_conversion_with_adaptor!(CellbaseError, BlockError, Error);
 impl_error_conversion_with_adaptor!(UnclesError, BlockError, Error);
+impl_error_conversion_with_adaptor!(InvalidChainRootError, BlockError, Error);
@@ -1,5 +1,11 @@
-use ckb_error::{def_error_base_on_kind, prelude::*, Error};
-use ckb_types::{core::Version, packed::Byte32};
+use ckb_error::{def_error_base_on_kind, prelude::*, Error, ErrorKind};
+use ckb_types::{
+    core::{
+        service::{BlockRejection, VerificationPhase},
+        BlockNumber, BlockView, Version,
+    },
+    packed::Byte32,
+};
 use derive_more::Display;
 
 pub use ckb_types::core::{
@@ -126,7 +132,11 @@ pub enum BlockErrorKind {
     /// The calculated extra-hash does not match with the one in the header.
     InvalidExtraHash,
 
-    /// The calculated hash of chain root does not match with the one in the header.
+    /// The calculated hash of chain root does not match with the one in the block extension.
+    ///
+    /// The underlying error is [`InvalidChainRootError`].
+    ///
+    /// [`InvalidChainRootError`]: ../ckb_verification/struct.InvalidChainRootError.html
     InvalidChainRoot,
 }
 
@@ -154,6 +164,22 @@ pub struct UnknownParentError {
     pub parent_hash: Byte32,
 }
 
+/// The chain root committed by the block extension does not match with the calculated one.
+#[derive(Error, Debug, PartialEq, Eq, Clone)]
+#[error(
+    "InvalidChainRootError(block_number: {block_number}, block_hash: {block_hash}, expected: {expected}, actual: {actual})"
+)]
+pub struct InvalidChainRootError {
+    /// The number of the block.
+    pub block_number: BlockNumber,
+    /// The hash of the block.
+    pub block_hash: Byte32,
+    /// The chain root hash in the block extension.
+    pub expected: Byte32,
+    /// The calculated chain root hash.
+    pub actual: Byte32,
+}
+
 /// Errors due to the fact that the 2pc rule is not respected.
 ///
 /// See also [Two-Step Transaction Confirmation](https://github.com/nervosnetwork/rfcs/blob/master/rfcs/0020-ckb-consensus-protocol/0020-ckb-consensus-protocol.md#two-step-transaction-confirmation)
@@ -379,3 +405,41 @@ impl HeaderError {
             .unwrap_or(false)
     }
 }
+
+/// Convert the error which rejects `block` into a structured record, for rejection analytics.
+pub fn block_rejection(
+    block: &BlockView,
+    phase: VerificationPhase,
+    error: &Error,
+) -> BlockRejection {
+    let mut error_sub_kind = None;
+    let mut tx_index = None;
+    let mut tx_error_kind = None;
+    match error.kind() {
+        ErrorKind::Header => {
+            error_sub_kind = error
+                .downcast_ref::<HeaderError>()
+                .map(|e| e.kind().to_string());
+        }
+        ErrorKind::Block => {
+            if let Some(block_error) = error.downcast_ref::<BlockError>() {
+                error_sub_kind = Some(block_error.kind().to_string());
+                if let Some(txs_error) = block_error.downcast_ref::<BlockTransactionsError>() {
+                    tx_index = Some(txs_error.index);
+                    tx_error_kind = Some(txs_error.error.kind().to_string());
+                }
+            }
+        }
+        _ => {}
+    }
+    BlockRejection {
+        number: block.number(),
+        hash: block.hash(),
+        phase,
+        error_kind: error.kind().to_string(),
+        error_sub_kind,
+        tx_index,
+        tx_error_kind,
+        message: error.to_string(),
+    }
+}
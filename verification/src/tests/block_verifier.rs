@@ -1,8 +1,9 @@
 use super::super::block_verifier::{
-    BlockBytesVerifier, BlockProposalsLimitVerifier, CellbaseVerifier, DuplicateVerifier,
-    MerkleRootVerifier,
+    BlockBytesVerifier, BlockProposalsLimitVerifier, BlockUnclesLimitVerifier, BlockVerifier,
+    CellbaseVerifier, DuplicateVerifier, MerkleRootVerifier,
 };
-use crate::{BlockErrorKind, CellbaseError};
+use crate::{BlockErrorKind, CellbaseError, UnclesError};
+use ckb_chain_spec::consensus::{Consensus, ConsensusBuilder};
 use ckb_error::assert_error_eq;
 use ckb_types::{
     bytes::Bytes,
@@ -14,6 +15,7 @@ use ckb_types::{
     packed::{Byte32, CellInput, CellOutputBuilder, OutPoint, ProposalShortId, Script},
     prelude::*,
 };
+use ckb_verification_traits::Verifier;
 
 use super::BuilderBaseOnBlockNumber;
 
@@ -423,6 +425,42 @@ pub fn test_max_block_bytes_verifier() {
     }
 }
 
+#[test]
+pub fn test_block_verifier_max_block_bytes_override() {
+    let block = BlockBuilder::new_with_number(MOCK_BLOCK_NUMBER)
+        .transaction(create_cellbase_transaction_with_block_number(
+            MOCK_BLOCK_NUMBER,
+        ))
+        .build();
+    let block_bytes = block.data().serialized_size_without_uncle_proposals() as u64;
+
+    let consensus = Consensus::default();
+    assert!(BlockVerifier::new(&consensus).verify(&block).is_ok());
+    assert!(BlockVerifier::new(&consensus)
+        .with_max_block_bytes(block_bytes)
+        .verify(&block)
+        .is_ok());
+    assert_error_eq!(
+        BlockVerifier::new(&consensus)
+            .with_max_block_bytes(block_bytes - 1)
+            .verify(&block)
+            .unwrap_err(),
+        BlockErrorKind::ExceededMaximumBlockBytes,
+    );
+
+    // The override never loosens the consensus limit
+    let consensus = ConsensusBuilder::default()
+        .max_block_bytes(block_bytes - 1)
+        .build();
+    assert_error_eq!(
+        BlockVerifier::new(&consensus)
+            .with_max_block_bytes(u64::MAX)
+            .verify(&block)
+            .unwrap_err(),
+        BlockErrorKind::ExceededMaximumBlockBytes,
+    );
+}
+
 #[test]
 pub fn test_max_proposals_limit_verifier() {
     let block = BlockBuilder::default()
@@ -442,3 +480,43 @@ pub fn test_max_proposals_limit_verifier() {
         );
     }
 }
+
+#[test]
+pub fn test_max_uncles_limit_verifier() {
+    let consensus = ConsensusBuilder::default().build();
+    let max_uncles_num = consensus.max_uncles_num() as u32;
+    let uncles: Vec<_> = (0..=max_uncles_num)
+        .map(|nonce| {
+            BlockBuilder::default()
+                .nonce(u128::from(nonce).pack())
+                .build()
+                .as_uncle()
+        })
+        .collect();
+    let block = BlockBuilder::default().uncles(uncles).build();
+
+    {
+        let verifier = BlockUnclesLimitVerifier::new(max_uncles_num + 1);
+        assert!(verifier.verify(&block).is_ok());
+    }
+
+    {
+        let verifier = BlockUnclesLimitVerifier::new(max_uncles_num);
+        assert_error_eq!(
+            verifier.verify(&block).unwrap_err(),
+            UnclesError::OverCount {
+                max: max_uncles_num,
+                actual: max_uncles_num + 1,
+            },
+        );
+    }
+
+    // an over-max uncles count is rejected by the non-contextual block verifier
+    assert_error_eq!(
+        BlockVerifier::new(&consensus).verify(&block).unwrap_err(),
+        UnclesError::OverCount {
+            max: max_uncles_num,
+            actual: max_uncles_num + 1,
+        },
+    );
+}
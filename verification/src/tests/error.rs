@@ -1,6 +1,15 @@
 use ckb_error::Error;
+use ckb_types::core::{
+    error::OutPointError,
+    service::{BlockRejection, VerificationPhase},
+    BlockBuilder,
+};
+use ckb_types::packed::Byte32;
 
-use crate::error::{BlockVersionError, HeaderError, HeaderErrorKind, TimestampError};
+use crate::error::{
+    block_rejection, BlockErrorKind, BlockTransactionsError, BlockVersionError, HeaderError,
+    HeaderErrorKind, TimestampError,
+};
 
 #[test]
 fn is_too_new() {
@@ -38,3 +47,42 @@ fn test_version_error_display() {
         format!("{e}")
     );
 }
+
+#[test]
+fn test_block_rejection() {
+    let block = BlockBuilder::default().build();
+
+    let err: Error = TimestampError::BlockTimeTooOld { min: 1, actual: 0 }.into();
+    assert_eq!(
+        block_rejection(&block, VerificationPhase::NonContextual, &err),
+        BlockRejection {
+            number: block.number(),
+            hash: block.hash(),
+            phase: VerificationPhase::NonContextual,
+            error_kind: "Header".to_owned(),
+            error_sub_kind: Some("Timestamp".to_owned()),
+            tx_index: None,
+            tx_error_kind: None,
+            message: err.to_string(),
+        }
+    );
+
+    let err: Error = BlockTransactionsError {
+        index: 2,
+        error: OutPointError::Dead(Default::default()).into(),
+    }
+    .into();
+    let rejection = block_rejection(&block, VerificationPhase::Contextual, &err);
+    assert_eq!(rejection.error_kind, "Block");
+    assert_eq!(
+        rejection.error_sub_kind,
+        Some("BlockTransactions".to_owned())
+    );
+    assert_eq!(rejection.tx_index, Some(2));
+    assert_eq!(rejection.tx_error_kind, Some("OutPoint".to_owned()));
+
+    let err: Error = BlockErrorKind::Commit.other(Byte32::zero()).into();
+    let rejection = block_rejection(&block, VerificationPhase::Contextual, &err);
+    assert_eq!(rejection.error_sub_kind, Some("Commit".to_owned()));
+    assert_eq!(rejection.tx_index, None);
+}